@@ -0,0 +1,579 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    camera::{Camera, CameraBuilderError, Capture, RenderObserver},
+    object::{Object, ObjectMaterial, Texture},
+    transform::Transform,
+    vector::{Colour, Direction, Point},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How a [`Track`] fills the time between keyframes.
+pub enum Interpolation {
+    /// Straight line between each pair of keyframes.
+    #[default]
+    Linear,
+    /// Smooth curve that passes through every keyframe, using the neighbouring keyframes for the tangents.
+    CatmullRom,
+}
+
+/// Values that can be animated by a [`Track`].
+pub trait Interpolate: Clone {
+    /// Value at `t` on the line from `self` (at 0.0) to `other` (at 1.0).
+    /// `t` can be outside of [0.0..1.0] to extrapolate.
+    #[must_use]
+    fn lerp(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+macro_rules! interpolate_vector {
+    ($($type_name:ty),*) => {
+        $(
+            impl Interpolate for $type_name {
+                fn lerp(&self, other: &Self, t: f64) -> Self {
+                    *self + (*other - *self) * t
+                }
+            }
+        )*
+    };
+}
+
+interpolate_vector!(Point, Direction, Colour);
+
+impl Interpolate for Texture {
+    /// Interpolates solid colours, other textures switch halfway.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        match (self, other) {
+            (Self::Solid(lhs), Self::Solid(rhs)) => Self::Solid(lhs.lerp(rhs, t)),
+            _ if t < 0.5 => self.clone(),
+            _ => other.clone(),
+        }
+    }
+}
+
+impl Interpolate for ObjectMaterial {
+    /// Interpolates the parameters of materials of the same kind, materials of different kinds switch halfway.
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        match (self, other) {
+            (Self::Lambert { albedo: lhs }, Self::Lambert { albedo: rhs }) => Self::Lambert {
+                albedo: lhs.lerp(rhs, t),
+            },
+            (
+                Self::Metal {
+                    albedo: lhs_albedo,
+                    fuzzy_scatter: lhs_fuzzy_scatter,
+                },
+                Self::Metal {
+                    albedo: rhs_albedo,
+                    fuzzy_scatter: rhs_fuzzy_scatter,
+                },
+            ) => Self::Metal {
+                albedo: lhs_albedo.lerp(rhs_albedo, t),
+                fuzzy_scatter: lhs_fuzzy_scatter.lerp(rhs_fuzzy_scatter, t).max(0.),
+            },
+            (
+                Self::Dialectric {
+                    refraction_index: lhs,
+                },
+                Self::Dialectric {
+                    refraction_index: rhs,
+                },
+            ) => Self::Dialectric {
+                refraction_index: lhs.lerp(rhs, t),
+            },
+            (
+                Self::Hair {
+                    absorption: lhs_absorption,
+                    longitudinal_roughness: lhs_longitudinal,
+                    azimuthal_roughness: lhs_azimuthal,
+                    refraction_index: lhs_index,
+                },
+                Self::Hair {
+                    absorption: rhs_absorption,
+                    longitudinal_roughness: rhs_longitudinal,
+                    azimuthal_roughness: rhs_azimuthal,
+                    refraction_index: rhs_index,
+                },
+            ) => Self::Hair {
+                absorption: lhs_absorption.lerp(rhs_absorption, t),
+                longitudinal_roughness: lhs_longitudinal.lerp(rhs_longitudinal, t),
+                azimuthal_roughness: lhs_azimuthal.lerp(rhs_azimuthal, t),
+                refraction_index: lhs_index.lerp(rhs_index, t),
+            },
+            _ if t < 0.5 => self.clone(),
+            _ => other.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Value that changes over time, set by keyframes.</br>
+/// Before the first keyframe the value is the one of the first keyframe, and after the last keyframe the
+/// value is the one of the last keyframe.
+pub struct Track<T> {
+    keyframes: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T: Interpolate> Track<T> {
+    /// Creates a Track with a single keyframe, with linear interpolation</br>
+    /// # Parameters
+    /// `time`: Time of the keyframe, in seconds</br>
+    /// `value`: Value at `time`</br>
+    pub fn new(time: f64, value: T) -> Self {
+        Self {
+            keyframes: vec![(time, value)],
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// Adds a keyframe, replacing the keyframe at the same `time` if there is one.
+    #[must_use]
+    pub fn with_keyframe(mut self, time: f64, value: T) -> Self {
+        match self
+            .keyframes
+            .binary_search_by(|(keyframe_time, _)| keyframe_time.total_cmp(&time))
+        {
+            Ok(index) => self.keyframes[index].1 = value,
+            Err(index) => self.keyframes.insert(index, (time, value)),
+        }
+        self
+    }
+
+    /// Inputs the `interpolation` between keyframes</br>
+    /// # Note
+    /// Defaults to Linear.
+    #[must_use]
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Value of the Track at `time`, in seconds.
+    pub fn sample(&self, time: f64) -> T {
+        let next = self
+            .keyframes
+            .partition_point(|(keyframe_time, _)| *keyframe_time <= time);
+        if next == 0 {
+            return self.keyframes[0].1.clone();
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1.clone();
+        }
+
+        let (t1, p1) = &self.keyframes[next - 1];
+        let (t2, p2) = &self.keyframes[next];
+        match self.interpolation {
+            Interpolation::Linear => p1.lerp(p2, (time - t1) / (t2 - t1)),
+            Interpolation::CatmullRom => {
+                // Missing neighbours at the ends are the end keyframe repeated, one interval away
+                let (t0, p0) = next.checked_sub(2).map_or((t1 - (t2 - t1), p1), |index| {
+                    (self.keyframes[index].0, &self.keyframes[index].1)
+                });
+                let (t3, p3) = self
+                    .keyframes
+                    .get(next + 1)
+                    .map_or((t2 + (t2 - t1), p2), |(t3, p3)| (*t3, p3));
+                catmull_rom([(t0, p0), (*t1, p1), (*t2, p2), (t3, p3)], time)
+            }
+        }
+    }
+}
+
+/// Barry-Goldman evaluation of a Catmull-Rom spline, only needs `lerp` so it works for any [`Interpolate`].
+fn catmull_rom<T: Interpolate>(points: [(f64, &T); 4], time: f64) -> T {
+    let [(t0, p0), (t1, p1), (t2, p2), (t3, p3)] = points;
+    let factor = |start: f64, end: f64| (time - start) / (end - start);
+
+    let a1 = p0.lerp(p1, factor(t0, t1));
+    let a2 = p1.lerp(p2, factor(t1, t2));
+    let a3 = p2.lerp(p3, factor(t2, t3));
+    let b1 = a1.lerp(&a2, factor(t0, t2));
+    let b2 = a2.lerp(&a3, factor(t1, t3));
+    b1.lerp(&b2, factor(t1, t2))
+}
+
+#[derive(Debug, Clone)]
+enum CameraMotion {
+    Keyframed {
+        position: Track<Point>,
+        target: Track<Point>,
+    },
+    Turntable {
+        from: Point,
+        target: Point,
+        revolution_time: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+/// Moves a Camera over time, keeping the sensor and lens of the Camera.
+pub struct CameraAnimation {
+    camera: Camera,
+    motion: CameraMotion,
+    world_up: Direction,
+    focus_on_target: bool,
+}
+
+impl CameraAnimation {
+    /// Creates a CameraAnimation where the position and the target of `camera` follow Tracks</br>
+    /// # Parameters
+    /// `camera`: Camera that gives the sensor and lens of every frame</br>
+    /// `position`: Point in space where the camera is present</br>
+    /// `target`: Point in space the camera is looking at</br>
+    /// `world_up`: Direction that should appear up on the image</br>
+    pub fn keyframed(
+        camera: Camera,
+        position: Track<Point>,
+        target: Track<Point>,
+        world_up: Direction,
+    ) -> Self {
+        Self {
+            camera,
+            motion: CameraMotion::Keyframed { position, target },
+            world_up,
+            focus_on_target: false,
+        }
+    }
+
+    /// Creates a CameraAnimation that orbits `target` around `world_up`, looking at it</br>
+    /// # Parameters
+    /// `camera`: Camera that gives the sensor and lens of every frame</br>
+    /// `from`: Point in space where the camera starts, sets the radius and height of the orbit</br>
+    /// `target`: Point in space the camera orbits and looks at</br>
+    /// `world_up`: Direction that should appear up on the image, and axis of the orbit</br>
+    /// `revolution_time`: Time to complete a full orbit, in seconds</br>
+    /// Returns `None` if `revolution_time` is not positive and finite.
+    pub fn turntable(
+        camera: Camera,
+        from: Point,
+        target: Point,
+        world_up: Direction,
+        revolution_time: f64,
+    ) -> Option<Self> {
+        (revolution_time > 0. && revolution_time.is_finite()).then_some(Self {
+            camera,
+            motion: CameraMotion::Turntable {
+                from,
+                target,
+                revolution_time,
+            },
+            world_up,
+            focus_on_target: false,
+        })
+    }
+
+    /// Focuses the Camera on the target at every frame, instead of keeping the focal distance of the Camera.
+    #[must_use]
+    pub fn with_focus_on_target(mut self) -> Self {
+        self.focus_on_target = true;
+        self
+    }
+
+    /// Camera at `time`, in seconds</br>
+    /// # Errors
+    /// Fails if the position and target at `time` can not make a view, see `CameraBuilder::look_at`.
+    pub fn camera_at(&self, time: f64) -> Result<Camera, CameraBuilderError> {
+        let (from, to) = match &self.motion {
+            CameraMotion::Keyframed { position, target } => {
+                (position.sample(time), target.sample(time))
+            }
+            CameraMotion::Turntable {
+                from,
+                target,
+                revolution_time,
+            } => {
+                let angle = 360. * time / revolution_time;
+                let offset = Transform::rotation(self.world_up, angle)
+                    .apply_direction(target.point_towards(*from));
+                (*target + offset, *target)
+            }
+        };
+
+        let camera = self.camera.looking_at(from, to, self.world_up)?;
+        Ok(if self.focus_on_target {
+            camera.focused_at(from.point_towards(to).length())
+        } else {
+            camera
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Object with Tracks for its placement and material.
+pub struct AnimatedObject {
+    object: Object,
+    translation: Option<Track<Direction>>,
+    rotation: Option<Track<Direction>>,
+    scale: Option<Track<Direction>>,
+    material: Option<Track<ObjectMaterial>>,
+}
+
+impl From<Object> for AnimatedObject {
+    fn from(object: Object) -> Self {
+        Self::new(object)
+    }
+}
+
+impl AnimatedObject {
+    /// Creates an AnimatedObject that does not change until Tracks are added.
+    pub fn new(object: Object) -> Self {
+        Self {
+            object,
+            translation: None,
+            rotation: None,
+            scale: None,
+            material: None,
+        }
+    }
+
+    /// Inputs the Track of the offset applied to the Object.
+    #[must_use]
+    pub fn with_translation(mut self, translation: Track<Direction>) -> Self {
+        self.translation = Some(translation);
+        self
+    }
+
+    /// Inputs the Track of the rotation applied to the Object, in degrees around the X, Y, then Z axes
+    /// through the origin.
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Track<Direction>) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Inputs the Track of the scale applied to each axis of the Object, from the origin.
+    #[must_use]
+    pub fn with_scale(mut self, scale: Track<Direction>) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Inputs the Track of the material of the Object, replacing the material of the Object.
+    #[must_use]
+    pub fn with_material(mut self, material: Track<ObjectMaterial>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    /// Object at `time`, in seconds</br>
+    /// # Note
+    /// The Object is scaled, then rotated, then translated, after any Transform it already had.
+    pub fn object_at(&self, time: f64) -> Object {
+        let mut object = self.object.clone();
+        if self.translation.is_some() || self.rotation.is_some() || self.scale.is_some() {
+            let sample = |track: &Option<Track<Direction>>, default: Direction| {
+                track.as_ref().map_or(default, |track| track.sample(time))
+            };
+            object = object.with_transform(Transform::from_translation_rotation_scale(
+                sample(&self.translation, Direction::new(0., 0., 0.)),
+                sample(&self.rotation, Direction::new(0., 0., 0.)),
+                sample(&self.scale, Direction::new(1., 1., 1.)),
+            ));
+        }
+        if let Some(material) = &self.material {
+            object = object.with_material(material.sample(time));
+        }
+        object
+    }
+}
+
+#[derive(Debug)]
+/// Errors when rendering the frames of an Animation.
+pub enum AnimationError {
+    /// The Camera of `frame` could not be built.
+    Camera {
+        frame: usize,
+        error: CameraBuilderError,
+    },
+    /// A frame could not be written.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Camera { frame, error } => write!(f, "camera of frame {frame}: {error}"),
+            Self::Io(error) => write!(f, "failed to write frame: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AnimationError {}
+
+impl From<std::io::Error> for AnimationError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Camera and Objects that change over time, rendered as a sequence of frames.
+pub struct Animation {
+    camera: CameraAnimation,
+    objects: Vec<AnimatedObject>,
+    frame_rate: f64,
+}
+
+impl Animation {
+    /// Creates an Animation without Objects</br>
+    /// # Parameters
+    /// `camera`: Motion of the Camera</br>
+    /// `frame_rate`: Frames per second, frame `n` is at `n / frame_rate` seconds</br>
+    pub fn new(camera: CameraAnimation, frame_rate: f64) -> Self {
+        Self {
+            camera,
+            objects: Vec::new(),
+            frame_rate: frame_rate.max(f64::EPSILON),
+        }
+    }
+
+    /// Adds an Object, an [`Object`] that is not an [`AnimatedObject`] stays still.
+    #[must_use]
+    pub fn with_object(mut self, object: impl Into<AnimatedObject>) -> Self {
+        self.objects.push(object.into());
+        self
+    }
+
+    /// Time of `frame`, in seconds.
+    pub fn frame_time(&self, frame: usize) -> f64 {
+        frame as f64 / self.frame_rate
+    }
+
+    /// Camera and Objects at `time`, in seconds</br>
+    /// # Errors
+    /// Fails if the Camera can not be built at `time`.
+    pub fn scene_at(&self, time: f64) -> Result<(Camera, Vec<Object>), CameraBuilderError> {
+        Ok((
+            self.camera.camera_at(time)?,
+            self.objects
+                .iter()
+                .map(|object| object.object_at(time))
+                .collect(),
+        ))
+    }
+
+    /// Renders `frames` as PNGs named `<path_prefix>_<frame>.png`, with the frame number padded to 4 digits,
+    /// like `render_0001.png`</br>
+    /// `observer` receives the progress of each frame, and cancelling stops before the frame is written.</br>
+    /// Returns the number of frames written.
+    /// # Errors
+    /// Fails if the Camera of a frame can not be built, or if a frame can not be written.
+    pub fn render_frames(
+        &self,
+        frames: RangeInclusive<usize>,
+        path_prefix: &str,
+        observer: &mut impl RenderObserver,
+    ) -> Result<usize, AnimationError> {
+        let mut written = 0;
+        for frame in frames {
+            let (camera, scene) = self
+                .scene_at(self.frame_time(frame))
+                .map_err(|error| AnimationError::Camera { frame, error })?;
+            match camera.capture_image_with_observer(&scene, observer) {
+                Capture::Complete(image) => {
+                    let png = crate::png::image_to_png(&image);
+                    std::fs::write(format!("{path_prefix}_{frame:04}.png"), png)?;
+                    written += 1;
+                }
+                Capture::Cancelled(_) => break,
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{RenderControl, RenderProgress};
+
+    use super::*;
+
+    #[test]
+    fn track_test() {
+        let track = Track::new(0., 0.)
+            .with_keyframe(2., 4.)
+            .with_keyframe(1., 1.)
+            .with_keyframe(3., 9.);
+        assert_eq!(track.sample(-1.), 0.);
+        assert_eq!(track.sample(0.5), 0.5);
+        assert_eq!(track.sample(10.), 9.);
+
+        let track = track.with_interpolation(Interpolation::CatmullRom);
+        for (time, value) in [(0., 0.), (1., 1.), (2., 4.), (3., 9.)] {
+            assert!((track.sample(time) - value).abs() < 1e-12);
+        }
+        // Evenly spaced keyframes of a parabola are followed exactly between the inner keyframes
+        assert!((track.sample(1.5) - 2.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn turntable_test() {
+        let camera = Camera::builder()
+            .look_at(
+                Point::new(0., 0., 5.),
+                Point::new(0., 0., 0.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(4, 2, 1, 2)
+            .input_lens(2., 0., 60.)
+            .build();
+        let turntable = |revolution_time| {
+            CameraAnimation::turntable(
+                camera.clone(),
+                Point::new(0., 0., 5.),
+                Point::new(0., 0., 0.),
+                Direction::new(0., 1., 0.),
+                revolution_time,
+            )
+        };
+        for revolution_time in [0., -1., f64::NAN, f64::INFINITY] {
+            assert!(turntable(revolution_time).is_none());
+        }
+
+        // Frame 6 at 4 frames per second is a quarter of a 6 second revolution
+        let animation = Animation::new(turntable(6.).expect("Expect valid turntable"), 4.);
+        let (frame_camera, _) = animation
+            .scene_at(animation.frame_time(6))
+            .expect("Expect camera of frame");
+        let values = crate::description::describe_item(&frame_camera)
+            .split_whitespace()
+            .take(6)
+            .map(|value| value.parse::<f64>().expect("Expect number"))
+            .collect::<Vec<_>>();
+        // Counter-clockwise around up moves the camera from +Z to +X, looking back at the target
+        for (value, expected) in values.iter().zip([5., 0., 0., -1., 0., 0.]) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+
+        // Cancelling a frame stops the sequence, with only the frames before it written
+        let path_prefix = std::env::temp_dir()
+            .join(format!("rtiaw_turntable_{}", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut rows = 0;
+        let written = animation
+            .render_frames(0..=3, &path_prefix, &mut |_: &RenderProgress| {
+                rows += 1;
+                // Each frame has 2 rows, so this is the first row of frame 2
+                if rows == 5 {
+                    RenderControl::Cancel
+                } else {
+                    RenderControl::Continue
+                }
+            })
+            .expect("Expect frames written");
+        assert_eq!(written, 2);
+        assert_eq!(rows, 5);
+        for frame in 0..4 {
+            let path = format!("{path_prefix}_{frame:04}.png");
+            assert_eq!(std::path::Path::new(&path).exists(), frame < 2);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -1,25 +1,104 @@
+use std::{
+    io::Write,
+    net::{TcpListener, ToSocketAddrs},
+};
+
 use rtiaw::{
-    camera::Camera,
+    animation::{Animation, CameraAnimation},
+    camera::{Camera, RenderControl, RenderObserver, RenderProgress},
     object::{Object, ObjectMaterial},
     vector::{Colour, Direction, Point},
 };
 
 fn main() {
+    // Each of these selects how to render, and only one can apply
+    let modes = [
+        "--worker",
+        "--frames",
+        "--workers",
+        "--checkpoint",
+        "--time-budget",
+    ]
+    .into_iter()
+    .filter(|mode| std::env::args().any(|arg| arg == *mode))
+    .collect::<Vec<_>>();
+    if modes.len() > 1 {
+        eprintln!("`{}` cannot be used together.", modes.join("`, `"));
+        std::process::exit(2);
+    }
+
+    // `--worker <address>` captures tiles for coordinators connecting to `<address>` instead of rendering
+    if let Some(address) = argument_value("--worker") {
+        run_worker(&address);
+        return;
+    }
+
     let start_time = std::time::Instant::now();
 
     let scene = make_scene();
 
-    let camera_center = Point::new(13., 2., 3.);
-    let camera_forward = camera_center.point_towards(Point::new(0., 0., 0.));
-    let camera_left = Direction::new(0., 1., 0.).cross(camera_forward);
-    let camera_up = camera_forward.cross(camera_left);
-
     let camera = Camera::builder()
-        .input_position(camera_center, camera_forward, camera_up)
+        .look_at(
+            Point::new(13., 2., 3.),
+            Point::new(0., 0., 0.),
+            Direction::new(0., 1., 0.),
+        )
+        .expect("Expect valid camera position.")
         .input_sensor(1600, 900, 100, 50)
         .input_lens(10., 0.125, 20.)
         .build();
-    let image = camera.capture_image(&scene);
+    // `--frames <first>..<last>` renders a turntable around the scene to `render_<frame>.png`
+    if let Some(frames) = argument_value("--frames") {
+        render_turntable(camera, scene, &frames);
+        return;
+    }
+    // `--workers <address>,<address>` sends the scene to workers and assembles the tiles they capture
+    let workers = argument_value("--workers").map(|addresses| {
+        addresses
+            .split(',')
+            .flat_map(|address| {
+                address
+                    .to_socket_addrs()
+                    .expect("Expect worker address to be valid.")
+            })
+            .collect::<Vec<_>>()
+    });
+    // `--checkpoint <path>` saves the progress every minute, and continues from `<path>` if it exists
+    let checkpoint_path = argument_value("--checkpoint");
+    // `--time-budget <seconds>` renders for that long instead of a fixed number of samples
+    let time_budget = argument_value("--time-budget").map(|seconds| {
+        std::time::Duration::from_secs_f64(
+            seconds
+                .parse()
+                .expect("Expect time budget to be a number of seconds."),
+        )
+    });
+
+    let image = if let Some(workers) = workers {
+        rtiaw::distributed::capture_image_distributed(
+            &camera,
+            &scene,
+            &workers,
+            64,
+            std::time::Duration::from_secs(600),
+            &mut ProgressBar::new(40),
+        )
+        .expect("Expect workers to complete the render.")
+    } else if let Some(time_budget) = time_budget {
+        camera.capture_image_for(&scene, time_budget, &mut ProgressBar::new(40))
+    } else if let Some(checkpoint_path) = checkpoint_path {
+        camera
+            .capture_image_with_checkpoints(
+                &scene,
+                std::path::Path::new(&checkpoint_path),
+                std::time::Duration::from_secs(60),
+                &mut ProgressBar::new(40),
+            )
+            .expect("Expect checkpoint saved and loaded.")
+    } else {
+        camera.capture_image_with_observer(&scene, &mut ProgressBar::new(40))
+    }
+    .into_image();
 
     println!(
         "Completed rendering in {:?}. Now saving to `render.ppm`.",
@@ -29,32 +108,128 @@ fn main() {
     std::fs::write("render.ppm", ppm.as_bytes()).expect("Expect render saved to file.");
 }
 
+/// Value that follows `name` in the command line arguments.
+fn argument_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+/// Renders `frames`, given as `<first>..<last>`, of a 24 frames per second turntable that completes a
+/// revolution over the frames.
+fn render_turntable(camera: Camera, scene: Vec<Object>, frames: &str) {
+    let (first, last) = frames
+        .split_once("..")
+        .and_then(|(first, last)| Some((first.parse::<usize>().ok()?, last.parse::<usize>().ok()?)))
+        .expect("Expect frames as `<first>..<last>`.");
+    const FRAME_RATE: f64 = 24.;
+
+    let animation = scene.into_iter().fold(
+        Animation::new(
+            CameraAnimation::turntable(
+                camera,
+                Point::new(13., 2., 3.),
+                Point::new(0., 0., 0.),
+                Direction::new(0., 1., 0.),
+                (last.saturating_sub(first) + 1) as f64 / FRAME_RATE,
+            )
+            .expect("Expect at least one frame."),
+            FRAME_RATE,
+        ),
+        Animation::with_object,
+    );
+
+    let start_time = std::time::Instant::now();
+    let written = animation
+        .render_frames(first..=last, "render", &mut ProgressBar::new(40))
+        .expect("Expect frames saved to files.");
+    println!(
+        "Completed rendering {written} frames in {:?}.",
+        start_time.elapsed()
+    );
+}
+
+/// Captures tiles for each coordinator that connects to `address`, one coordinator at a time.
+fn run_worker(address: &str) {
+    let listener = TcpListener::bind(address).expect("Expect worker address available.");
+    println!("Waiting for coordinators on {address}.");
+    for stream in listener.incoming() {
+        let result = stream.and_then(rtiaw::distributed::work_for_coordinator);
+        match result {
+            Ok(()) => println!("Coordinator finished."),
+            Err(err) => eprintln!("Coordinator failed: {err}"),
+        }
+    }
+}
+
+/// Progress bar drawn on a single line of the terminal.
+struct ProgressBar {
+    width: usize,
+}
+
+impl ProgressBar {
+    fn new(width: usize) -> Self {
+        Self { width }
+    }
+}
+
+impl RenderObserver for ProgressBar {
+    fn on_progress(&mut self, progress: &RenderProgress) -> RenderControl {
+        let filled = ((progress.fraction() * self.width as f64).round() as usize).min(self.width);
+        let eta = progress
+            .estimated_remaining()
+            .map_or("--".to_owned(), |eta| format!("{}s", eta.as_secs()));
+
+        // Progress is only informative, a closed terminal must not stop the render
+        let mut stderr = std::io::stderr();
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {:>3.0}% ETA {eta:<8}",
+            "#".repeat(filled),
+            " ".repeat(self.width - filled),
+            progress.fraction() * 100.
+        )
+        .and_then(|_| {
+            if progress.rows_completed >= progress.total_rows {
+                writeln!(stderr)
+            } else {
+                stderr.flush()
+            }
+        });
+
+        RenderControl::Continue
+    }
+}
+
 fn make_scene() -> Vec<Object> {
+    // The scene must be the same on every run to continue from a checkpoint
+    rtiaw::random::seed(42);
+
     let mut scene = vec![Object::new_sphere(
         Point::new(0., -1000., 0.),
         1000.,
         rtiaw::object::ObjectMaterial::Lambert {
-            albedo: Colour::new(0.5, 0.5, 0.5),
+            albedo: Colour::new(0.5, 0.5, 0.5).into(),
         },
     )];
 
     for a in -11..11 {
         for b in -11..11 {
             let center = Point::new(
-                a as f64 + 0.9 * rand::random::<f64>(),
+                a as f64 + 0.9 * rtiaw::random::random::<f64>(),
                 0.2,
-                b as f64 + 0.9 * rand::random::<f64>(),
+                b as f64 + 0.9 * rtiaw::random::random::<f64>(),
             );
 
             if center.point_towards(Point::new(4., 0.2, 0.)).length() > 0.9 {
-                let material = match rand::random::<f64>() {
+                let material = match rtiaw::random::random::<f64>() {
                     a if (0.0..0.85).contains(&a) => {
                         let albedo = Colour::new_random() * Colour::new_random();
-                        rtiaw::object::ObjectMaterial::Lambert { albedo }
+                        rtiaw::object::ObjectMaterial::Lambert {
+                            albedo: albedo.into(),
+                        }
                     }
                     a if (0.85..0.9).contains(&a) => {
                         let albedo = (Colour::new_random() / 2.) + 0.5;
-                        let fuzzy_scatter = rand::random::<f64>() / 2.;
+                        let fuzzy_scatter = rtiaw::random::random::<f64>() / 2.;
                         ObjectMaterial::Metal {
                             albedo,
                             fuzzy_scatter,
@@ -80,7 +255,7 @@ fn make_scene() -> Vec<Object> {
         Point::new(-4., 1., 0.),
         1.,
         ObjectMaterial::Lambert {
-            albedo: Colour::new(0.4, 0.2, 0.1),
+            albedo: Colour::new(0.4, 0.2, 0.1).into(),
         },
     ));
     scene.push(Object::new_sphere(
@@ -0,0 +1,107 @@
+use std::io::{Read, Write};
+
+use crate::{vector::Colour, Image};
+
+/// Bytes written by `write_to` for each pixel, three channels, the weight, and the number of samples.
+pub(super) const PIXEL_LENGTH: u64 = 40;
+
+#[derive(Clone)]
+/// Buffer where the weighted samples of each pixel are summed before being resolved into an [`Image`].
+pub struct AccumulationBuffer {
+    colours: Box<[Colour]>,
+    weights: Box<[f64]>,
+    sample_counts: Box<[usize]>,
+    width: usize,
+    height: usize,
+}
+
+impl AccumulationBuffer {
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            colours: vec![Colour::default(); width.saturating_mul(height)].into_boxed_slice(),
+            weights: vec![0.; width.saturating_mul(height)].into_boxed_slice(),
+            sample_counts: vec![0; width.saturating_mul(height)].into_boxed_slice(),
+            width,
+            height,
+        }
+    }
+
+    pub fn add_sample(&mut self, index: usize, colour: Colour, weight: f64) {
+        self.colours[index] += colour * weight;
+        self.weights[index] += weight;
+        self.sample_counts[index] += 1;
+    }
+
+    /// Number of samples added to the pixel at `index`.
+    pub fn sample_count(&self, index: usize) -> usize {
+        self.sample_counts[index]
+    }
+
+    pub fn get_dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Divides each pixel by the sum of the weights of its samples and converts to gamma space.
+    /// Pixels whose weights do not sum to a positive value are left black.
+    pub fn resolve(&self) -> Image {
+        Image {
+            pixels: self
+                .colours
+                .iter()
+                .zip(self.weights.iter())
+                .map(|(colour, weight)| {
+                    if *weight > 0. {
+                        let colour = *colour / *weight;
+                        Colour::new(colour[0].max(0.), colour[1].max(0.), colour[2].max(0.))
+                            .linear_to_gamma()
+                    } else {
+                        Colour::default()
+                    }
+                })
+                .collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Writes the sums of each pixel as little-endian values, without the dimensions of the buffer.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for ((colour, weight), sample_count) in self
+            .colours
+            .iter()
+            .zip(self.weights.iter())
+            .zip(self.sample_counts.iter())
+        {
+            writer.write_all(&colour[0].to_le_bytes())?;
+            writer.write_all(&colour[1].to_le_bytes())?;
+            writer.write_all(&colour[2].to_le_bytes())?;
+            writer.write_all(&weight.to_le_bytes())?;
+            writer.write_all(&(*sample_count as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a buffer of `width` by `height` written by `write_to`.
+    /// # Note
+    /// The buffer is allocated before reading, so the size must be checked against the bytes available.
+    pub fn read_from(reader: &mut impl Read, width: usize, height: usize) -> std::io::Result<Self> {
+        let mut buffer = Self::new(width, height);
+        for idx in 0..buffer.colours.len() {
+            buffer.colours[idx] = Colour::new(
+                f64::from_bits(read_u64(reader)?),
+                f64::from_bits(read_u64(reader)?),
+                f64::from_bits(read_u64(reader)?),
+            );
+            buffer.weights[idx] = f64::from_bits(read_u64(reader)?);
+            buffer.sample_counts[idx] = read_u64(reader)? as usize;
+        }
+        Ok(buffer)
+    }
+}
+
+pub(super) fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
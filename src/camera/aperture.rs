@@ -0,0 +1,176 @@
+use crate::{
+    description::{Describe, DescriptionError, Tokens},
+    Image,
+};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Shape of the aperture of the lens, which is also the shape of the out-of-focus highlights (bokeh).
+pub enum ApertureShape {
+    /// Circular aperture.
+    #[default]
+    Disk,
+    /// Regular polygon with one side per blade of the diaphragm, `rotation` in degrees.
+    Polygon { blades: usize, rotation: f64 },
+    /// Aperture with the shape of a grayscale image.
+    Mask(ApertureMask),
+}
+
+impl ApertureShape {
+    /// Creates a point of the aperture, each component between [-1.0..1.0].
+    pub fn sample(&self) -> (f64, f64) {
+        match self {
+            Self::Disk => Self::sample_disk(),
+            Self::Polygon { blades, rotation } => Self::sample_polygon(*blades, *rotation),
+            Self::Mask(mask) => mask.sample(),
+        }
+    }
+
+    /// Uniform sample on the unit disk using the concentric mapping, which maps the square into the disk
+    /// without clumping points towards the center.
+    fn sample_disk() -> (f64, f64) {
+        let x = (crate::random::random::<f64>() * 2.) - 1.;
+        let y = (crate::random::random::<f64>() * 2.) - 1.;
+        if x == 0. && y == 0. {
+            (0., 0.)
+        } else if x.abs() > y.abs() {
+            let theta = std::f64::consts::FRAC_PI_4 * (y / x);
+            (x * theta.cos(), x * theta.sin())
+        } else {
+            let theta = std::f64::consts::FRAC_PI_2 - std::f64::consts::FRAC_PI_4 * (x / y);
+            (y * theta.cos(), y * theta.sin())
+        }
+    }
+
+    /// Uniform sample on a regular polygon inscribed on the unit disk, by picking one of the triangles
+    /// formed by the center and a side, which all have the same area.
+    fn sample_polygon(blades: usize, rotation: f64) -> (f64, f64) {
+        if blades < 3 {
+            return Self::sample_disk();
+        }
+
+        let blade_angle = std::f64::consts::TAU / blades as f64;
+        let blade = (crate::random::random::<f64>() * blades as f64).floor();
+        let start_angle = rotation.to_radians() + blade * blade_angle;
+        let end_angle = start_angle + blade_angle;
+
+        let (mut u, mut v) = (
+            crate::random::random::<f64>(),
+            crate::random::random::<f64>(),
+        );
+        if u + v > 1. {
+            (u, v) = (1. - u, 1. - v);
+        }
+        (
+            u * start_angle.cos() + v * end_angle.cos(),
+            u * start_angle.sin() + v * end_angle.sin(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Aperture taken from a grayscale image, brighter pixels let more light through.
+/// The image is stretched to cover [-1.0..1.0] in both axes.
+pub struct ApertureMask {
+    cumulative_weights: Box<[f64]>,
+    width: usize,
+    height: usize,
+}
+
+impl ApertureMask {
+    /// Creates an ApertureMask from the average of the channels of each pixel of `image`.
+    /// Returns `None` when no pixel lets light through.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let (width, height) = image.get_dimensions();
+        let mut total = 0.;
+        let cumulative_weights = image
+            .get_pixels()
+            .iter()
+            .map(|pixel| {
+                total += ((pixel[0] + pixel[1] + pixel[2]) / 3.).max(0.);
+                total
+            })
+            .collect::<Box<[f64]>>();
+
+        (total > 0.).then_some(Self {
+            cumulative_weights,
+            width,
+            height,
+        })
+    }
+
+    fn sample(&self) -> (f64, f64) {
+        let total = self.cumulative_weights[self.cumulative_weights.len() - 1];
+        let target = crate::random::random::<f64>() * total;
+        let index = self
+            .cumulative_weights
+            .partition_point(|weight| *weight <= target)
+            .min(self.cumulative_weights.len() - 1);
+
+        let x = (index % self.width) as f64 + crate::random::random::<f64>();
+        let y = (index / self.width) as f64 + crate::random::random::<f64>();
+        // Top of the image is the top of the aperture
+        (
+            (x / self.width as f64) * 2. - 1.,
+            1. - (y / self.height as f64) * 2.,
+        )
+    }
+}
+
+impl Describe for ApertureMask {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.width.describe(description);
+        self.height.describe(description);
+        for weight in self.cumulative_weights.iter() {
+            weight.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let width: usize = tokens.value()?;
+        let height: usize = tokens.value()?;
+        let count = width
+            .checked_mul(height)
+            .filter(|count| *count <= tokens.remaining())
+            .ok_or_else(|| tokens.error("aperture mask has more pixels than weights"))?;
+        let cumulative_weights = (0..count)
+            .map(|_| tokens.value())
+            .collect::<Result<Box<[f64]>, _>>()?;
+        // Sampling searches the weights, so they must never go down, which NaN also fails
+        let mut previous = 0.;
+        if !cumulative_weights.iter().all(|weight| {
+            let increasing = *weight >= previous;
+            previous = *weight;
+            increasing
+        }) {
+            return Err(tokens.error("aperture mask weights must not decrease"));
+        }
+        if !cumulative_weights.last().is_some_and(|total| *total > 0.) {
+            return Err(tokens.error("aperture mask lets no light through"));
+        }
+        Ok(Self {
+            cumulative_weights,
+            width,
+            height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aperture_mask_read_test() {
+        let read = |text: &str| ApertureMask::read(&mut Tokens::new(text, 1));
+
+        let mask = read("2 1 0.5 1").expect("Expect valid mask");
+        assert_eq!((mask.width, mask.height), (2, 1));
+        assert_eq!(*mask.cumulative_weights, [0.5, 1.]);
+        // The size is checked before any weight is read
+        assert!(read("4294967296 4294967296 1").is_err());
+        assert!(read("2 2 0.5 1").is_err());
+        assert!(read("2 1 1 0.5").is_err());
+        assert!(read("2 1 NaN 1").is_err());
+        assert!(read("2 1 -1 1").is_err());
+    }
+}
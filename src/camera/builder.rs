@@ -1,6 +1,9 @@
 use crate::vector::{Direction, Point};
 
-use super::Camera;
+use super::{
+    ApertureShape, Camera, LensDistortion, PhysicalExposure, Projection, ReconstructionFilter,
+    Vignetting,
+};
 
 pub struct InputPosition;
 pub struct InputSensor;
@@ -9,14 +12,54 @@ pub struct InputComplete;
 
 pub struct CameraBuilder<T> {
     camera: Camera,
+    target_distance: Option<f64>,
     state: std::marker::PhantomData<T>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Errors when building a Camera from inputs that would not produce a valid view.
+pub enum CameraBuilderError {
+    /// One of the inputs has a NaN or infinite component.
+    NonFiniteInput,
+    /// The camera position and the target are the same point, so there is no forward direction.
+    TargetAtPosition,
+    /// The world up is zero or parallel to the forward direction, so there is no left direction.
+    UpParallelToForward,
+    /// Auto-focus was requested on a Camera that was not positioned with `look_at`.
+    NoFocusTarget,
+}
+
+impl std::fmt::Display for CameraBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonFiniteInput => write!(f, "camera input has a non-finite component"),
+            Self::TargetAtPosition => write!(f, "camera target is at the camera position"),
+            Self::UpParallelToForward => {
+                write!(f, "camera up is zero or parallel to the forward direction")
+            }
+            Self::NoFocusTarget => write!(f, "auto-focus requires a camera built with `look_at`"),
+        }
+    }
+}
+
+impl std::error::Error for CameraBuilderError {}
+
+impl<T> CameraBuilder<T> {
+    fn next_state<U>(self) -> CameraBuilder<U> {
+        CameraBuilder {
+            camera: self.camera,
+            target_distance: self.target_distance,
+            state: std::marker::PhantomData::<U>,
+        }
+    }
+}
+
 impl CameraBuilder<InputPosition> {
     /// Creates a CameraBuilder
     pub fn new(camera: Camera) -> Self {
         Self {
             camera,
+            target_distance: None,
             state: std::marker::PhantomData,
         }
     }
@@ -27,7 +70,8 @@ impl CameraBuilder<InputPosition> {
     /// `forward`: Direction where the camera is pointing towards</br>
     /// `up`: Direction where the top of the camera is pointing towards</br>
     /// # Note
-    /// When `up` is not perpendicular to `forward` the image will have a tilt-shift-like effect.
+    /// When `up` is not perpendicular to `forward` the image will have a tilt-shift-like effect,
+    /// `look_at` builds a perpendicular `up` instead.
     pub fn input_position(
         mut self,
         center: Point,
@@ -39,10 +83,54 @@ impl CameraBuilder<InputPosition> {
         self.camera.up = up.unit_vector();
         self.camera.left = up.cross(forward).unit_vector();
 
-        CameraBuilder {
-            camera: self.camera,
-            state: std::marker::PhantomData::<InputSensor>,
+        self.next_state::<InputSensor>()
+    }
+
+    /// Inputs `from`, `to`, and `world_up` of a Camera, the `forward`, `left`, and `up` of the camera
+    /// are made perpendicular to each other</br>
+    /// # Parameters
+    /// `from`: Point in space where the camera is present</br>
+    /// `to`: Point in space the camera is looking at, used as the focus target by `input_lens_auto_focus`</br>
+    /// `world_up`: Direction that should appear up on the image, does not need to be perpendicular to
+    /// the view direction</br>
+    /// # Errors
+    /// Fails if any input is not finite, if `from` and `to` are the same point, or if `world_up`
+    /// is zero or parallel to the view direction.
+    pub fn look_at(
+        mut self,
+        from: Point,
+        to: Point,
+        world_up: Direction,
+    ) -> Result<CameraBuilder<InputSensor>, CameraBuilderError> {
+        let is_finite = |components: [f64; 3]| components.iter().all(|c| c.is_finite());
+        if !is_finite([from[0], from[1], from[2]])
+            || !is_finite([to[0], to[1], to[2]])
+            || !is_finite([world_up[0], world_up[1], world_up[2]])
+        {
+            return Err(CameraBuilderError::NonFiniteInput);
+        }
+
+        let view_direction = from.point_towards(to);
+        let target_distance = view_direction.length();
+        if target_distance < 1e-12 {
+            return Err(CameraBuilderError::TargetAtPosition);
         }
+        let forward = view_direction / target_distance;
+
+        let left = world_up.cross(forward);
+        let left_length = left.length();
+        if left_length < 1e-12 * world_up.length().max(1.) {
+            return Err(CameraBuilderError::UpParallelToForward);
+        }
+        let left = left / left_length;
+
+        self.camera.center = from;
+        self.camera.forward = forward;
+        self.camera.left = left;
+        self.camera.up = forward.cross(left);
+        self.target_distance = Some(target_distance);
+
+        Ok(self.next_state::<InputSensor>())
     }
 }
 
@@ -65,10 +153,7 @@ impl CameraBuilder<InputSensor> {
         self.camera.shutter_length = shutter_length;
         self.camera.max_ray_depth = max_ray_depth;
 
-        CameraBuilder {
-            camera: self.camera,
-            state: std::marker::PhantomData::<InputLens>,
-        }
+        self.next_state::<InputLens>()
     }
 }
 
@@ -77,7 +162,8 @@ impl CameraBuilder<InputLens> {
     /// # Parameters
     /// `focal_distance`: Distance that the camera will focus, objects closer or farther will be blurred</br>
     /// `aperture`: Determinates the size of the aperture, higher values will cause objects out of
-    /// focus to be blurrier. Will now darken the image like in a physical camera.</br>
+    /// focus to be blurrier, the shape is set by `with_aperture_shape`. Will not darken the image like in a
+    /// physical camera, `input_physical_lens` does.</br>
     /// `field_of_view`: Vertical view angle, clamped between (0.0, 180.)</br>
     pub fn input_lens(
         mut self,
@@ -88,16 +174,204 @@ impl CameraBuilder<InputLens> {
         self.camera.focal_distance = focal_distance.max(0.);
         self.camera.aperture = aperture.max(0.);
         self.camera.field_of_view = field_of_view.clamp(0.1, 179.9);
+        self.camera.exposure = None;
+        self.camera.projection = Projection::Perspective;
 
-        CameraBuilder {
-            camera: self.camera,
-            state: std::marker::PhantomData::<InputComplete>,
-        }
+        self.next_state::<InputComplete>()
+    }
+
+    /// Inputs `focal_distance`, `exposure`, and `field_of_view` of a Camera that behaves like a physical camera</br>
+    /// # Parameters
+    /// `focal_distance`: Same as `input_lens`</br>
+    /// `exposure`: f-number, shutter time, and ISO of the camera, the aperture is derived from the f-number and
+    /// the field of view, and the image is darkened or brightened following the exposure value</br>
+    /// `field_of_view`: Same as `input_lens`</br>
+    pub fn input_physical_lens(
+        self,
+        focal_distance: f64,
+        exposure: PhysicalExposure,
+        field_of_view: f64,
+    ) -> CameraBuilder<InputComplete> {
+        let field_of_view = field_of_view.clamp(0.1, 179.9);
+        let mut builder = self.input_lens(
+            focal_distance,
+            exposure.aperture_radius(field_of_view),
+            field_of_view,
+        );
+        builder.camera.exposure = Some(exposure);
+        builder
+    }
+
+    /// Inputs `aperture` and `field_of_view` of a Camera, focusing on the target given to `look_at`</br>
+    /// # Parameters
+    /// `aperture`: Same as `input_lens`</br>
+    /// `field_of_view`: Same as `input_lens`</br>
+    /// # Errors
+    /// Fails if the Camera was not positioned with `look_at`.
+    pub fn input_lens_auto_focus(
+        self,
+        aperture: f64,
+        field_of_view: f64,
+    ) -> Result<CameraBuilder<InputComplete>, CameraBuilderError> {
+        let focal_distance = self
+            .target_distance
+            .ok_or(CameraBuilderError::NoFocusTarget)?;
+        Ok(self.input_lens(focal_distance, aperture, field_of_view))
+    }
+
+    /// Inputs `view_height` of an orthographic Camera</br>
+    /// # Parameters
+    /// `view_height`: Height in world units of the area seen by the camera, the width follows the
+    /// aspect ratio of the sensor</br>
+    pub fn input_orthographic(mut self, view_height: f64) -> CameraBuilder<InputComplete> {
+        self.camera.projection = Projection::Orthographic {
+            view_height: view_height.max(0.),
+        };
+
+        self.next_state::<InputComplete>()
+    }
+
+    /// Inputs `field_of_view` of an equidistant fisheye Camera</br>
+    /// # Parameters
+    /// `field_of_view`: View angle covered by the height of the sensor, clamped between (0.0, 360.0]</br>
+    pub fn input_fisheye(mut self, field_of_view: f64) -> CameraBuilder<InputComplete> {
+        self.camera.projection = Projection::Fisheye {
+            field_of_view: field_of_view.clamp(0.1, 360.),
+        };
+
+        self.next_state::<InputComplete>()
+    }
+
+    /// Makes the Camera capture a 360° equirectangular panorama</br>
+    /// # Note
+    /// A sensor twice as wide as tall keeps the pixels square.
+    pub fn input_equirectangular(mut self) -> CameraBuilder<InputComplete> {
+        self.camera.projection = Projection::Equirectangular;
+
+        self.next_state::<InputComplete>()
     }
 }
 
 impl CameraBuilder<InputComplete> {
+    /// Inputs the `reconstruction_filter` used to weight the samples of each pixel</br>
+    /// # Note
+    /// Defaults to a Box filter with a radius of half a pixel.
+    pub fn with_reconstruction_filter(
+        mut self,
+        reconstruction_filter: ReconstructionFilter,
+    ) -> Self {
+        self.camera.reconstruction_filter = reconstruction_filter;
+        self
+    }
+
+    /// Inputs the `seed` of the random generator, captures with the same seed and settings give the same Image</br>
+    /// # Note
+    /// Defaults to 0.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.camera.seed = seed;
+        self
+    }
+
+    /// Inputs the `aperture_shape` of the lens, scaled by the `aperture` given to `input_lens`</br>
+    /// # Note
+    /// Defaults to a Disk.
+    pub fn with_aperture_shape(mut self, aperture_shape: ApertureShape) -> Self {
+        self.camera.aperture_shape = aperture_shape;
+        self
+    }
+
+    /// Inputs the `lens_distortion` of a perspective Camera</br>
+    /// # Note
+    /// Defaults to no distortion.
+    pub fn with_lens_distortion(mut self, lens_distortion: LensDistortion) -> Self {
+        self.camera.lens_distortion = lens_distortion;
+        self
+    }
+
+    /// Inputs the `vignetting` of a perspective Camera</br>
+    /// # Note
+    /// Defaults to no vignetting.
+    pub fn with_vignetting(mut self, vignetting: Vignetting) -> Self {
+        self.camera.vignetting = vignetting;
+        self
+    }
+
     pub fn build(self) -> Camera {
         self.camera
     }
 }
+
+impl Camera {
+    /// Creates a copy of the Camera moved to `from` and looking at `to`, with the same sensor and lens</br>
+    /// # Parameters
+    /// Same as `look_at`</br>
+    /// # Errors
+    /// Same as `look_at`.
+    pub fn looking_at(
+        &self,
+        from: Point,
+        to: Point,
+        world_up: Direction,
+    ) -> Result<Camera, CameraBuilderError> {
+        CameraBuilder::new(self.clone())
+            .look_at(from, to, world_up)
+            .map(|builder| builder.camera)
+    }
+
+    /// Creates a copy of the Camera focused at `focal_distance`.
+    #[must_use]
+    pub fn focused_at(&self, focal_distance: f64) -> Camera {
+        Camera {
+            focal_distance: focal_distance.max(0.),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn look_at_test() {
+        let world_up = Direction::new(0., 1., 0.);
+        let from = Point::new(0., 0., 3.);
+
+        let camera = Camera::builder()
+            .look_at(from, Point::new(0., 0., 0.), Direction::new(0., 2., 1.))
+            .expect("Expect valid camera position")
+            .input_sensor(1, 1, 1, 1)
+            .input_lens_auto_focus(0., 90.)
+            .expect("Expect focus target")
+            .build();
+        assert!(camera.forward.dot(camera.up).abs() < 1e-12);
+        assert!(camera.forward.dot(camera.left).abs() < 1e-12);
+        assert!((camera.focal_distance - 3.).abs() < 1e-12);
+
+        assert_eq!(
+            Camera::builder().look_at(from, from, world_up).err(),
+            Some(CameraBuilderError::TargetAtPosition)
+        );
+        assert_eq!(
+            Camera::builder()
+                .look_at(from, Point::new(0., 5., 3.), world_up)
+                .err(),
+            Some(CameraBuilderError::UpParallelToForward)
+        );
+        assert_eq!(
+            Camera::builder()
+                .look_at(Point::new(f64::NAN, 0., 0.), from, world_up)
+                .err(),
+            Some(CameraBuilderError::NonFiniteInput)
+        );
+        assert_eq!(
+            Camera::builder()
+                .input_position(from, Direction::new(0., 0., -1.), world_up)
+                .input_sensor(1, 1, 1, 1)
+                .input_lens_auto_focus(0., 90.)
+                .err(),
+            Some(CameraBuilderError::NoFocusTarget)
+        );
+    }
+}
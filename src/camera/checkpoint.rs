@@ -0,0 +1,320 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{description::describe_scene, object::Object};
+
+use super::{
+    accumulation::{read_u64, PIXEL_LENGTH},
+    AccumulationBuffer, Camera, SensorRegion,
+};
+
+const MAGIC: &[u8; 8] = b"RTIAWCKP";
+const VERSION: u64 = 3;
+/// Bytes before the buffer, the magic followed by 11 values.
+const HEADER_LENGTH: u64 = 8 + 11 * 8;
+
+/// State of an unfinished capture, enough to continue it later with the same result as an uninterrupted capture.</br>
+/// Every sample reseeds the random generator from the Camera seed, the pixel, and the number of samples the pixel
+/// already has, so the sample counts in the buffer are the whole state of the generator.
+pub struct Checkpoint {
+    settings_fingerprint: u64,
+    sensor_width: usize,
+    sensor_height: usize,
+    region: SensorRegion,
+    max_ray_depth: usize,
+    seed: u64,
+    shutter_length: usize,
+    buffer: AccumulationBuffer,
+}
+
+impl Checkpoint {
+    /// Creates a Checkpoint of a capture of `region` of `scene` by `camera`.
+    pub fn new(
+        camera: &Camera,
+        scene: &[Object],
+        region: SensorRegion,
+        buffer: AccumulationBuffer,
+    ) -> Self {
+        Self {
+            settings_fingerprint: Self::settings_fingerprint(camera, scene),
+            sensor_width: camera.sensor_width,
+            sensor_height: camera.sensor_height,
+            region,
+            max_ray_depth: camera.max_ray_depth,
+            seed: camera.seed,
+            shutter_length: camera.shutter_length,
+            buffer,
+        }
+    }
+
+    /// Number of samples requested by the capture that saved the Checkpoint.
+    pub fn shutter_length(&self) -> usize {
+        self.shutter_length
+    }
+
+    /// Returns the accumulated samples if the Checkpoint was made by a capture of `region` of `scene` with the same
+    /// settings as `camera`. The number of samples is allowed to differ, so a capture can be continued to a higher
+    /// sample count.
+    /// # Errors
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if the settings or the scene differ.
+    pub fn resume(
+        self,
+        camera: &Camera,
+        scene: &[Object],
+        region: SensorRegion,
+    ) -> std::io::Result<AccumulationBuffer> {
+        if self.settings_fingerprint != Self::settings_fingerprint(camera, scene)
+            || self.sensor_width != camera.sensor_width
+            || self.sensor_height != camera.sensor_height
+            || self.region != region
+            || self.max_ray_depth != camera.max_ray_depth
+            || self.seed != camera.seed
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "checkpoint was saved with different render settings",
+            ))
+        } else {
+            Ok(self.buffer)
+        }
+    }
+
+    /// Saves the Checkpoint to `path`. The file is written next to `path` and then renamed,
+    /// so an interrupted save does not destroy a previous Checkpoint.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let temporary_path = path.with_extension("partial");
+        let mut writer = BufWriter::new(std::fs::File::create(&temporary_path)?);
+        self.write_to(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        std::fs::rename(temporary_path, path)
+    }
+
+    /// Loads the Checkpoint of a capture of `region` from `path`.
+    /// # Errors
+    /// Fails if the file can't be read, or with [`std::io::ErrorKind::InvalidData`] like `read_from`.
+    pub fn load(path: &Path, region: SensorRegion) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let length = file.metadata()?.len();
+        Self::read_from(&mut BufReader::new(file), length, region)
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for value in [
+            VERSION,
+            self.settings_fingerprint,
+            self.sensor_width as u64,
+            self.sensor_height as u64,
+            self.region.x as u64,
+            self.region.y as u64,
+            self.region.width as u64,
+            self.region.height as u64,
+            self.max_ray_depth as u64,
+            self.seed,
+            self.shutter_length as u64,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        self.buffer.write_to(writer)
+    }
+
+    /// Reads the Checkpoint of a capture of `region` from the `length` bytes of `reader`.
+    /// # Errors
+    /// Fails with [`std::io::ErrorKind::InvalidData`] if the Checkpoint is not of a supported version, is of another
+    /// region, or is shorter than its buffer. These are checked before the buffer is allocated.
+    pub fn read_from(
+        reader: &mut impl Read,
+        length: u64,
+        region: SensorRegion,
+    ) -> std::io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u64(reader)? != VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "file is not a checkpoint of a supported version",
+            ));
+        }
+
+        let settings_fingerprint = read_u64(reader)?;
+        let sensor_width = read_u64(reader)? as usize;
+        let sensor_height = read_u64(reader)? as usize;
+        let saved_region = SensorRegion::new(
+            read_u64(reader)? as usize,
+            read_u64(reader)? as usize,
+            read_u64(reader)? as usize,
+            read_u64(reader)? as usize,
+        );
+        let max_ray_depth = read_u64(reader)? as usize;
+        let seed = read_u64(reader)?;
+        let shutter_length = read_u64(reader)? as usize;
+        let invalid = |message| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        if saved_region != region {
+            return Err(invalid("checkpoint was saved for a different region"));
+        }
+        let buffer_length = (region.width as u64)
+            .checked_mul(region.height as u64)
+            .and_then(|pixels| pixels.checked_mul(PIXEL_LENGTH))
+            .and_then(|buffer_length| buffer_length.checked_add(HEADER_LENGTH));
+        if buffer_length.is_none_or(|buffer_length| buffer_length > length) {
+            return Err(invalid("checkpoint is shorter than its buffer"));
+        }
+        let buffer = AccumulationBuffer::read_from(reader, region.width, region.height)?;
+
+        Ok(Self {
+            settings_fingerprint,
+            sensor_width,
+            sensor_height,
+            region,
+            max_ray_depth,
+            seed,
+            shutter_length,
+            buffer,
+        })
+    }
+
+    /// FNV-1a hash of the description of `scene` and of every setting of `camera` except the number of samples.
+    /// # Note
+    /// Custom geometry is described without its contents, so changes to it are not noticed.
+    fn settings_fingerprint(camera: &Camera, scene: &[Object]) -> u64 {
+        let settings = describe_scene(
+            &Camera {
+                shutter_length: 0,
+                ..camera.clone()
+            },
+            scene,
+        );
+        settings.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::{
+        camera::{Capture, RenderControl, RenderProgress},
+        object::{Object, ObjectMaterial},
+        vector::{Colour, Direction, Point},
+    };
+
+    use super::*;
+
+    #[test]
+    fn resume_checkpoint_test() {
+        let scene = vec![
+            Object::new_sphere(
+                Point::new(0., -100.5, -1.),
+                100.,
+                ObjectMaterial::Lambert {
+                    albedo: Colour::new(0.5, 0.5, 0.5).into(),
+                },
+            ),
+            Object::new_sphere(
+                Point::new(0., 0., -1.),
+                0.5,
+                ObjectMaterial::Dialectric {
+                    refraction_index: 1.5,
+                },
+            ),
+        ];
+        let camera = Camera::builder()
+            .look_at(
+                Point::new(0., 0., 1.),
+                Point::new(0., 0., -1.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(12, 8, 6, 8)
+            .input_lens_auto_focus(0.1, 60.)
+            .expect("Expect focus target")
+            .with_seed(7)
+            .build();
+        let checkpoint_path =
+            std::env::temp_dir().join(format!("rtiaw_checkpoint_{}.ckp", std::process::id()));
+
+        let uninterrupted = camera.capture_image(&scene);
+
+        let mut cancel_after_rows = |progress: &RenderProgress| {
+            if progress.rows_completed == 19 {
+                RenderControl::Cancel
+            } else {
+                RenderControl::Continue
+            }
+        };
+        let interrupted = camera
+            .capture_image_with_checkpoints(
+                &scene,
+                &checkpoint_path,
+                Duration::ZERO,
+                &mut cancel_after_rows,
+            )
+            .expect("Expect checkpoint saved");
+        assert!(matches!(interrupted, Capture::Cancelled(_)));
+        assert!(checkpoint_path.exists());
+
+        let resumed = camera
+            .capture_image_with_checkpoints(&scene, &checkpoint_path, Duration::ZERO, &mut ())
+            .expect("Expect checkpoint resumed");
+        assert!(resumed.is_complete());
+        assert!(!checkpoint_path.exists());
+        assert_eq!(
+            resumed.into_image().get_pixels(),
+            uninterrupted.get_pixels()
+        );
+    }
+
+    #[test]
+    fn read_checkpoint_test() {
+        let scene = vec![Object::new_sphere(
+            Point::new(0., 0., -1.),
+            0.5,
+            ObjectMaterial::Lambert {
+                albedo: Colour::new(0.5, 0.5, 0.5).into(),
+            },
+        )];
+        let camera = Camera::builder()
+            .look_at(
+                Point::new(0., 0., 1.),
+                Point::new(0., 0., -1.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(4, 2, 1, 2)
+            .input_lens(2., 0., 60.)
+            .build();
+        let region = SensorRegion::full(4, 2);
+        let mut bytes = Vec::new();
+        Checkpoint::new(&camera, &scene, region, AccumulationBuffer::new(4, 2))
+            .write_to(&mut bytes)
+            .expect("Expect checkpoint written");
+        let read = |bytes: &[u8], region| {
+            Checkpoint::read_from(&mut &bytes[..], bytes.len() as u64, region)
+        };
+
+        let checkpoint = read(&bytes, region).expect("Expect checkpoint read");
+        assert!(checkpoint
+            .resume(&camera, &scene[..0], region)
+            .is_err_and(|err| err.kind() == std::io::ErrorKind::InvalidData));
+        let checkpoint = read(&bytes, region).expect("Expect checkpoint read");
+        assert!(checkpoint.resume(&camera, &scene, region).is_ok());
+
+        // The size is checked against the file and the region before the buffer is allocated
+        let is_invalid = |result: std::io::Result<Checkpoint>| {
+            result.is_err_and(|err| err.kind() == std::io::ErrorKind::InvalidData)
+        };
+        assert!(is_invalid(read(&bytes[..bytes.len() - 1], region)));
+        assert!(is_invalid(read(&bytes, SensorRegion::full(2, 2))));
+        let mut huge = bytes[..HEADER_LENGTH as usize].to_vec();
+        huge[56..72].copy_from_slice(&[0xFF; 16]);
+        assert!(is_invalid(read(
+            &huge,
+            SensorRegion::new(0, 0, usize::MAX, usize::MAX)
+        )));
+    }
+}
@@ -0,0 +1,251 @@
+use crate::description::{Describe, DescriptionError, Tokens};
+
+use super::{
+    ApertureMask, ApertureShape, Camera, LensDistortion, PhysicalExposure, Projection,
+    ReconstructionFilter, Vignetting,
+};
+
+/// Largest number of pixels of the sensor of a Camera read from a description.
+const MAX_SENSOR_AREA: usize = 1 << 26;
+
+impl Describe for Camera {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.center.describe(description);
+        self.forward.describe(description);
+        self.up.describe(description);
+        self.left.describe(description);
+        self.sensor_width.describe(description);
+        self.sensor_height.describe(description);
+        self.focal_distance.describe(description);
+        self.aperture.describe(description);
+        self.aperture_shape.describe(description);
+        self.lens_distortion.describe(description);
+        self.vignetting.describe(description);
+        self.field_of_view.describe(description);
+        match &self.exposure {
+            Some(exposure) => {
+                description.push("physical".to_owned());
+                exposure.describe(description);
+            }
+            None => description.push("none".to_owned()),
+        }
+        self.shutter_length.describe(description);
+        self.max_ray_depth.describe(description);
+        self.seed.describe(description);
+        self.reconstruction_filter.describe(description);
+        self.projection.describe(description);
+        self.eye_offset.describe(description);
+        self.convergence_distance.describe(description);
+    }
+
+    /// # Errors
+    /// Fails if the sensor has more than 2^26 pixels, or if the lens or the projection have values that are not
+    /// finite or out of range, since the Camera can come from an untrusted description.
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let camera = Self {
+            center: Describe::read(tokens)?,
+            forward: Describe::read(tokens)?,
+            up: Describe::read(tokens)?,
+            left: Describe::read(tokens)?,
+            sensor_width: Describe::read(tokens)?,
+            sensor_height: Describe::read(tokens)?,
+            focal_distance: Describe::read(tokens)?,
+            aperture: Describe::read(tokens)?,
+            aperture_shape: Describe::read(tokens)?,
+            lens_distortion: Describe::read(tokens)?,
+            vignetting: Describe::read(tokens)?,
+            field_of_view: Describe::read(tokens)?,
+            exposure: match tokens.word()? {
+                "physical" => Some(PhysicalExposure::read(tokens)?),
+                "none" => None,
+                word => return tokens.unknown_variant("exposure", word),
+            },
+            shutter_length: Describe::read(tokens)?,
+            max_ray_depth: Describe::read(tokens)?,
+            seed: Describe::read(tokens)?,
+            reconstruction_filter: Describe::read(tokens)?,
+            projection: Describe::read(tokens)?,
+            eye_offset: Describe::read(tokens)?,
+            convergence_distance: Describe::read(tokens)?,
+        };
+
+        if camera
+            .sensor_width
+            .checked_mul(camera.sensor_height)
+            .is_none_or(|area| area > MAX_SENSOR_AREA)
+        {
+            return Err(tokens.error("sensor is too large"));
+        }
+        let distortion = &camera.lens_distortion;
+        let coefficients = [
+            distortion.k1,
+            distortion.k2,
+            distortion.k3,
+            distortion.p1,
+            distortion.p2,
+        ];
+        if !(0. ..f64::INFINITY).contains(&camera.focal_distance)
+            || !(0. ..f64::INFINITY).contains(&camera.aperture)
+            || !coefficients
+                .iter()
+                .all(|coefficient| coefficient.is_finite())
+            || !(0. ..180.).contains(&camera.field_of_view)
+        {
+            return Err(tokens.error("lens values are out of range"));
+        }
+        let projection_valid = match camera.projection {
+            Projection::Perspective | Projection::Equirectangular => true,
+            Projection::Orthographic { view_height } => {
+                view_height.is_finite() && view_height >= 0.
+            }
+            Projection::Fisheye { field_of_view } => field_of_view > 0. && field_of_view <= 360.,
+        };
+        if !projection_valid {
+            return Err(tokens.error("projection values are out of range"));
+        }
+        Ok(camera)
+    }
+}
+
+impl Describe for ApertureShape {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Disk => description.push("disk".to_owned()),
+            Self::Polygon { blades, rotation } => {
+                description.push("polygon".to_owned());
+                blades.describe(description);
+                rotation.describe(description);
+            }
+            Self::Mask(mask) => {
+                description.push("mask".to_owned());
+                mask.describe(description);
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "disk" => Ok(Self::Disk),
+            "polygon" => Ok(Self::Polygon {
+                blades: tokens.value()?,
+                rotation: tokens.value()?,
+            }),
+            "mask" => Ok(Self::Mask(ApertureMask::read(tokens)?)),
+            word => tokens.unknown_variant("aperture shape", word),
+        }
+    }
+}
+
+impl Describe for LensDistortion {
+    fn describe(&self, description: &mut Vec<String>) {
+        for coefficient in [self.k1, self.k2, self.k3, self.p1, self.p2] {
+            coefficient.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok(Self {
+            k1: tokens.value()?,
+            k2: tokens.value()?,
+            k3: tokens.value()?,
+            p1: tokens.value()?,
+            p2: tokens.value()?,
+        })
+    }
+}
+
+impl Describe for Vignetting {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::None => description.push("none".to_owned()),
+            Self::Natural => description.push("natural".to_owned()),
+            Self::Optical { strength } => {
+                description.push("optical".to_owned());
+                strength.describe(description);
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "none" => Ok(Self::None),
+            "natural" => Ok(Self::Natural),
+            "optical" => Ok(Self::Optical {
+                strength: tokens.value()?,
+            }),
+            word => tokens.unknown_variant("vignetting", word),
+        }
+    }
+}
+
+impl Describe for ReconstructionFilter {
+    fn describe(&self, description: &mut Vec<String>) {
+        let (name, parameters): (_, &[f64]) = match self {
+            Self::Box { radius } => ("box", &[*radius]),
+            Self::Tent { radius } => ("tent", &[*radius]),
+            Self::Gaussian { radius, alpha } => ("gaussian", &[*radius, *alpha]),
+            Self::Mitchell { radius, b, c } => ("mitchell", &[*radius, *b, *c]),
+            Self::Lanczos { radius, tau } => ("lanczos", &[*radius, *tau]),
+        };
+        description.push(name.to_owned());
+        for parameter in parameters {
+            parameter.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "box" => Ok(Self::Box {
+                radius: tokens.value()?,
+            }),
+            "tent" => Ok(Self::Tent {
+                radius: tokens.value()?,
+            }),
+            "gaussian" => Ok(Self::Gaussian {
+                radius: tokens.value()?,
+                alpha: tokens.value()?,
+            }),
+            "mitchell" => Ok(Self::Mitchell {
+                radius: tokens.value()?,
+                b: tokens.value()?,
+                c: tokens.value()?,
+            }),
+            "lanczos" => Ok(Self::Lanczos {
+                radius: tokens.value()?,
+                tau: tokens.value()?,
+            }),
+            word => tokens.unknown_variant("reconstruction filter", word),
+        }
+    }
+}
+
+impl Describe for Projection {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Perspective => description.push("perspective".to_owned()),
+            Self::Orthographic { view_height } => {
+                description.push("orthographic".to_owned());
+                view_height.describe(description);
+            }
+            Self::Fisheye { field_of_view } => {
+                description.push("fisheye".to_owned());
+                field_of_view.describe(description);
+            }
+            Self::Equirectangular => description.push("equirectangular".to_owned()),
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "perspective" => Ok(Self::Perspective),
+            "orthographic" => Ok(Self::Orthographic {
+                view_height: tokens.value()?,
+            }),
+            "fisheye" => Ok(Self::Fisheye {
+                field_of_view: tokens.value()?,
+            }),
+            "equirectangular" => Ok(Self::Equirectangular),
+            word => tokens.unknown_variant("projection", word),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::description::{Describe, DescriptionError, Tokens};
+
+/// Height of a full-frame (36mm x 24mm) sensor, in meters.
+const SENSOR_HEIGHT: f64 = 0.024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Real-world camera settings, used to derive the aperture of the lens and how much of the scene radiance
+/// reaches the sensor.
+/// # Note
+/// Assumes world units are meters, the sensor is full-frame, and the radiance of the scene is luminance in cd/m².
+pub struct PhysicalExposure {
+    f_number: f64,
+    shutter_time: f64,
+    iso: f64,
+}
+
+impl PhysicalExposure {
+    /// Creates a PhysicalExposure</br>
+    /// # Parameters
+    /// `f_number`: Ratio of the focal length to the diameter of the aperture, ƒ/2.8 is `2.8`</br>
+    /// `shutter_time`: Time the shutter stays open, in seconds</br>
+    /// `iso`: Sensitivity of the sensor</br>
+    #[must_use]
+    pub fn new(f_number: f64, shutter_time: f64, iso: f64) -> Self {
+        Self {
+            f_number: f_number.max(f64::EPSILON),
+            shutter_time: shutter_time.max(f64::EPSILON),
+            iso: iso.max(f64::EPSILON),
+        }
+    }
+
+    /// Exposure value normalized to ISO 100.
+    pub fn ev100(&self) -> f64 {
+        (self.f_number.powi(2) / self.shutter_time * 100. / self.iso).log2()
+    }
+
+    /// Factor applied to the scene radiance, from the saturation based sensitivity of the sensor.
+    pub fn exposure_multiplier(&self) -> f64 {
+        1. / (1.2 * 2_f64.powf(self.ev100()))
+    }
+
+    /// Radius of the aperture, in meters, for a lens with vertical `field_of_view` in degrees.
+    pub fn aperture_radius(&self, field_of_view: f64) -> f64 {
+        let focal_length = (SENSOR_HEIGHT / 2.) / (field_of_view.to_radians() / 2.).tan();
+        focal_length / (2. * self.f_number)
+    }
+}
+
+impl Describe for PhysicalExposure {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.f_number.describe(description);
+        self.shutter_time.describe(description);
+        self.iso.describe(description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok(Self::new(tokens.value()?, tokens.value()?, tokens.value()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn physical_exposure_test() {
+        let unit = PhysicalExposure::new(1., 1., 100.);
+        assert!(unit.ev100().abs() < 1e-12);
+        assert!((unit.exposure_multiplier() - 1. / 1.2).abs() < 1e-12);
+
+        // Sunny 16 rule
+        let sunny = PhysicalExposure::new(16., 1. / 100., 100.);
+        assert!((sunny.ev100() - 25_600_f64.log2()).abs() < 1e-12);
+        assert!((sunny.ev100() - 14.64).abs() < 0.01);
+        assert!((sunny.exposure_multiplier() - 1. / (1.2 * 25_600.)).abs() < 1e-15);
+        // Doubling the ISO is one stop brighter
+        let faster = PhysicalExposure::new(16., 1. / 100., 200.);
+        assert!((sunny.ev100() - faster.ev100() - 1.).abs() < 1e-12);
+
+        // A 50mm lens on a full-frame sensor at ƒ/2 has an aperture 25mm wide
+        let field_of_view = 2. * (12_f64 / 50.).atan().to_degrees();
+        let aperture = PhysicalExposure::new(2., 1. / 100., 100.).aperture_radius(field_of_view);
+        assert!((aperture - 0.0125).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,158 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Reconstruction filter used to weight the samples taken for each pixel.</br>
+/// Samples are taken uniformly inside the filter support (`radius` pixels around the pixel center
+/// in each axis) and weighted by the filter, the pixel is the weighted average of its samples.
+pub enum ReconstructionFilter {
+    /// Every sample inside the support has the same weight.
+    Box { radius: f64 },
+    /// Weight falls off linearly from the pixel center.
+    Tent { radius: f64 },
+    /// Gaussian curve with falloff `alpha`, shifted so it reaches zero at `radius`.
+    Gaussian { radius: f64, alpha: f64 },
+    /// Mitchell-Netravali cubic, `b` and `c` control blurring and ringing.
+    /// `b = c = 1/3` is the recommended value.
+    Mitchell { radius: f64, b: f64, c: f64 },
+    /// Sinc windowed by a Lanczos window, `tau` is the number of sinc cycles inside the support.
+    Lanczos { radius: f64, tau: f64 },
+}
+
+impl Default for ReconstructionFilter {
+    /// Creates a Box filter that covers exactly one pixel.
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+impl ReconstructionFilter {
+    pub fn radius(&self) -> f64 {
+        match self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::Mitchell { radius, .. }
+            | Self::Lanczos { radius, .. } => *radius,
+        }
+    }
+
+    /// Creates an offset from the pixel center uniformly distributed inside the filter support.
+    pub fn sample_offset(&self) -> (f64, f64) {
+        let radius = self.radius();
+        (
+            ((crate::random::random::<f64>() * 2.) - 1.) * radius,
+            ((crate::random::random::<f64>() * 2.) - 1.) * radius,
+        )
+    }
+
+    /// Weight of a sample at offset (`x`, `y`) from the pixel center.
+    /// Mitchell and Lanczos have negative lobes, so the weight may be negative.
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+
+    fn evaluate_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match *self {
+            Self::Box { radius } => {
+                if x <= radius {
+                    1.
+                } else {
+                    0.
+                }
+            }
+            Self::Tent { radius } => (radius - x).max(0.),
+            Self::Gaussian { radius, alpha } => {
+                ((-alpha * x.powi(2)).exp() - (-alpha * radius.powi(2)).exp()).max(0.)
+            }
+            Self::Mitchell { radius, b, c } => Self::mitchell_1d(2. * x / radius, b, c),
+            Self::Lanczos { radius, tau } => {
+                let x = x / radius;
+                if x > 1. {
+                    0.
+                } else {
+                    Self::sinc(x * tau) * Self::sinc(x)
+                }
+            }
+        }
+    }
+
+    fn mitchell_1d(x: f64, b: f64, c: f64) -> f64 {
+        if x > 2. {
+            0.
+        } else if x > 1. {
+            ((-b - 6. * c) * x.powi(3)
+                + (6. * b + 30. * c) * x.powi(2)
+                + (-12. * b - 48. * c) * x
+                + (8. * b + 24. * c))
+                / 6.
+        } else {
+            ((12. - 9. * b - 6. * c) * x.powi(3)
+                + (-18. + 12. * b + 6. * c) * x.powi(2)
+                + (6. - 2. * b))
+                / 6.
+        }
+    }
+
+    fn sinc(x: f64) -> f64 {
+        if x.abs() < 1e-5 {
+            1.
+        } else {
+            let x = x * std::f64::consts::PI;
+            x.sin() / x
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_test() {
+        let third = 1. / 3.;
+        let filters = [
+            (ReconstructionFilter::Box { radius: 1. }, 1., 1.),
+            (ReconstructionFilter::Tent { radius: 1. }, 1., 0.),
+            (
+                ReconstructionFilter::Gaussian {
+                    radius: 1.,
+                    alpha: 2.,
+                },
+                1. - (-2f64).exp(),
+                0.,
+            ),
+            (
+                ReconstructionFilter::Mitchell {
+                    radius: 1.,
+                    b: third,
+                    c: third,
+                },
+                (6. - 2. * third) / 6.,
+                0.,
+            ),
+            (
+                ReconstructionFilter::Lanczos {
+                    radius: 1.,
+                    tau: 3.,
+                },
+                1.,
+                0.,
+            ),
+        ];
+        for (filter, at_center, at_radius) in filters {
+            assert_eq!(filter.radius(), 1.);
+            assert!((filter.evaluate(0., 0.) - at_center * at_center).abs() < 1e-12);
+            assert!((filter.evaluate(1., 0.) - at_radius * at_center).abs() < 1e-12);
+            assert!((filter.evaluate(0., -1.) - at_radius * at_center).abs() < 1e-12);
+            assert_eq!(filter.evaluate(1.5, 0.), 0.);
+
+            // Samples spread over the support must not cancel out, or the pixel would be left black
+            let steps = 20;
+            let offset = |step: usize| step as f64 / steps as f64 * 2. - 1.;
+            let total = (0..=steps)
+                .flat_map(|x| (0..=steps).map(move |y| (offset(x), offset(y))))
+                .map(|(x, y)| filter.evaluate(x, y))
+                .sum::<f64>();
+            assert!(total > 0., "{filter:?} weights sum to {total}");
+        }
+    }
+}
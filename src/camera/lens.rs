@@ -0,0 +1,133 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// Brown-Conrady lens distortion, with radial coefficients `k1`, `k2`, and `k3`, and tangential coefficients
+/// `p1` and `p2`. Positive `k1` gives pincushion distortion and negative `k1` gives barrel distortion.</br>
+/// Coefficients are applied on normalized image coordinates (divided by the focal length), with x pointing
+/// right and y pointing down, matching the coefficients given by common calibration tools.
+pub struct LensDistortion {
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub p1: f64,
+    pub p2: f64,
+}
+
+impl LensDistortion {
+    /// Moves an undistorted point of the image to where the lens places it.
+    pub fn distort(&self, x: f64, y: f64) -> (f64, f64) {
+        let radius_squared = x.powi(2) + y.powi(2);
+        let radial = 1.
+            + self.k1 * radius_squared
+            + self.k2 * radius_squared.powi(2)
+            + self.k3 * radius_squared.powi(3);
+        (
+            x * radial + 2. * self.p1 * x * y + self.p2 * (radius_squared + 2. * x.powi(2)),
+            y * radial + self.p1 * (radius_squared + 2. * y.powi(2)) + 2. * self.p2 * x * y,
+        )
+    }
+
+    /// Finds the undistorted point that the lens places at (`x`, `y`). The distortion has no closed-form inverse,
+    /// so it is found by fixed-point iteration.
+    pub fn undistort(&self, x: f64, y: f64) -> (f64, f64) {
+        let (mut undistorted_x, mut undistorted_y) = (x, y);
+        for _ in 0..20 {
+            let radius_squared = undistorted_x.powi(2) + undistorted_y.powi(2);
+            let radial = 1.
+                + self.k1 * radius_squared
+                + self.k2 * radius_squared.powi(2)
+                + self.k3 * radius_squared.powi(3);
+            let tangential_x = 2. * self.p1 * undistorted_x * undistorted_y
+                + self.p2 * (radius_squared + 2. * undistorted_x.powi(2));
+            let tangential_y = self.p1 * (radius_squared + 2. * undistorted_y.powi(2))
+                + 2. * self.p2 * undistorted_x * undistorted_y;
+            undistorted_x = (x - tangential_x) / radial;
+            undistorted_y = (y - tangential_y) / radial;
+        }
+        (undistorted_x, undistorted_y)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// Light falloff towards the borders of the image.
+pub enum Vignetting {
+    #[default]
+    None,
+    /// Cosine-fourth falloff, from the angle of the ray to the optical axis.
+    Natural,
+    /// The lens barrel acts as a second aperture shifted towards the border of the image, cutting the
+    /// aperture into a cat's eye shape. `strength` is how much the second aperture moves, in aperture radii,
+    /// for each sensor half-height away from the center.
+    Optical { strength: f64 },
+}
+
+impl Vignetting {
+    /// Fraction of the light that reaches a point of the sensor, given the undistorted image coordinates
+    /// (`image_x`, `image_y`), the position of the sample on the aperture (`aperture_x`, `aperture_y`),
+    /// and the position on the sensor (`sensor_x`, `sensor_y`), in sensor half-heights.
+    pub(crate) fn attenuation(
+        &self,
+        (image_x, image_y): (f64, f64),
+        (aperture_x, aperture_y): (f64, f64),
+        (sensor_x, sensor_y): (f64, f64),
+    ) -> f64 {
+        match self {
+            Self::None => 1.,
+            Self::Natural => (1. + image_x.powi(2) + image_y.powi(2)).powi(-2),
+            Self::Optical { strength } => {
+                // Aperture y points up, sensor y points down
+                let barrel_x = aperture_x - strength * sensor_x;
+                let barrel_y = aperture_y + strength * sensor_y;
+                if barrel_x.powi(2) + barrel_y.powi(2) > 1. {
+                    0.
+                } else {
+                    1.
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lens_test() {
+        let distortion = LensDistortion {
+            k1: -0.2,
+            k2: 0.05,
+            k3: 0.01,
+            p1: 0.001,
+            p2: -0.002,
+        };
+        let steps = (-4..=4).map(|step| f64::from(step) * 0.2);
+        for (x, y) in steps
+            .clone()
+            .flat_map(|x| steps.clone().map(move |y| (x, y)))
+        {
+            let (distorted_x, distorted_y) = distortion.distort(x, y);
+            let (undistorted_x, undistorted_y) = distortion.undistort(distorted_x, distorted_y);
+            assert!((undistorted_x - x).abs() < 1e-9 && (undistorted_y - y).abs() < 1e-9);
+        }
+        assert_eq!(LensDistortion::default().distort(0.3, -0.4), (0.3, -0.4));
+
+        // Every point of the aperture lets light through at the center of the sensor
+        for vignetting in [
+            Vignetting::None,
+            Vignetting::Natural,
+            Vignetting::Optical { strength: 1. },
+        ] {
+            for aperture in [(0., 0.), (1., 0.), (-0.6, 0.8)] {
+                assert_eq!(vignetting.attenuation((0., 0.), aperture, (0., 0.)), 1.);
+            }
+        }
+        // Towards the border the light falls off
+        assert_eq!(
+            Vignetting::Natural.attenuation((1., 0.), (0., 0.), (1., 0.)),
+            0.25
+        );
+        assert_eq!(
+            Vignetting::Optical { strength: 1. }.attenuation((1., 0.), (-0.5, 0.), (1., 0.)),
+            0.
+        );
+    }
+}
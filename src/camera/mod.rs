@@ -1,5 +1,21 @@
+mod accumulation;
+mod aperture;
 mod builder;
-pub use builder::*;
+mod checkpoint;
+mod description;
+mod exposure;
+mod filter;
+mod lens;
+mod progress;
+mod projection;
+mod region;
+mod stereo;
+pub use self::{
+    accumulation::*, aperture::*, builder::*, checkpoint::*, exposure::*, filter::*, lens::*,
+    progress::*, projection::*, region::*, stereo::*,
+};
+
+use std::{path::Path, time::Duration};
 
 use crate::{
     object_storage::ObjectStorage,
@@ -8,7 +24,7 @@ use crate::{
     Image,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// A Camera object representend by it's position in space `center`, the direction it's pointing towards `forward`,
 /// where its `up` is, and the dimensions of the sensor (represented by `image_width` and `image_height`).
 /// The number of samples that will be collected by each pixel is represented by `shutter_lenghth`. The focal length
@@ -22,9 +38,18 @@ pub struct Camera {
     sensor_height: usize,
     focal_distance: f64,
     aperture: f64,
+    aperture_shape: ApertureShape,
+    lens_distortion: LensDistortion,
+    vignetting: Vignetting,
     field_of_view: f64,
+    exposure: Option<PhysicalExposure>,
     shutter_length: usize,
     max_ray_depth: usize,
+    seed: u64,
+    reconstruction_filter: ReconstructionFilter,
+    projection: Projection,
+    eye_offset: f64,
+    convergence_distance: f64,
 }
 
 impl Camera {
@@ -39,76 +64,463 @@ impl Camera {
             sensor_height: usize::default(),
             focal_distance: f64::default(),
             aperture: f64::default(),
+            aperture_shape: ApertureShape::default(),
+            lens_distortion: LensDistortion::default(),
+            vignetting: Vignetting::default(),
             field_of_view: f64::default(),
+            exposure: None,
             shutter_length: usize::default(),
             max_ray_depth: usize::default(),
+            seed: u64::default(),
+            reconstruction_filter: ReconstructionFilter::default(),
+            projection: Projection::default(),
+            eye_offset: 0.,
+            convergence_distance: f64::INFINITY,
         })
     }
 
+    /// Width and height, in pixels, of the sensor.
+    pub fn get_sensor_dimensions(&self) -> (usize, usize) {
+        (self.sensor_width, self.sensor_height)
+    }
+
+    /// Exposure value normalized to ISO 100, if the Camera uses a physical exposure.
+    pub fn ev100(&self) -> Option<f64> {
+        self.exposure.map(|exposure| exposure.ev100())
+    }
+
     pub fn capture_image(&self, scene: &impl ObjectStorage) -> Image {
-        let mut buffer: Box<[Colour]> =
-            vec![Colour::default(); self.sensor_width * self.sensor_height].into_boxed_slice();
+        self.capture_image_with_observer(scene, &mut ())
+            .into_image()
+    }
+
+    /// Captures the whole sensor, reporting the progress to `observer` after each row.
+    pub fn capture_image_with_observer(
+        &self,
+        scene: &impl ObjectStorage,
+        observer: &mut impl RenderObserver,
+    ) -> Capture {
+        self.capture_region_with_observer(
+            scene,
+            SensorRegion::full(self.sensor_width, self.sensor_height),
+            observer,
+        )
+    }
+
+    /// Captures only the `region` of the sensor, returning an Image with the size of the region.
+    /// The region is clamped to the sensor.
+    pub fn capture_region(&self, scene: &impl ObjectStorage, region: SensorRegion) -> Image {
+        self.capture_region_with_observer(scene, region, &mut ())
+            .into_image()
+    }
+
+    /// Captures only the `region` of the sensor, reporting the progress to `observer` after each row.
+    /// The region is clamped to the sensor.
+    pub fn capture_region_with_observer(
+        &self,
+        scene: &impl ObjectStorage,
+        region: SensorRegion,
+        observer: &mut impl RenderObserver,
+    ) -> Capture {
+        let start_time = std::time::Instant::now();
+        let region = region.clamp_to(self.sensor_width, self.sensor_height);
+        let mut buffer = AccumulationBuffer::new(region.width, region.height);
+
+        for row in 0..region.height {
+            for column in 0..region.width {
+                self.capture_pixel(
+                    scene,
+                    &mut buffer,
+                    row * region.width + column,
+                    region.x + column,
+                    region.y + row,
+                );
+            }
+
+            let progress = RenderProgress {
+                rows_completed: row + 1,
+                total_rows: region.height,
+                elapsed: start_time.elapsed(),
+            };
+            if observer.on_progress(&progress) == RenderControl::Cancel {
+                return Capture::Cancelled(buffer.resolve());
+            }
+        }
+
+        Capture::Complete(buffer.resolve())
+    }
+
+    /// Captures the whole sensor like `capture_image_with_observer`, saving the progress to `checkpoint_path`
+    /// every `interval`, and when cancelled by the `observer`. If `checkpoint_path` already has a Checkpoint, the
+    /// capture continues from it. The Checkpoint is removed once the capture completes.
+    /// # Errors
+    /// Fails if the Checkpoint can't be read or written, or if it was saved with different settings.
+    pub fn capture_image_with_checkpoints(
+        &self,
+        scene: &impl ObjectStorage,
+        checkpoint_path: &Path,
+        interval: Duration,
+        observer: &mut impl RenderObserver,
+    ) -> std::io::Result<Capture> {
+        self.capture_region_with_checkpoints(
+            scene,
+            SensorRegion::full(self.sensor_width, self.sensor_height),
+            checkpoint_path,
+            interval,
+            observer,
+        )
+    }
 
-        let scale = 1.0 / self.shutter_length as f64;
+    /// Captures only the `region` of the sensor like `capture_image_with_checkpoints`.
+    /// # Errors
+    /// Same as `capture_image_with_checkpoints`.
+    pub fn capture_region_with_checkpoints(
+        &self,
+        scene: &impl ObjectStorage,
+        region: SensorRegion,
+        checkpoint_path: &Path,
+        interval: Duration,
+        observer: &mut impl RenderObserver,
+    ) -> std::io::Result<Capture> {
+        let start_time = std::time::Instant::now();
+        let region = region.clamp_to(self.sensor_width, self.sensor_height);
+        let mut buffer = match Checkpoint::load(checkpoint_path, region) {
+            Ok(checkpoint) => checkpoint.resume(self, scene.objects(), region)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                AccumulationBuffer::new(region.width, region.height)
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Samples are taken in passes over the whole region, so a Checkpoint is a
+        // progressive image with the same number of samples in every pixel
+        let first_pass = (0..region.area())
+            .map(|idx| buffer.sample_count(idx))
+            .min()
+            .unwrap_or(self.shutter_length);
+        let mut last_save = std::time::Instant::now();
+
+        for pass in first_pass..self.shutter_length {
+            for row in 0..region.height {
+                for column in 0..region.width {
+                    let idx = row * region.width + column;
+                    if buffer.sample_count(idx) <= pass {
+                        self.capture_sample(
+                            scene,
+                            &mut buffer,
+                            idx,
+                            region.x + column,
+                            region.y + row,
+                        );
+                    }
+                }
 
-        for (idx, pixel) in buffer.iter_mut().enumerate() {
-            let horizontal_pixel_id = idx % self.sensor_width;
-            let vertical_pixel_id = idx / self.sensor_width;
+                let progress = RenderProgress {
+                    rows_completed: (pass - first_pass) * region.height + row + 1,
+                    total_rows: (self.shutter_length - first_pass) * region.height,
+                    elapsed: start_time.elapsed(),
+                };
+                if observer.on_progress(&progress) == RenderControl::Cancel {
+                    let image = buffer.resolve();
+                    Checkpoint::new(self, scene.objects(), region, buffer).save(checkpoint_path)?;
+                    return Ok(Capture::Cancelled(image));
+                }
+            }
 
-            for _ in 0..self.shutter_length {
-                let pixel_ray = self.get_ray_for_pixel(horizontal_pixel_id, vertical_pixel_id);
-                *pixel += Self::trace_ray(pixel_ray.unit_ray(), scene, self.max_ray_depth);
+            if last_save.elapsed() >= interval && pass + 1 < self.shutter_length {
+                Checkpoint::new(self, scene.objects(), region, buffer.clone())
+                    .save(checkpoint_path)?;
+                last_save = std::time::Instant::now();
             }
         }
 
-        Image {
-            pixels: buffer
-                .iter()
-                .map(|color| (*color * scale).linear_to_gamma())
-                .collect(),
-            width: self.sensor_width,
-            height: self.sensor_height,
+        match std::fs::remove_file(checkpoint_path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(Capture::Complete(buffer.resolve())),
         }
     }
 
-    fn generate_jitter() -> (f64, f64) {
-        (
-            (rand::random::<f64>() * 2.) - 1.,
-            (rand::random::<f64>() * 2.) - 1.,
+    /// Captures the whole sensor in passes of one sample per pixel until `time_budget` is spent, ignoring
+    /// `shutter_length`. Each pixel is normalized by the samples it received, so the last pass may stop midway.
+    /// `observer` receives the progress after each row of each pass, with `total_rows` estimated from the speed
+    /// of the rows completed so far and never less than one pass.
+    /// # Note
+    /// The first pass always completes, so every pixel has at least one sample even if it exceeds `time_budget`.
+    pub fn capture_image_for(
+        &self,
+        scene: &impl ObjectStorage,
+        time_budget: Duration,
+        observer: &mut impl RenderObserver,
+    ) -> Capture {
+        self.capture_region_for(
+            scene,
+            SensorRegion::full(self.sensor_width, self.sensor_height),
+            time_budget,
+            observer,
         )
     }
 
-    fn get_ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        let apparent_aspect_ration = self.sensor_width as f64 / self.sensor_height as f64;
+    /// Captures only the `region` of the sensor like `capture_image_for`.
+    pub fn capture_region_for(
+        &self,
+        scene: &impl ObjectStorage,
+        region: SensorRegion,
+        time_budget: Duration,
+        observer: &mut impl RenderObserver,
+    ) -> Capture {
+        let start_time = std::time::Instant::now();
+        let region = region.clamp_to(self.sensor_width, self.sensor_height);
+        let mut buffer = AccumulationBuffer::new(region.width, region.height);
+        let mut rows_completed = 0;
+
+        for pass in 0.. {
+            for row in 0..region.height {
+                for column in 0..region.width {
+                    self.capture_sample(
+                        scene,
+                        &mut buffer,
+                        row * region.width + column,
+                        region.x + column,
+                        region.y + row,
+                    );
+                }
+                rows_completed += 1;
 
-        let vertical_field_of_view =
-            (self.field_of_view.to_radians() / 2.).tan() * self.focal_distance;
-        let horizontal_field_of_view = vertical_field_of_view * apparent_aspect_ration;
+                let elapsed = start_time.elapsed();
+                let out_of_time = elapsed >= time_budget && (pass > 0 || row + 1 == region.height);
+                let progress = RenderProgress {
+                    rows_completed,
+                    total_rows: if out_of_time {
+                        rows_completed
+                    } else {
+                        ((rows_completed as f64 * time_budget.as_secs_f64()
+                            / elapsed.as_secs_f64().max(f64::EPSILON))
+                            as usize)
+                            .max(rows_completed + 1)
+                            .max(region.height)
+                    },
+                    elapsed,
+                };
+                if observer.on_progress(&progress) == RenderControl::Cancel {
+                    return Capture::Cancelled(buffer.resolve());
+                }
+                if out_of_time {
+                    return Capture::Complete(buffer.resolve());
+                }
+            }
+            if region.height == 0 {
+                break;
+            }
+        }
 
-        let (jitter_x, jitter_y) = Self::generate_jitter();
-        let horizontal_offset =
-            (((x as f64 + 0.5 + jitter_x) / self.sensor_width as f64) * 2.) - 1.;
-        let vertical_offset = (((y as f64 + 0.5 + jitter_y) / self.sensor_height as f64) * 2.) - 1.;
+        Capture::Complete(buffer.resolve())
+    }
+
+    /// Takes all samples of the pixel (`x`, `y`) of the sensor, adding them to `buffer` at `idx`.
+    fn capture_pixel(
+        &self,
+        scene: &impl ObjectStorage,
+        buffer: &mut AccumulationBuffer,
+        idx: usize,
+        x: usize,
+        y: usize,
+    ) {
+        while buffer.sample_count(idx) < self.shutter_length {
+            self.capture_sample(scene, buffer, idx, x, y);
+        }
+    }
+
+    /// Takes the next sample of the pixel (`x`, `y`) of the sensor, adding it to `buffer` at `idx`.
+    fn capture_sample(
+        &self,
+        scene: &impl ObjectStorage,
+        buffer: &mut AccumulationBuffer,
+        idx: usize,
+        x: usize,
+        y: usize,
+    ) {
+        crate::random::seed(crate::random::mix_seed(&[
+            self.seed,
+            (y * self.sensor_width + x) as u64,
+            buffer.sample_count(idx) as u64,
+        ]));
+
+        let exposure_multiplier = self
+            .exposure
+            .map_or(1., |exposure| exposure.exposure_multiplier());
+        let (offset_x, offset_y) = self.reconstruction_filter.sample_offset();
+        let weight = self.reconstruction_filter.evaluate(offset_x, offset_y);
+        let colour = self
+            .get_ray_for_pixel(x as f64 + 0.5 + offset_x, y as f64 + 0.5 + offset_y)
+            .map_or(Colour::default(), |(pixel_ray, attenuation)| {
+                Self::trace_ray(pixel_ray.unit_ray(), scene, self.max_ray_depth) * attenuation
+            });
+        buffer.add_sample(idx, colour * exposure_multiplier, weight);
+    }
+
+    /// Captures only the `region` of the sensor, writing it over the same region of `image`.
+    /// The region is clamped to both the sensor and `image`.
+    pub fn capture_region_into(
+        &self,
+        scene: &impl ObjectStorage,
+        region: SensorRegion,
+        image: &mut Image,
+    ) {
+        let (width, height) = image.get_dimensions();
+        let region = region.clamp_to(width, height);
+        let captured = self.capture_region(scene, region);
+        image.paste(&captured, region.x, region.y);
+    }
+
+    /// Creates a Ray that passes through the point (`x`, `y`) of the sensor, in pixels, where (0, 0) is the
+    /// top-left corner of the sensor, and the fraction of light that reaches the sensor through it.
+    /// Returns `None` if the point is not covered by the projection.
+    fn get_ray_for_pixel(&self, x: f64, y: f64) -> Option<(Ray, f64)> {
+        let horizontal_offset = ((x / self.sensor_width as f64) * 2.) - 1.;
+        let vertical_offset = ((y / self.sensor_height as f64) * 2.) - 1.;
+
+        match self.projection {
+            Projection::Perspective => {
+                Some(self.perspective_ray(horizontal_offset, vertical_offset))
+            }
+            Projection::Orthographic { view_height } => Some((
+                self.orthographic_ray(horizontal_offset, vertical_offset, view_height),
+                1.,
+            )),
+            Projection::Fisheye { field_of_view } => self
+                .fisheye_ray(horizontal_offset, vertical_offset, field_of_view)
+                .map(|ray| (ray, 1.)),
+            Projection::Equirectangular => Some((
+                self.equirectangular_ray(horizontal_offset, vertical_offset),
+                1.,
+            )),
+        }
+    }
 
-        let (jitter_x, jitter_y) = Self::generate_jitter();
+    fn apparent_aspect_ratio(&self) -> f64 {
+        self.sensor_width as f64 / self.sensor_height as f64
+    }
+
+    fn perspective_ray(&self, horizontal_offset: f64, vertical_offset: f64) -> (Ray, f64) {
+        let half_view_tangent = (self.field_of_view.to_radians() / 2.).tan();
+        let aspect_ratio = self.apparent_aspect_ratio();
+
+        // Normalized image coordinates, x pointing right and y pointing down
+        let (image_x, image_y) = self.lens_distortion.undistort(
+            horizontal_offset * half_view_tangent * aspect_ratio,
+            vertical_offset * half_view_tangent,
+        );
+
+        let (aperture_x, aperture_y) = self.aperture_shape.sample();
         let aperture_source = self.center
-            + (self.up * self.aperture * jitter_y)
-            + (self.left * self.aperture * jitter_x);
+            + (self.up * self.aperture * aperture_y)
+            + (self.left * (self.eye_offset - self.aperture * aperture_x));
 
         // Adding `up` and `left` result on the top-left (0, 0) pixel,
         // so to iterate from top-left to bottom-right, the offsets calculated above
         // need to be flipped
         let viewport_target = self.center
             + (self.forward * self.focal_distance)
-            + (self.up * vertical_field_of_view * -vertical_offset)
-            + (self.left * horizontal_field_of_view * -horizontal_offset);
+            + (self.up * self.focal_distance * -image_y)
+            + (self.left * self.focal_distance * -image_x)
+            // Off-axis shift so both eyes see the same point at `convergence_distance`
+            + (self.left
+                * self.eye_offset
+                * (1. - self.focal_distance / self.convergence_distance));
+
+        let attenuation = self.vignetting.attenuation(
+            (image_x, image_y),
+            (aperture_x, aperture_y),
+            (horizontal_offset * aspect_ratio, vertical_offset),
+        );
 
-        Ray::new(
-            aperture_source,
-            aperture_source.point_towards(viewport_target).unit_vector(),
+        (
+            Ray::new(
+                aperture_source,
+                aperture_source.point_towards(viewport_target).unit_vector(),
+            ),
+            attenuation,
         )
     }
 
+    fn orthographic_ray(
+        &self,
+        horizontal_offset: f64,
+        vertical_offset: f64,
+        view_height: f64,
+    ) -> Ray {
+        let half_height = view_height / 2.;
+        let half_width = half_height * self.apparent_aspect_ratio();
+
+        // Same as the perspective projection, `up` and `left` point towards the top-left pixel
+        let source = self.center
+            + (self.up * half_height * -vertical_offset)
+            + (self.left * half_width * -horizontal_offset);
+
+        self.converge(Ray::new(source, self.forward), self.left * self.eye_offset)
+    }
+
+    fn fisheye_ray(
+        &self,
+        horizontal_offset: f64,
+        vertical_offset: f64,
+        field_of_view: f64,
+    ) -> Option<Ray> {
+        // Distances are measured in sensor heights so the image circle is round
+        let horizontal_offset = horizontal_offset * self.apparent_aspect_ratio();
+        let radius = (horizontal_offset.powi(2) + vertical_offset.powi(2)).sqrt();
+        if radius > 1. {
+            return None;
+        }
+
+        let theta = radius * field_of_view.to_radians() / 2.;
+        let sideways = if radius > 0. {
+            (self.left * -horizontal_offset + self.up * -vertical_offset) / radius
+        } else {
+            Direction::default()
+        };
+
+        Some(self.converge(
+            Ray::new(
+                self.center,
+                self.forward * theta.cos() + sideways * theta.sin(),
+            ),
+            self.left * self.eye_offset,
+        ))
+    }
+
+    fn equirectangular_ray(&self, horizontal_offset: f64, vertical_offset: f64) -> Ray {
+        let longitude = horizontal_offset * std::f64::consts::PI;
+        let latitude = -vertical_offset * std::f64::consts::FRAC_PI_2;
+
+        let horizontal = self.forward * longitude.cos() - self.left * longitude.sin();
+        // Omni-directional stereo, the eyes rotate around `center` following the longitude
+        let eye_direction = self.left * longitude.cos() + self.forward * longitude.sin();
+        self.converge(
+            Ray::new(
+                self.center,
+                horizontal * latitude.cos() + self.up * latitude.sin(),
+            ),
+            eye_direction * self.eye_offset,
+        )
+    }
+
+    /// Moves the source of a `ray` by `eye_shift`, keeping it pointed at the point the original ray
+    /// reaches at `convergence_distance`.
+    fn converge(&self, ray: Ray, eye_shift: Direction) -> Ray {
+        let source = *ray.origin() + eye_shift;
+        if self.convergence_distance.is_finite() {
+            Ray::new(
+                source,
+                source
+                    .point_towards(ray.at(self.convergence_distance))
+                    .unit_vector(),
+            )
+        } else {
+            Ray::new(source, *ray.direction())
+        }
+    }
+
     fn trace_ray(ray: Ray, scene: &impl ObjectStorage, depth: usize) -> Colour {
         if depth == 0 {
             Colour::default()
@@ -122,3 +534,157 @@ impl Camera {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::object::{Object, ObjectMaterial};
+
+    use super::*;
+
+    fn test_scene() -> Vec<Object> {
+        vec![
+            Object::new_sphere(
+                Point::new(0., -100.5, -1.),
+                100.,
+                ObjectMaterial::Lambert {
+                    albedo: Colour::new(0.5, 0.5, 0.5).into(),
+                },
+            ),
+            Object::new_sphere(
+                Point::new(0., 0., -1.),
+                0.5,
+                ObjectMaterial::Metal {
+                    albedo: Colour::new(0.8, 0.6, 0.2),
+                    fuzzy_scatter: 0.3,
+                },
+            ),
+        ]
+    }
+
+    fn test_camera(shutter_length: usize) -> Camera {
+        Camera::builder()
+            .look_at(
+                Point::new(0., 0., 1.),
+                Point::new(0., 0., -1.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(8, 6, shutter_length, 8)
+            .input_lens(2., 0.05, 60.)
+            .with_seed(3)
+            .build()
+    }
+
+    #[test]
+    fn capture_for_test() {
+        let scene = test_scene();
+        let region = SensorRegion::new(2, 1, 5, 4);
+        let one_sample = test_camera(1).capture_region(&scene, region);
+        let two_samples = test_camera(2).capture_region(&scene, region);
+
+        // A zero budget still completes one full pass
+        let mut last_progress = None;
+        let capture = test_camera(100).capture_region_for(
+            &scene,
+            region,
+            Duration::ZERO,
+            &mut |progress: &RenderProgress| {
+                last_progress = Some(*progress);
+                RenderControl::Continue
+            },
+        );
+        assert!(capture.is_complete());
+        assert_eq!(capture.into_image().get_pixels(), one_sample.get_pixels());
+        let last_progress = last_progress.expect("Expect progress reported");
+        assert_eq!(
+            (last_progress.rows_completed, last_progress.total_rows),
+            (4, 4)
+        );
+
+        // Each pixel is normalized by its own samples when a pass stops midway
+        let capture = test_camera(100).capture_region_for(
+            &scene,
+            region,
+            Duration::from_secs(3600),
+            &mut |progress: &RenderProgress| {
+                if progress.rows_completed == 4 + 2 {
+                    RenderControl::Cancel
+                } else {
+                    RenderControl::Continue
+                }
+            },
+        );
+        let image = capture.into_image();
+        let (two_rows, one_row) = image.get_pixels().split_at(2 * 5);
+        assert_eq!(two_rows, &two_samples.get_pixels()[..2 * 5]);
+        assert_eq!(one_row, &one_sample.get_pixels()[2 * 5..]);
+    }
+
+    #[test]
+    fn capture_region_test() {
+        let scene = test_scene();
+        let camera = test_camera(4);
+        let full = camera.capture_image(&scene);
+
+        // A region is the same crop of a full capture
+        let region = SensorRegion::new(2, 1, 5, 4);
+        let crop = camera.capture_region(&scene, region);
+        assert_eq!(crop.get_dimensions(), (5, 4));
+        for row in 0..4 {
+            let start = (region.y + row) * 8 + region.x;
+            assert_eq!(
+                &crop.get_pixels()[row * 5..(row + 1) * 5],
+                &full.get_pixels()[start..start + 5]
+            );
+        }
+
+        // Pixels outside the region are left untouched
+        let marker = Colour::new(-1., -1., -1.);
+        let mut image = Image::new(8, 6);
+        image.get_pixels_mut().fill(marker);
+        camera.capture_region_into(&scene, region, &mut image);
+        for (idx, pixel) in image.get_pixels().iter().enumerate() {
+            let (x, y) = (idx % 8, idx / 8);
+            let inside = (2..7).contains(&x) && (1..5).contains(&y);
+            assert_eq!(*pixel == marker, !inside);
+            if inside {
+                assert_eq!(pixel, &full.get_pixels()[idx]);
+            }
+        }
+
+        // Pasted pixels that fall outside the image are dropped
+        let mut image = Image::new(8, 6);
+        image.paste(&crop, 7, 5);
+        assert_eq!(image.get_pixels()[47], crop.get_pixels()[0]);
+        assert!(image.get_pixels()[..47]
+            .iter()
+            .all(|pixel| *pixel == Colour::default()));
+    }
+
+    #[test]
+    fn clamp_region_test() {
+        assert_eq!(
+            SensorRegion::new(2, 1, 5, 4).clamp_to(8, 6),
+            SensorRegion::new(2, 1, 5, 4)
+        );
+        // Partly outside
+        assert_eq!(
+            SensorRegion::new(6, 4, 5, 4).clamp_to(8, 6),
+            SensorRegion::new(6, 4, 2, 2)
+        );
+        assert_eq!(
+            SensorRegion::new(0, 0, usize::MAX, usize::MAX).clamp_to(8, 6),
+            SensorRegion::full(8, 6)
+        );
+        // Fully outside gives an empty region
+        let outside = SensorRegion::new(10, 9, 3, 3).clamp_to(8, 6);
+        assert_eq!(outside, SensorRegion::new(8, 6, 0, 0));
+        assert_eq!(outside.area(), 0);
+        assert_eq!(
+            test_camera(1)
+                .capture_region(&test_scene(), outside)
+                .get_dimensions(),
+            (0, 0)
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use crate::Image;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Progress of a capture, reported after each row of the sensor is completed.
+pub struct RenderProgress {
+    pub rows_completed: usize,
+    pub total_rows: usize,
+    pub elapsed: Duration,
+}
+
+impl RenderProgress {
+    /// Fraction of the rows completed, between [0.0..1.0].
+    pub fn fraction(&self) -> f64 {
+        if self.total_rows == 0 {
+            1.
+        } else {
+            self.rows_completed as f64 / self.total_rows as f64
+        }
+    }
+
+    /// Estimated time until the capture completes, assuming the remaining rows take as long as the completed ones.
+    /// Returns `None` before the first row completes.
+    pub fn estimated_remaining(&self) -> Option<Duration> {
+        (self.rows_completed > 0).then(|| {
+            self.elapsed.mul_f64(
+                self.total_rows.saturating_sub(self.rows_completed) as f64
+                    / self.rows_completed as f64,
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderControl {
+    Continue,
+    Cancel,
+}
+
+/// Receives the progress of a capture, and decides if the capture should continue.
+pub trait RenderObserver {
+    fn on_progress(&mut self, progress: &RenderProgress) -> RenderControl;
+}
+
+impl RenderObserver for () {
+    fn on_progress(&mut self, _progress: &RenderProgress) -> RenderControl {
+        RenderControl::Continue
+    }
+}
+
+impl<F: FnMut(&RenderProgress) -> RenderControl> RenderObserver for F {
+    fn on_progress(&mut self, progress: &RenderProgress) -> RenderControl {
+        self(progress)
+    }
+}
+
+/// Result of a capture that can be cancelled by a [`RenderObserver`].
+pub enum Capture {
+    Complete(Image),
+    /// The capture was cancelled. Each pixel is normalized by the samples it received so far, so captures that
+    /// sample row by row leave the rows not reached black, while captures in passes give a noisier image.
+    Cancelled(Image),
+}
+
+impl Capture {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete(_))
+    }
+
+    pub fn into_image(self) -> Image {
+        match self {
+            Self::Complete(image) | Self::Cancelled(image) => image,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        camera::Camera,
+        object::Object,
+        vector::{Colour, Direction, Point},
+    };
+
+    use super::*;
+
+    #[test]
+    fn render_progress_test() {
+        let camera = Camera::builder()
+            .look_at(
+                Point::new(0., 0., 1.),
+                Point::new(0., 0., -1.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(4, 6, 1, 2)
+            .input_lens(2., 0., 60.)
+            .build();
+        let scene: Vec<Object> = Vec::new();
+
+        let mut reported = Vec::new();
+        let capture =
+            camera.capture_image_with_observer(&scene, &mut |progress: &RenderProgress| {
+                reported.push(*progress);
+                RenderControl::Continue
+            });
+        assert!(capture.is_complete());
+        assert_eq!(
+            reported
+                .iter()
+                .map(|progress| (progress.rows_completed, progress.total_rows))
+                .collect::<Vec<_>>(),
+            (1..=6).map(|row| (row, 6)).collect::<Vec<_>>()
+        );
+        assert!(reported
+            .windows(2)
+            .all(|pair| pair[0].elapsed <= pair[1].elapsed
+                && pair[0].fraction() < pair[1].fraction()));
+
+        // Rows after the cancellation are not captured
+        let mut calls = 0;
+        let capture =
+            camera.capture_image_with_observer(&scene, &mut |progress: &RenderProgress| {
+                calls += 1;
+                if progress.rows_completed == 2 {
+                    RenderControl::Cancel
+                } else {
+                    RenderControl::Continue
+                }
+            });
+        assert_eq!(calls, 2);
+        assert!(!capture.is_complete());
+        let image = capture.into_image();
+        assert_eq!(image.get_dimensions(), (4, 6));
+        let (captured, skipped) = image.get_pixels().split_at(2 * 4);
+        assert!(captured.iter().all(|pixel| *pixel != Colour::default()));
+        assert!(skipped.iter().all(|pixel| *pixel == Colour::default()));
+
+        let overshoot = RenderProgress {
+            rows_completed: 12,
+            total_rows: 6,
+            elapsed: Duration::from_secs(1),
+        };
+        assert_eq!(overshoot.estimated_remaining(), Some(Duration::ZERO));
+    }
+}
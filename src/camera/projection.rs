@@ -0,0 +1,77 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// How the points of the sensor are mapped into rays leaving the camera.
+pub enum Projection {
+    /// Thin-lens perspective projection, using the `focal_distance`, `aperture`, and `field_of_view`
+    /// of the Camera.
+    #[default]
+    Perspective,
+    /// Parallel rays leaving a rectangle `view_height` tall, in world units, centered on the camera.
+    Orthographic { view_height: f64 },
+    /// Equidistant fisheye, the angle of a ray to `forward` grows linearly with the distance to the center of the
+    /// sensor. `field_of_view` is the angle covered by the height of the sensor, the area outside the image circle
+    /// is left black.
+    Fisheye { field_of_view: f64 },
+    /// 360° panorama, the width of the sensor covers all longitudes and the height covers all latitudes.
+    Equirectangular,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        camera::Camera,
+        vector::{Direction, Point},
+    };
+
+    #[test]
+    fn projection_test() {
+        let (width, height) = (200., 100.);
+        let builder = || {
+            Camera::builder()
+                .look_at(
+                    Point::new(0., 0., 0.),
+                    Point::new(0., 0., -1.),
+                    Direction::new(0., 1., 0.),
+                )
+                .expect("Expect valid camera position")
+                .input_sensor(width as usize, height as usize, 1, 1)
+        };
+        let (forward, up) = (Direction::new(0., 0., -1.), Direction::new(0., 1., 0.));
+        let direction = |camera: &Camera, x: f64, y: f64| {
+            camera
+                .get_ray_for_pixel(x, y)
+                .map(|(ray, _)| *ray.direction())
+        };
+        let close = |lhs: Direction, rhs: Direction| (lhs - rhs).length() < 1e-12;
+
+        let cameras = [
+            builder().input_lens(1., 0., 90.).build(),
+            builder().input_orthographic(2.).build(),
+            builder().input_fisheye(120.).build(),
+            builder().input_equirectangular().build(),
+        ];
+        for camera in &cameras {
+            let center = direction(camera, width / 2., height / 2.).expect("Expect center ray");
+            assert!(close(center, forward), "{:?}", camera.projection);
+        }
+
+        // Orthographic rays are parallel, leaving from the whole view
+        let (ray, _) = cameras[1]
+            .get_ray_for_pixel(0., 0.)
+            .expect("Expect corner ray");
+        assert!(close(*ray.direction(), forward));
+        assert_eq!(*ray.origin(), Point::new(-2., 1., 0.));
+
+        // The top edge of the fisheye is at half of the field of view, and the corners are outside the circle
+        let edge = direction(&cameras[2], width / 2., 0.).expect("Expect edge ray");
+        assert!((edge.dot(forward) - 60f64.to_radians().cos()).abs() < 1e-12);
+        assert!(edge.dot(up) > 0.);
+        assert!(direction(&cameras[2], 0., 0.).is_none());
+
+        // The panorama wraps around at the sides, and its top looks straight up
+        let first = direction(&cameras[3], 0., 10.).expect("Expect first column ray");
+        let last = direction(&cameras[3], width, 10.).expect("Expect last column ray");
+        assert!(close(first, last));
+        let top = direction(&cameras[3], width / 3., 0.).expect("Expect top ray");
+        assert!(close(top, up));
+    }
+}
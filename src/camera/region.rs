@@ -0,0 +1,43 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A rectangle of the sensor, in pixels, starting at the top-left pixel (`x`, `y`).
+pub struct SensorRegion {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl SensorRegion {
+    #[must_use]
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Creates a SensorRegion that covers a whole sensor of `width` by `height`.
+    #[must_use]
+    pub fn full(width: usize, height: usize) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    /// Shrinks the region so it fits inside a sensor of `width` by `height`.
+    #[must_use]
+    pub fn clamp_to(self, width: usize, height: usize) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+}
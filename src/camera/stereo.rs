@@ -0,0 +1,144 @@
+use crate::{object_storage::ObjectStorage, Image};
+
+use super::Camera;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How the images of both eyes are packed into a single [`Image`].
+pub enum StereoLayout {
+    /// Left eye on the left half, right eye on the right half.
+    SideBySide,
+    /// Left eye on the top half, right eye on the bottom half.
+    TopBottom,
+}
+
+#[derive(Debug, Clone)]
+/// A pair of eyes built around a Camera, each eye is displaced by half the `interpupillary_distance`
+/// along the `left` of the Camera. Both eyes see the same point at `convergence_distance`,
+/// `f64::INFINITY` makes the eyes parallel.
+/// # Note
+/// An equirectangular Camera produces omni-directional stereo, where the eyes rotate around the Camera
+/// center following the longitude of each pixel.
+pub struct StereoRig {
+    camera: Camera,
+    interpupillary_distance: f64,
+    convergence_distance: f64,
+    layout: StereoLayout,
+}
+
+impl StereoRig {
+    /// Creates a StereoRig</br>
+    /// # Parameters
+    /// `camera`: Camera at the middle of both eyes</br>
+    /// `interpupillary_distance`: Distance between the eyes, in world units</br>
+    /// `convergence_distance`: Distance where the view of both eyes meet, objects at this distance
+    /// appear at the screen plane</br>
+    /// `layout`: How both eyes are packed in the captured image</br>
+    pub fn new(
+        camera: Camera,
+        interpupillary_distance: f64,
+        convergence_distance: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        Self {
+            camera,
+            interpupillary_distance: interpupillary_distance.max(0.),
+            convergence_distance: convergence_distance.max(f64::EPSILON),
+            layout,
+        }
+    }
+
+    /// Creates the Cameras of the left and right eyes.
+    pub fn eye_cameras(&self) -> (Camera, Camera) {
+        let eye = |eye_offset: f64| Camera {
+            eye_offset,
+            convergence_distance: self.convergence_distance,
+            ..self.camera.clone()
+        };
+        (
+            eye(self.interpupillary_distance / 2.),
+            eye(-self.interpupillary_distance / 2.),
+        )
+    }
+
+    /// Captures both eyes and packs them following the layout of the StereoRig.
+    pub fn capture_image(&self, scene: &impl ObjectStorage) -> Image {
+        let (left_camera, right_camera) = self.eye_cameras();
+        let left_image = left_camera.capture_image(scene);
+        let right_image = right_camera.capture_image(scene);
+
+        let (eye_width, eye_height) = left_image.get_dimensions();
+        let (right_x, right_y) = match self.layout {
+            StereoLayout::SideBySide => (eye_width, 0),
+            StereoLayout::TopBottom => (0, eye_height),
+        };
+        let mut image = Image::new(eye_width + right_x, eye_height + right_y);
+        image.paste(&left_image, 0, 0);
+        image.paste(&right_image, right_x, right_y);
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        object::{Object, ObjectMaterial},
+        vector::{Colour, Direction, Point},
+    };
+
+    use super::*;
+
+    #[test]
+    fn stereo_rig_test() {
+        let camera = |width, height| {
+            Camera::builder()
+                .look_at(
+                    Point::new(0., 0., 1.),
+                    Point::new(0., 0., -1.),
+                    Direction::new(0., 1., 0.),
+                )
+                .expect("Expect valid camera position")
+                .input_sensor(width, height, 2, 4)
+                .input_lens(2., 0., 60.)
+                .build()
+        };
+        let scene = vec![Object::new_sphere(
+            Point::new(0.2, 0., -0.5),
+            0.3,
+            ObjectMaterial::Lambert {
+                albedo: Colour::new(0.8, 0.3, 0.3).into(),
+            },
+        )];
+
+        let rig = StereoRig::new(camera(6, 4), 0.5, 3., StereoLayout::SideBySide);
+        let (left, right) = rig.eye_cameras();
+        assert_eq!((left.eye_offset, right.eye_offset), (0.25, -0.25));
+        assert_eq!(left.convergence_distance, 3.);
+        assert_eq!(right.convergence_distance, 3.);
+        let left_image = left.capture_image(&scene);
+        let right_image = right.capture_image(&scene);
+        assert_ne!(left_image.get_pixels(), right_image.get_pixels());
+
+        for layout in [StereoLayout::SideBySide, StereoLayout::TopBottom] {
+            let image = StereoRig {
+                layout,
+                ..rig.clone()
+            }
+            .capture_image(&scene);
+            let (right_x, right_y) = match layout {
+                StereoLayout::SideBySide => (6, 0),
+                StereoLayout::TopBottom => (0, 4),
+            };
+            assert_eq!(image.get_dimensions(), (6 + right_x, 4 + right_y));
+            let mut expected = Image::new(6 + right_x, 4 + right_y);
+            expected.paste(&left_image, 0, 0);
+            expected.paste(&right_image, right_x, right_y);
+            assert_eq!(image.get_pixels(), expected.get_pixels());
+
+            // Empty sensors give empty images instead of panicking
+            for (width, height) in [(0, 4), (6, 0)] {
+                let empty = StereoRig::new(camera(width, height), 0.5, 3., layout);
+                assert!(empty.capture_image(&scene).get_pixels().is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    camera::Camera,
+    object::Object,
+    vector::{Colour, Direction, Point},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Error reading a scene description, with the line where it happened.
+pub struct DescriptionError {
+    line: usize,
+    message: String,
+}
+
+impl DescriptionError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for DescriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DescriptionError {}
+
+/// Whitespace separated values of one line of a scene description.
+pub(crate) struct Tokens<'a> {
+    tokens: std::str::SplitWhitespace<'a>,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(text: &'a str, line: usize) -> Self {
+        Self {
+            tokens: text.split_whitespace(),
+            line,
+        }
+    }
+
+    pub(crate) fn error(&self, message: impl Into<String>) -> DescriptionError {
+        DescriptionError::new(self.line, message)
+    }
+
+    pub(crate) fn word(&mut self) -> Result<&'a str, DescriptionError> {
+        self.tokens
+            .next()
+            .ok_or_else(|| self.error("line ended before all values were read"))
+    }
+
+    /// Number of words left on the line, to check counts read from the line before allocating for them.
+    pub(crate) fn remaining(&self) -> usize {
+        self.tokens.clone().count()
+    }
+
+    /// Reads the next word, if the line has one.
+    pub(crate) fn optional_word(&mut self) -> Option<&'a str> {
+        self.tokens.next()
+    }
+
+    pub(crate) fn value<T: std::str::FromStr>(&mut self) -> Result<T, DescriptionError> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| self.error(format!("`{word}` is not a valid value")))
+    }
+
+    /// Reads a word that chooses between variants, failing with the unknown word.
+    pub(crate) fn unknown_variant<T>(&self, kind: &str, word: &str) -> Result<T, DescriptionError> {
+        Err(self.error(format!("`{word}` is not a known {kind}")))
+    }
+
+    fn finish(mut self) -> Result<(), DescriptionError> {
+        match self.tokens.next() {
+            Some(word) => Err(self.error(format!("unexpected value `{word}`"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Types that can be written into, and read back from, a scene description.
+/// Floating point values are written with the shortest representation that reads back to the same value, so
+/// a description reproduces the scene exactly.
+pub(crate) trait Describe: Sized {
+    fn describe(&self, description: &mut Vec<String>);
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError>;
+}
+
+macro_rules! describe_with_display {
+    ($($type_name:ty),*) => {
+        $(
+            impl Describe for $type_name {
+                fn describe(&self, description: &mut Vec<String>) {
+                    description.push(self.to_string());
+                }
+
+                fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+                    tokens.value()
+                }
+            }
+        )*
+    };
+}
+
+describe_with_display!(f64, usize, u32, u64);
+
+macro_rules! describe_vector {
+    ($($type_name:ident),*) => {
+        $(
+            impl Describe for $type_name {
+                fn describe(&self, description: &mut Vec<String>) {
+                    self[0].describe(description);
+                    self[1].describe(description);
+                    self[2].describe(description);
+                }
+
+                fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+                    Ok(Self::new(tokens.value()?, tokens.value()?, tokens.value()?))
+                }
+            }
+        )*
+    };
+}
+
+describe_vector!(Point, Direction, Colour);
+
+impl<A: Describe, B: Describe> Describe for (A, B) {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.0.describe(description);
+        self.1.describe(description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok((A::read(tokens)?, B::read(tokens)?))
+    }
+}
+
+impl<T: Describe, const N: usize> Describe for [T; N] {
+    fn describe(&self, description: &mut Vec<String>) {
+        for item in self {
+            item.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let items = (0..N)
+            .map(|_| T::read(tokens))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(items
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("Exactly N items were read")))
+    }
+}
+
+impl<T: Describe> Describe for Vec<T> {
+    fn describe(&self, description: &mut Vec<String>) {
+        describe_list(self, description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        read_list(tokens)
+    }
+}
+
+/// Writes the number of `items` followed by the items.
+pub(crate) fn describe_list<T: Describe>(items: &[T], description: &mut Vec<String>) {
+    items.len().describe(description);
+    for item in items {
+        item.describe(description);
+    }
+}
+
+/// Reads a list written by [`describe_list`].
+pub(crate) fn read_list<T: Describe>(tokens: &mut Tokens) -> Result<Vec<T>, DescriptionError> {
+    let count: usize = tokens.value()?;
+    (0..count).map(|_| T::read(tokens)).collect()
+}
+
+/// Writes `camera` and `objects` as text, one line for the camera and one line for each object.
+pub fn describe_scene(camera: &Camera, objects: &[Object]) -> String {
+    std::iter::once(("camera", describe_item(camera)))
+        .chain(
+            objects
+                .iter()
+                .map(|object| ("object", describe_item(object))),
+        )
+        .map(|(kind, values)| format!("{kind} {values}\n"))
+        .collect()
+}
+
+/// Reads a scene written by [`describe_scene`]. Empty lines and lines starting with `#` are ignored.
+/// # Errors
+/// Fails if a line is malformed, or if there is not exactly one camera.
+pub fn read_scene(description: &str) -> Result<(Camera, Vec<Object>), DescriptionError> {
+    let mut camera = None;
+    let mut objects = Vec::new();
+
+    for (line_index, line) in description.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut tokens = Tokens::new(line, line_index + 1);
+        match tokens.word()? {
+            "camera" if camera.is_some() => return Err(tokens.error("more than one camera")),
+            "camera" => camera = Some(Camera::read(&mut tokens)?),
+            "object" => objects.push(Object::read(&mut tokens)?),
+            word => return tokens.unknown_variant("scene item", word),
+        }
+        tokens.finish()?;
+    }
+
+    camera
+        .map(|camera| (camera, objects))
+        .ok_or_else(|| DescriptionError::new(description.lines().count(), "missing camera"))
+}
+
+pub(crate) fn describe_item(item: &impl Describe) -> String {
+    let mut description = Vec::new();
+    item.describe(&mut description);
+    description.join(" ")
+}
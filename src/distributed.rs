@@ -0,0 +1,549 @@
+use std::{
+    collections::VecDeque,
+    io::{BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    camera::{Camera, Capture, RenderControl, RenderObserver, RenderProgress, SensorRegion},
+    description::{describe_scene, read_scene},
+    object::Object,
+    vector::Colour,
+    Image,
+};
+
+/// Coordinator to worker, the scene description as text.
+const MESSAGE_SCENE: u8 = 0;
+/// Coordinator to worker, the region of the sensor to capture.
+const MESSAGE_TILE: u8 = 1;
+/// Coordinator to worker, no more tiles will be sent.
+const MESSAGE_DONE: u8 = 2;
+/// Worker to coordinator, the region and the pixels of a captured tile.
+const MESSAGE_TILE_RESULT: u8 = 3;
+/// Longest scene description a worker accepts. Together with the limits on the sensor size checked when the
+/// Camera is read and on the tile size, a broken coordinator cannot exhaust the memory of a worker.
+const MAX_SCENE_LENGTH: u64 = 1 << 26;
+/// Longest side of a tile, in pixels. Larger tiles given to `capture_image_distributed` are split.
+const MAX_TILE_SIZE: usize = 1024;
+/// Length of a region, its position and size as four `u64`.
+const REGION_LENGTH: u64 = 32;
+/// Length of a pixel in a tile result, three `f64` channels.
+const PIXEL_LENGTH: u64 = 24;
+
+/// Captures `objects` with `camera` by splitting the sensor in tiles of `tile_size` by `tile_size` pixels, and
+/// sending them to the `workers`, which must be running [`work_for_coordinator`]. Tiles are handed out as workers
+/// finish their previous tile, and the tile of a worker that fails, or that takes longer than `timeout` to connect
+/// or to return a tile, is given to another worker.</br>
+/// `tile_size` is clamped between 1 and 1024.</br>
+/// `observer` receives the progress each time a tile completes, with `rows_completed` counting the pixels completed
+/// in rows of the sensor.
+/// # Errors
+/// Fails if every worker fails before all tiles are captured, or before connecting to any worker if `objects`
+/// have custom geometry, which cannot be sent to the workers.
+pub fn capture_image_distributed(
+    camera: &Camera,
+    objects: &[Object],
+    workers: &[SocketAddr],
+    tile_size: usize,
+    timeout: Duration,
+    observer: &mut impl RenderObserver,
+) -> std::io::Result<Capture> {
+    if objects.iter().any(Object::has_custom_geometry) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "custom geometry cannot be sent to workers",
+        ));
+    }
+    let start_time = std::time::Instant::now();
+    let (width, height) = camera.get_sensor_dimensions();
+    let scene = describe_scene(camera, objects);
+
+    let tile_size = tile_size.clamp(1, MAX_TILE_SIZE);
+    let tiles = Mutex::new(
+        (0..height)
+            .step_by(tile_size)
+            .flat_map(|y| {
+                (0..width)
+                    .step_by(tile_size)
+                    .map(move |x| SensorRegion::new(x, y, tile_size, tile_size))
+            })
+            .map(|tile| tile.clamp_to(width, height))
+            .collect::<VecDeque<_>>(),
+    );
+    let total_area = width * height;
+    let pending_tiles = AtomicUsize::new(tiles.lock().expect("Expect tile queue").len());
+    let cancelled = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for worker in workers {
+            let sender = sender.clone();
+            let (scene, tiles, pending_tiles, cancelled) =
+                (&scene, &tiles, &pending_tiles, &cancelled);
+            scope.spawn(move || {
+                let queue = TileQueue {
+                    tiles,
+                    pending_tiles,
+                    cancelled,
+                };
+                if let Err(err) = coordinate_worker(worker, scene, timeout, &queue, &sender) {
+                    // The error is only reported if no worker is left to finish the capture
+                    let _ = sender.send(Err(err));
+                }
+            });
+        }
+        drop(sender);
+
+        let mut image = Image::new(width, height);
+        let mut completed_area = 0;
+        let mut last_error = None;
+        for result in receiver {
+            match result {
+                Ok((tile_region, tile_image)) => {
+                    image.paste(&tile_image, tile_region.x, tile_region.y);
+                    completed_area += tile_region.area();
+
+                    let progress = RenderProgress {
+                        rows_completed: completed_area / width.max(1),
+                        total_rows: height,
+                        elapsed: start_time.elapsed(),
+                    };
+                    if observer.on_progress(&progress) == RenderControl::Cancel {
+                        cancelled.store(true, Ordering::Relaxed);
+                    }
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        if completed_area == total_area {
+            Ok(Capture::Complete(image))
+        } else if cancelled.load(Ordering::Relaxed) {
+            Ok(Capture::Cancelled(image))
+        } else {
+            Err(last_error.unwrap_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotConnected, "no worker was available")
+            }))
+        }
+    })
+}
+
+/// Tiles shared by the threads talking to the workers.
+struct TileQueue<'a> {
+    tiles: &'a Mutex<VecDeque<SensorRegion>>,
+    /// Tiles that are either queued or being captured by a worker.
+    pending_tiles: &'a AtomicUsize,
+    cancelled: &'a AtomicBool,
+}
+
+impl TileQueue<'_> {
+    /// Takes the next tile. When the queue is empty but other workers still have tiles, waits in case
+    /// one of them fails and its tile returns to the queue.
+    fn next_tile(&self) -> Option<SensorRegion> {
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Some(tile) = self
+                .tiles
+                .lock()
+                .expect("Expect tile queue not poisoned")
+                .pop_front()
+            {
+                return Some(tile);
+            }
+            if self.pending_tiles.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    fn complete_tile(&self) {
+        self.pending_tiles.fetch_sub(1, Ordering::Release);
+    }
+
+    fn return_tile(&self, tile: SensorRegion) {
+        self.tiles
+            .lock()
+            .expect("Expect tile queue not poisoned")
+            .push_back(tile);
+    }
+}
+
+/// Sends `scene` to `worker` and then tiles until there are no tiles left, returning the tile in progress
+/// to the queue on failure, or if connecting, sending, or receiving takes longer than `timeout`.
+fn coordinate_worker(
+    worker: &SocketAddr,
+    scene: &str,
+    timeout: Duration,
+    queue: &TileQueue,
+    results: &mpsc::Sender<std::io::Result<(SensorRegion, Image)>>,
+) -> std::io::Result<()> {
+    let stream = TcpStream::connect_timeout(worker, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    write_message(&mut writer, MESSAGE_SCENE, scene.as_bytes())?;
+
+    while let Some(tile) = queue.next_tile() {
+        let result = write_message(&mut writer, MESSAGE_TILE, &region_to_bytes(tile))
+            .and_then(|_| read_tile_result(&mut reader, tile));
+        match result {
+            Ok((region, image)) if region == tile => {
+                queue.complete_tile();
+                // The coordinator may have finished after a cancellation
+                let _ = results.send(Ok((region, image)));
+            }
+            Ok(_) => {
+                queue.return_tile(tile);
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "worker returned a different tile",
+                ));
+            }
+            Err(err) => {
+                queue.return_tile(tile);
+                return Err(err);
+            }
+        }
+    }
+
+    write_message(&mut writer, MESSAGE_DONE, &[])
+}
+
+/// Serves a coordinator connected through `stream`, capturing the tiles it sends until it is done.
+/// Tiles are clamped to the sensor, and the region actually captured is sent back.
+/// # Errors
+/// Fails if the connection fails or the coordinator sends an invalid message, including a Camera with an invalid
+/// sensor or lens, or a tile with a side longer than 1024 pixels.
+pub fn work_for_coordinator(stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let mut scene = None;
+
+    loop {
+        let (kind, payload) = read_message(&mut reader, |kind| match kind {
+            MESSAGE_SCENE => MAX_SCENE_LENGTH,
+            MESSAGE_TILE => REGION_LENGTH,
+            _ => 0,
+        })?;
+        match (kind, &scene) {
+            (MESSAGE_SCENE, _) => {
+                let description = String::from_utf8(payload)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+                scene =
+                    Some(read_scene(&description).map_err(|err| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+                    })?);
+            }
+            (MESSAGE_TILE, Some((camera, objects))) => {
+                let region = region_from_bytes(&payload)?;
+                if region.width > MAX_TILE_SIZE || region.height > MAX_TILE_SIZE {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "tile is too large",
+                    ));
+                }
+                let (width, height) = camera.get_sensor_dimensions();
+                let region = region.clamp_to(width, height);
+                let image = camera.capture_region(objects, region);
+                write_tile_result(&mut writer, region, &image)?;
+            }
+            (MESSAGE_DONE, _) => return Ok(()),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "unexpected message from coordinator",
+                ))
+            }
+        }
+    }
+}
+
+fn write_message(writer: &mut impl Write, kind: u8, payload: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&[kind])?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads a message, failing before reading its payload if it is longer than `max_length` gives for its kind.
+fn read_message(
+    reader: &mut impl Read,
+    max_length: impl Fn(u8) -> u64,
+) -> std::io::Result<(u8, Vec<u8>)> {
+    let mut kind = [0];
+    reader.read_exact(&mut kind)?;
+    let mut length = [0; 8];
+    reader.read_exact(&mut length)?;
+    let length = u64::from_le_bytes(length);
+    if length > max_length(kind[0]) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "message is too long for its kind",
+        ));
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+    Ok((kind[0], payload))
+}
+
+fn write_tile_result(
+    writer: &mut impl Write,
+    region: SensorRegion,
+    image: &Image,
+) -> std::io::Result<()> {
+    let mut payload = region_to_bytes(region).to_vec();
+    for pixel in image.get_pixels() {
+        for channel in 0..3 {
+            payload.extend_from_slice(&pixel[channel].to_le_bytes());
+        }
+    }
+    write_message(writer, MESSAGE_TILE_RESULT, &payload)
+}
+
+/// Reads the result of the `requested` tile, which can be no longer than the pixels of that tile.
+fn read_tile_result(
+    reader: &mut impl Read,
+    requested: SensorRegion,
+) -> std::io::Result<(SensorRegion, Image)> {
+    let (kind, payload) = read_message(reader, |kind| match kind {
+        MESSAGE_TILE_RESULT => (requested.area() as u64)
+            .checked_mul(PIXEL_LENGTH)
+            .and_then(|length| length.checked_add(REGION_LENGTH))
+            .unwrap_or(u64::MAX),
+        _ => 0,
+    })?;
+    if kind != MESSAGE_TILE_RESULT {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "unexpected message from worker",
+        ));
+    }
+
+    let region = region_from_bytes(&payload)?;
+    let channels = payload[REGION_LENGTH as usize..]
+        .chunks_exact(8)
+        .map(|bytes| f64::from_le_bytes(bytes.try_into().expect("Expect 8 byte chunks")))
+        .collect::<Vec<_>>();
+    if channels.len() != region.area() * 3 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "tile has the wrong number of pixels",
+        ));
+    }
+
+    let mut image = Image::new(region.width, region.height);
+    for (pixel, channels) in image.get_pixels_mut().iter_mut().zip(channels.chunks(3)) {
+        *pixel = Colour::new(channels[0], channels[1], channels[2]);
+    }
+    Ok((region, image))
+}
+
+fn region_to_bytes(region: SensorRegion) -> [u8; REGION_LENGTH as usize] {
+    let mut bytes = [0; REGION_LENGTH as usize];
+    for (chunk, value) in bytes
+        .chunks_mut(8)
+        .zip([region.x, region.y, region.width, region.height])
+    {
+        chunk.copy_from_slice(&(value as u64).to_le_bytes());
+    }
+    bytes
+}
+
+fn region_from_bytes(bytes: &[u8]) -> std::io::Result<SensorRegion> {
+    if bytes.len() < REGION_LENGTH as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "message is too short for a region",
+        ));
+    }
+    let value = |index: usize| {
+        u64::from_le_bytes(
+            bytes[index * 8..(index + 1) * 8]
+                .try_into()
+                .expect("Expect 8 byte slice"),
+        ) as usize
+    };
+    Ok(SensorRegion::new(value(0), value(1), value(2), value(3)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{net::TcpListener, sync::Arc};
+
+    use crate::{
+        object::{ObjectGeometry, ObjectMaterial},
+        vector::{Direction, Point},
+    };
+
+    use super::*;
+
+    #[test]
+    fn capture_image_distributed_test() {
+        let objects = vec![
+            Object::new_sphere(
+                Point::new(0., -100.5, -1.),
+                100.,
+                ObjectMaterial::Lambert {
+                    albedo: Colour::new(0.5, 0.5, 0.5).into(),
+                },
+            ),
+            Object::new_sphere(
+                Point::new(0., 0., -1.),
+                0.5,
+                ObjectMaterial::Metal {
+                    albedo: Colour::new(0.8, 0.6, 0.2),
+                    fuzzy_scatter: 0.3,
+                },
+            ),
+        ];
+        let camera = Camera::builder()
+            .look_at(
+                Point::new(0., 0., 1.),
+                Point::new(0., 0., -1.),
+                Direction::new(0., 1., 0.),
+            )
+            .expect("Expect valid camera position")
+            .input_sensor(20, 10, 4, 8)
+            .input_lens(2., 0.05, 60.)
+            .build();
+
+        let mut workers = (0..2)
+            .map(|_| {
+                let listener = TcpListener::bind("127.0.0.1:0").expect("Expect free port");
+                let address = listener.local_addr().expect("Expect bound address");
+                std::thread::spawn(move || {
+                    let (stream, _) = listener.accept().expect("Expect coordinator");
+                    work_for_coordinator(stream).expect("Expect tiles captured");
+                });
+                address
+            })
+            .collect::<Vec<_>>();
+        // A worker that never answers, its tile times out and goes to another worker
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Expect free port");
+        workers.insert(0, listener.local_addr().expect("Expect bound address"));
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("Expect coordinator");
+            let _ = std::io::copy(&mut stream, &mut std::io::sink());
+        });
+
+        let timeout = Duration::from_secs(2);
+        let distributed =
+            capture_image_distributed(&camera, &objects, &workers, 6, timeout, &mut ())
+                .expect("Expect workers available");
+        assert!(distributed.is_complete());
+        assert_eq!(
+            distributed.into_image().get_pixels(),
+            camera.capture_image(&objects).get_pixels()
+        );
+
+        // Rejected before connecting to the workers, which are gone by now
+        let custom = Object::new_custom(
+            Arc::new(ObjectGeometry::Sphere {
+                center: Point::new(0., 0., -1.),
+                radius: 0.5,
+            }),
+            ObjectMaterial::Lambert {
+                albedo: Colour::new(0.5, 0.5, 0.5).into(),
+            },
+        );
+        assert!(matches!(
+            capture_image_distributed(&camera, &[custom], &workers, 6, timeout, &mut ()),
+            Err(error) if error.kind() == std::io::ErrorKind::InvalidInput
+        ));
+    }
+
+    #[test]
+    fn read_message_test() {
+        let header = |kind: u8, length: u64| {
+            let mut bytes = vec![kind];
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes
+        };
+        let tile = SensorRegion::new(0, 0, 2, 2);
+
+        // The length is rejected before the payload is allocated or read
+        let error = read_message(&mut header(MESSAGE_SCENE, u64::MAX).as_slice(), |_| {
+            MAX_SCENE_LENGTH
+        })
+        .expect_err("Expect oversized scene rejected");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        let error = read_tile_result(
+            &mut header(MESSAGE_TILE_RESULT, 32 + 24 * 4 + 1).as_slice(),
+            tile,
+        )
+        .expect_err("Expect oversized tile rejected");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut bytes = Vec::new();
+        write_tile_result(&mut bytes, tile, &Image::new(2, 2)).expect("Expect tile written");
+        let (region, image) =
+            read_tile_result(&mut bytes.as_slice(), tile).expect("Expect tile read");
+        assert_eq!((region, image.get_dimensions()), (tile, (2, 2)));
+    }
+
+    #[test]
+    fn work_for_coordinator_test() {
+        let camera = |width, height| {
+            Camera::builder()
+                .look_at(
+                    Point::new(0., 0., 1.),
+                    Point::new(0., 0., -1.),
+                    Direction::new(0., 1., 0.),
+                )
+                .expect("Expect valid camera position")
+                .input_sensor(width, height, 1, 2)
+                .input_lens(2., 0., 60.)
+                .build()
+        };
+        let objects = vec![Object::new_sphere(
+            Point::new(0., 0., -1.),
+            0.5,
+            ObjectMaterial::Lambert {
+                albedo: Colour::new(0.5, 0.5, 0.5).into(),
+            },
+        )];
+        // Sends the scene and the tile to a worker, returning its reply
+        let serve = |camera: &Camera, tile: SensorRegion| {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("Expect free port");
+            let address = listener.local_addr().expect("Expect bound address");
+            let worker = std::thread::spawn(move || {
+                let (stream, _) = listener.accept().expect("Expect coordinator");
+                work_for_coordinator(stream)
+            });
+            let stream = TcpStream::connect(address).expect("Expect worker");
+            let mut reader = BufReader::new(stream.try_clone().expect("Expect stream"));
+            let mut writer = BufWriter::new(stream);
+            let scene = describe_scene(camera, &objects);
+            let reply = write_message(&mut writer, MESSAGE_SCENE, scene.as_bytes())
+                .and_then(|_| write_message(&mut writer, MESSAGE_TILE, &region_to_bytes(tile)))
+                .and_then(|_| read_tile_result(&mut reader, tile));
+            let _ = write_message(&mut writer, MESSAGE_DONE, &[]);
+            (reply, worker.join().expect("Expect worker not panicked"))
+        };
+        let is_invalid = |result: std::io::Result<()>| {
+            result.is_err_and(|err| err.kind() == std::io::ErrorKind::InvalidData)
+        };
+
+        // The worker sends back the region it captured, clamped to the sensor
+        let (reply, worker) = serve(&camera(20, 10), SensorRegion::new(16, 8, 6, 6));
+        let (region, image) = reply.expect("Expect tile captured");
+        assert_eq!(region, SensorRegion::new(16, 8, 4, 2));
+        assert_eq!(image.get_dimensions(), (4, 2));
+        assert!(worker.is_ok());
+
+        let (_, worker) = serve(&camera(20, 10), SensorRegion::new(0, 0, 2048, 1));
+        assert!(is_invalid(worker));
+        let (_, worker) = serve(&camera(1 << 14, 1 << 13), SensorRegion::new(0, 0, 1, 1));
+        assert!(is_invalid(worker));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+/// Most arrays and objects nested inside each other, so malformed files cannot overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    /// Parses `text` as a single JSON value.
+    pub(super) fn parse(text: &str) -> Result<Self, &'static str> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            position: 0,
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.position == parser.bytes.len() {
+            Ok(value)
+        } else {
+            Err("trailing characters after JSON value")
+        }
+    }
+
+    /// Member `key` of an object, `None` for missing members and other values.
+    pub(super) fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// The number as an index or count, `None` if it is negative or has a fraction.
+    pub(super) fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|number| *number >= 0. && number.fract() == 0. && *number <= u32::MAX as f64)
+            .map(|number| number as usize)
+    }
+
+    pub(super) fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Items of the array member `key`, empty if the member is missing.
+    pub(super) fn items(&self, key: &str) -> &[Self] {
+        self.get(key).and_then(Self::as_array).unwrap_or(&[])
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &[u8]) -> Result<(), &'static str> {
+        if self.bytes[self.position..].starts_with(literal) {
+            self.position += literal.len();
+            Ok(())
+        } else {
+            Err("unexpected characters in JSON")
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("JSON is nested too deeply");
+        }
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            None => Err("unexpected end of JSON"),
+            Some(b'n') => self.expect(b"null").map(|()| Json::Null),
+            Some(b't') => self.expect(b"true").map(|()| Json::Bool(true)),
+            Some(b'f') => self.expect(b"false").map(|()| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err("expected ',' or ']' in JSON array"),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = BTreeMap::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.bytes.get(self.position) != Some(&b'"') {
+                        return Err("expected a key in JSON object");
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(b":")?;
+                    members.insert(key, self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err("expected ',' or '}' in JSON object"),
+                    }
+                }
+            }
+            Some(_) => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, &'static str> {
+        let start = self.position;
+        while let Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') =
+            self.bytes.get(self.position)
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()
+            .filter(|number| !number.is_empty())
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or("invalid number in JSON")
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        // Skips the opening quote
+        self.position += 1;
+        let mut string = Vec::new();
+        loop {
+            let byte = *self
+                .bytes
+                .get(self.position)
+                .ok_or("unterminated string in JSON")?;
+            self.position += 1;
+            match byte {
+                b'"' => return String::from_utf8(string).map_err(|_| "invalid UTF-8 in JSON"),
+                b'\\' => {
+                    let escape = *self
+                        .bytes
+                        .get(self.position)
+                        .ok_or("unterminated string in JSON")?;
+                    self.position += 1;
+                    let character = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err("invalid escape in JSON string"),
+                    };
+                    string.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => string.push(byte),
+            }
+        }
+    }
+
+    /// Character of a `\u` escape, joining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, &'static str> {
+        let first = self.hex_code()?;
+        let code = if (0xD800..0xDC00).contains(&first) {
+            self.expect(b"\\u")?;
+            let second = self.hex_code()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err("invalid surrogate pair in JSON string");
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        char::from_u32(code).ok_or("invalid character in JSON string")
+    }
+
+    fn hex_code(&mut self) -> Result<u32, &'static str> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or("invalid unicode escape in JSON string")?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
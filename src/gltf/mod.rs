@@ -0,0 +1,904 @@
+mod json;
+
+use std::{borrow::Cow, collections::HashMap, path::Path, sync::Arc};
+
+use crate::{
+    camera::{Camera, CameraBuilder, InputComplete},
+    object::{Object, ObjectMaterial, Texture, TriangleMesh},
+    transform::Transform,
+    vector::{Colour, Direction, Point},
+    Image, ImageError,
+};
+
+use self::json::Json;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+#[derive(Debug, Clone)]
+/// Objects and cameras of the default scene of a glTF file, placed by the node hierarchy.
+pub struct GltfScene {
+    /// One Object per triangle primitive of each mesh node.
+    pub objects: Vec<Object>,
+    pub cameras: Vec<GltfCamera>,
+}
+
+impl GltfScene {
+    /// Reads a `.gltf` or `.glb` file from `path`, choosing the format from the first bytes of the file.</br>
+    /// Buffers and images outside of the file are read relative to the directory of `path`.
+    /// # Errors
+    /// Fails if a file cannot be read, or if the contents are not a supported glTF.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GltfError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)?;
+        gltf_to_scene(&bytes, path.parent().unwrap_or(Path::new(".")))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Camera of a glTF node, looking down the -Z axis of the node with +Y up.
+pub struct GltfCamera {
+    pub center: Point,
+    pub forward: Direction,
+    pub up: Direction,
+    pub projection: GltfProjection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GltfProjection {
+    /// `field_of_view` is the vertical view angle in degrees, `aspect_ratio` is the width over the height
+    /// when the file sets one.
+    Perspective {
+        field_of_view: f64,
+        aspect_ratio: Option<f64>,
+    },
+    /// `view_height` is the height in world units of the area seen by the camera.
+    Orthographic { view_height: f64 },
+}
+
+impl GltfCamera {
+    /// CameraBuilder positioned and projecting like the glTF camera, with a pinhole lens</br>
+    /// # Parameters
+    /// Same as [`CameraBuilder::input_sensor`]</br>
+    /// # Note
+    /// `sensor_width` and `sensor_height` should follow the aspect ratio of a perspective camera when it has one.
+    pub fn builder(
+        &self,
+        sensor_width: usize,
+        sensor_height: usize,
+        shutter_length: usize,
+        max_ray_depth: usize,
+    ) -> CameraBuilder<InputComplete> {
+        let builder = Camera::builder()
+            .input_position(self.center, self.forward, self.up)
+            .input_sensor(sensor_width, sensor_height, shutter_length, max_ray_depth);
+        match self.projection {
+            GltfProjection::Perspective { field_of_view, .. } => {
+                builder.input_lens(1., 0., field_of_view)
+            }
+            GltfProjection::Orthographic { view_height } => builder.input_orthographic(view_height),
+        }
+    }
+}
+
+/// Reads the default scene of a glTF 2.0 file, either JSON text or GLB binary, with buffers and images outside
+/// of the file read relative to `directory`.</br>
+/// Metallic-roughness materials become the nearest ObjectMaterial:
+/// transmissive materials are Dialectric, metallic materials are Metal with the roughness as the fuzz, and
+/// the others are Lambert with the base colour texture, the vertex colours, or the base colour.
+/// # Note
+/// Only PNG textures are decoded, materials with other textures use their base colour.
+/// Points and lines are left out.
+/// # Errors
+/// Fails if the contents are not a glTF 2.0 file, if a buffer cannot be read, or if they use sparse
+/// accessors or remote URIs.
+pub fn gltf_to_scene(bytes: &[u8], directory: &Path) -> Result<GltfScene, GltfError> {
+    let (json, binary_chunk) = read_container(bytes)?;
+    if let Some(version) = json
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(Json::as_str)
+    {
+        if !version.starts_with("2.") {
+            return Err(GltfError::Unsupported("glTF versions other than 2"));
+        }
+    }
+    let buffers = json
+        .items("buffers")
+        .iter()
+        .enumerate()
+        .map(
+            |(index, buffer)| match buffer.get("uri").and_then(Json::as_str) {
+                Some(uri) => read_uri(uri, directory).map(Cow::Owned),
+                None if index == 0 => binary_chunk
+                    .map(Cow::Borrowed)
+                    .ok_or(GltfError::Malformed("buffer has no data")),
+                None => Err(GltfError::Malformed("buffer has no data")),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut document = Document {
+        json: &json,
+        buffers,
+        directory,
+        images: HashMap::new(),
+        meshes: HashMap::new(),
+    };
+    let mut scene = GltfScene {
+        objects: Vec::new(),
+        cameras: Vec::new(),
+    };
+
+    let nodes = json.items("nodes");
+    let roots = match json
+        .get("scene")
+        .and_then(Json::as_usize)
+        .or_else(|| (!json.items("scenes").is_empty()).then_some(0))
+    {
+        Some(index) => json
+            .items("scenes")
+            .get(index)
+            .ok_or(GltfError::Malformed("scene index out of range"))?
+            .items("nodes")
+            .iter()
+            .map(|node| {
+                node.as_usize()
+                    .ok_or(GltfError::Malformed("invalid node index"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        // Without scenes every node that is not a child is a root
+        None => (0..nodes.len())
+            .filter(|index| {
+                !nodes.iter().any(|node| {
+                    node.items("children")
+                        .iter()
+                        .any(|child| child.as_usize() == Some(*index))
+                })
+            })
+            .collect(),
+    };
+
+    // Nodes have at most one parent, so reaching a node twice means the hierarchy has a cycle or shares nodes,
+    // which could otherwise multiply the instances exponentially
+    let mut visited = vec![false; nodes.len()];
+    let mut stack = roots
+        .into_iter()
+        .map(|root| (root, None::<Transform>))
+        .collect::<Vec<_>>();
+    while let Some((index, parent)) = stack.pop() {
+        let node = nodes
+            .get(index)
+            .ok_or(GltfError::Malformed("node index out of range"))?;
+        if std::mem::replace(&mut visited[index], true) {
+            return Err(GltfError::Malformed("node has more than one parent"));
+        }
+        // Nodes scaled to nothing hide their children too
+        let Some(local) = node_transform(node)? else {
+            continue;
+        };
+        let world = match &parent {
+            Some(parent) => local.then(parent),
+            None => local,
+        };
+
+        if let Some(mesh) = node.get("mesh") {
+            let mesh = mesh
+                .as_usize()
+                .ok_or(GltfError::Malformed("invalid mesh index"))?;
+            scene.objects.extend(
+                document
+                    .mesh(mesh)?
+                    .iter()
+                    .map(|object| object.clone().with_transform(world)),
+            );
+        }
+        if let Some(camera) = node.get("camera") {
+            let camera = camera
+                .as_usize()
+                .and_then(|camera| json.items("cameras").get(camera))
+                .ok_or(GltfError::Malformed("camera index out of range"))?;
+            scene.cameras.push(read_camera(camera, &world)?);
+        }
+        for child in node.items("children") {
+            let child = child
+                .as_usize()
+                .ok_or(GltfError::Malformed("invalid node index"))?;
+            stack.push((child, Some(world)));
+        }
+    }
+    Ok(scene)
+}
+
+/// JSON of the file and the binary chunk of GLB files.
+fn read_container(bytes: &[u8]) -> Result<(Json, Option<&[u8]>), GltfError> {
+    if !bytes.starts_with(GLB_MAGIC) {
+        let text =
+            std::str::from_utf8(bytes).map_err(|_| GltfError::Malformed("glTF is not UTF-8"))?;
+        let json =
+            Json::parse(text.trim_start_matches('\u{feff}')).map_err(GltfError::Malformed)?;
+        return Ok((json, None));
+    }
+
+    let word = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .ok_or(GltfError::Malformed("GLB is truncated"))
+    };
+    if word(4)? != 2 {
+        return Err(GltfError::Unsupported("GLB versions other than 2"));
+    }
+    let length = (word(8)? as usize).min(bytes.len());
+    let (mut json, mut binary_chunk) = (None, None);
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let (chunk_length, kind) = (word(offset)? as usize, word(offset + 4)?);
+        let data = bytes
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or(GltfError::Malformed("GLB chunk is truncated"))?;
+        match kind {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(data),
+            GLB_BIN_CHUNK if binary_chunk.is_none() => binary_chunk = Some(data),
+            // Unknown chunks are skipped
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+    let text = json.ok_or(GltfError::Malformed("GLB has no JSON chunk"))?;
+    let text = std::str::from_utf8(text).map_err(|_| GltfError::Malformed("glTF is not UTF-8"))?;
+    Ok((
+        Json::parse(text).map_err(GltfError::Malformed)?,
+        binary_chunk,
+    ))
+}
+
+/// Contents of a base64 data URI, or of a file relative to `directory`.
+fn read_uri(uri: &str, directory: &Path) -> Result<Vec<u8>, GltfError> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (media_type, data) = data
+            .split_once(',')
+            .ok_or(GltfError::Malformed("data URI has no data"))?;
+        if !media_type.ends_with(";base64") {
+            return Err(GltfError::Unsupported("data URIs that are not base64"));
+        }
+        base64_decode(data).ok_or(GltfError::Malformed("invalid base64 in data URI"))
+    } else if uri.contains("://") {
+        Err(GltfError::Unsupported("remote URIs"))
+    } else {
+        Ok(std::fs::read(directory.join(percent_decode(uri)))?)
+    }
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut bits, mut bit_count) = (0u32, 0);
+    for character in text
+        .bytes()
+        .filter(|character| !character.is_ascii_whitespace())
+    {
+        if character == b'=' {
+            break;
+        }
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | u32::from(value);
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Replaces `%XX` escapes in a relative URI with the bytes they stand for.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        let escaped = (bytes[position] == b'%')
+            .then(|| bytes.get(position + 1..position + 3))
+            .flatten()
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                position += 3;
+            }
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Transform of a node relative to its parent, `None` if it cannot be inverted.
+fn node_transform(node: &Json) -> Result<Option<Transform>, GltfError> {
+    let numbers = |key: &str, default: &[f64]| -> Result<Vec<f64>, GltfError> {
+        match node.get(key) {
+            Some(value) => value
+                .as_array()
+                .filter(|items| items.len() == default.len())
+                .and_then(|items| items.iter().map(Json::as_f64).collect::<Option<Vec<_>>>())
+                .ok_or(GltfError::Malformed("invalid node transform")),
+            None => Ok(default.to_vec()),
+        }
+    };
+
+    let matrix = if node.get("matrix").is_some() {
+        // Column major
+        let m = numbers("matrix", &[0.; 16])?;
+        [0, 1, 2].map(|row| [m[row], m[4 + row], m[8 + row], m[12 + row]])
+    } else {
+        let translation = numbers("translation", &[0.; 3])?;
+        let scale = numbers("scale", &[1.; 3])?;
+        let [x, y, z, w] = <[f64; 4]>::try_from(numbers("rotation", &[0., 0., 0., 1.])?)
+            .expect("Rotations have 4 components");
+        let rotation = [
+            [
+                1. - 2. * (y * y + z * z),
+                2. * (x * y - z * w),
+                2. * (x * z + y * w),
+            ],
+            [
+                2. * (x * y + z * w),
+                1. - 2. * (x * x + z * z),
+                2. * (y * z - x * w),
+            ],
+            [
+                2. * (x * z - y * w),
+                2. * (y * z + x * w),
+                1. - 2. * (x * x + y * y),
+            ],
+        ];
+        [0, 1, 2].map(|row| {
+            [
+                rotation[row][0] * scale[0],
+                rotation[row][1] * scale[1],
+                rotation[row][2] * scale[2],
+                translation[row],
+            ]
+        })
+    };
+    Ok(Transform::from_matrix(matrix))
+}
+
+fn read_camera(camera: &Json, world: &Transform) -> Result<GltfCamera, GltfError> {
+    let number = |projection: &str, key: &str| {
+        camera
+            .get(projection)
+            .and_then(|projection| projection.get(key))
+            .and_then(Json::as_f64)
+    };
+    let projection = match camera.get("type").and_then(Json::as_str) {
+        Some("perspective") => GltfProjection::Perspective {
+            field_of_view: number("perspective", "yfov")
+                .ok_or(GltfError::Malformed("perspective camera has no yfov"))?
+                .to_degrees(),
+            aspect_ratio: number("perspective", "aspectRatio"),
+        },
+        Some("orthographic") => GltfProjection::Orthographic {
+            view_height: 2.
+                * number("orthographic", "ymag")
+                    .ok_or(GltfError::Malformed("orthographic camera has no ymag"))?,
+        },
+        _ => return Err(GltfError::Malformed("unknown camera type")),
+    };
+    Ok(GltfCamera {
+        center: world.apply_point(Point::default()),
+        forward: world
+            .apply_direction(Direction::new(0., 0., -1.))
+            .unit_vector(),
+        up: world
+            .apply_direction(Direction::new(0., 1., 0.))
+            .unit_vector(),
+        projection,
+    })
+}
+
+/// Metallic-roughness parameters of a glTF material.
+struct Material {
+    base_colour: Colour,
+    /// Index of the base colour texture and of the TEXCOORD attribute it uses.
+    texture: Option<(usize, usize)>,
+    metallic: f64,
+    roughness: f64,
+    transmission: f64,
+    refraction_index: f64,
+}
+
+impl Material {
+    /// Reads `material`, or the glTF default material when it is `None`.
+    fn read(material: Option<&Json>) -> Self {
+        let number = |path: &[&str], default: f64| {
+            material
+                .and_then(|material| path.iter().try_fold(material, |value, key| value.get(key)))
+                .and_then(Json::as_f64)
+                .unwrap_or(default)
+        };
+        let pbr = material.and_then(|material| material.get("pbrMetallicRoughness"));
+        let base_colour = pbr
+            .and_then(|pbr| pbr.get("baseColorFactor"))
+            .and_then(Json::as_array)
+            .and_then(|factor| {
+                Some(Colour::new(
+                    factor.first()?.as_f64()?,
+                    factor.get(1)?.as_f64()?,
+                    factor.get(2)?.as_f64()?,
+                ))
+            })
+            .unwrap_or(Colour::new(1., 1., 1.));
+        let texture = pbr
+            .and_then(|pbr| pbr.get("baseColorTexture"))
+            .and_then(|texture| {
+                Some((
+                    texture.get("index")?.as_usize()?,
+                    texture.get("texCoord").map_or(Some(0), Json::as_usize)?,
+                ))
+            });
+        Self {
+            base_colour,
+            texture,
+            metallic: number(&["pbrMetallicRoughness", "metallicFactor"], 1.),
+            roughness: number(&["pbrMetallicRoughness", "roughnessFactor"], 1.),
+            transmission: number(
+                &[
+                    "extensions",
+                    "KHR_materials_transmission",
+                    "transmissionFactor",
+                ],
+                0.,
+            ),
+            refraction_index: number(&["extensions", "KHR_materials_ior", "ior"], 1.5),
+        }
+    }
+}
+
+/// Buffers of a glTF and the Images and meshes already read from them.
+struct Document<'a> {
+    json: &'a Json,
+    buffers: Vec<Cow<'a, [u8]>>,
+    directory: &'a Path,
+    /// Decoded images in linear colours, `None` for images that are not PNG.
+    images: HashMap<usize, Option<Arc<Image>>>,
+    /// Objects of each mesh, shared by the nodes that place the mesh.
+    meshes: HashMap<usize, Vec<Object>>,
+}
+
+impl Document<'_> {
+    fn mesh(&mut self, index: usize) -> Result<&[Object], GltfError> {
+        if !self.meshes.contains_key(&index) {
+            let json = self.json;
+            let mesh = json
+                .items("meshes")
+                .get(index)
+                .ok_or(GltfError::Malformed("mesh index out of range"))?;
+            let mut objects = Vec::new();
+            for primitive in mesh.items("primitives") {
+                objects.extend(self.primitive(primitive)?);
+            }
+            self.meshes.insert(index, objects);
+        }
+        Ok(&self.meshes[&index])
+    }
+
+    /// Object with the triangles of `primitive`, `None` for points and lines.
+    fn primitive(&mut self, primitive: &Json) -> Result<Option<Object>, GltfError> {
+        let mode = primitive
+            .get("mode")
+            .map_or(Some(MODE_TRIANGLES), Json::as_usize)
+            .ok_or(GltfError::Malformed("invalid primitive mode"))?;
+        if ![MODE_TRIANGLES, MODE_TRIANGLE_STRIP, MODE_TRIANGLE_FAN].contains(&mode) {
+            return Ok(None);
+        }
+        let attribute = |name: &str| {
+            primitive
+                .get("attributes")
+                .and_then(|attributes| attributes.get(name))
+                .map(|accessor| {
+                    accessor
+                        .as_usize()
+                        .ok_or(GltfError::Malformed("invalid accessor index"))
+                })
+                .transpose()
+        };
+        let json = self.json;
+        let material = Material::read(
+            primitive
+                .get("material")
+                .and_then(Json::as_usize)
+                .and_then(|material| json.items("materials").get(material)),
+        );
+
+        let (positions, components) = self.accessor(
+            attribute("POSITION")?.ok_or(GltfError::Malformed("primitive has no POSITION"))?,
+        )?;
+        if components != 3 {
+            return Err(GltfError::Malformed("POSITION is not a VEC3"));
+        }
+        let positions = positions
+            .chunks_exact(3)
+            .map(|position| Point::new(position[0], position[1], position[2]))
+            .collect::<Vec<_>>();
+        let indices = match primitive.get("indices") {
+            Some(indices) => {
+                let (indices, _) = self.accessor(
+                    indices
+                        .as_usize()
+                        .ok_or(GltfError::Malformed("invalid accessor index"))?,
+                )?;
+                indices.into_iter().map(|index| index as usize).collect()
+            }
+            None => (0..positions.len()).collect::<Vec<_>>(),
+        };
+        let triangles = match mode {
+            MODE_TRIANGLE_STRIP => (2..indices.len())
+                .map(|end| {
+                    // Every other triangle of a strip is flipped to keep the winding
+                    if end % 2 == 0 {
+                        [indices[end - 2], indices[end - 1], indices[end]]
+                    } else {
+                        [indices[end - 1], indices[end - 2], indices[end]]
+                    }
+                })
+                .collect(),
+            MODE_TRIANGLE_FAN => (2..indices.len())
+                .map(|end| [indices[0], indices[end - 1], indices[end]])
+                .collect(),
+            _ => indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect(),
+        };
+        let count_mismatch = || GltfError::Malformed("attribute count does not match POSITION");
+        let mut mesh = TriangleMesh::new(positions, triangles)
+            .ok_or(GltfError::Malformed("index out of range"))?;
+
+        if let Some(normals) = attribute("NORMAL")? {
+            let normals = self
+                .accessor(normals)?
+                .0
+                .chunks_exact(3)
+                .map(|normal| Direction::new(normal[0], normal[1], normal[2]))
+                .collect();
+            mesh = mesh.with_normals(normals).ok_or_else(count_mismatch)?;
+        }
+        let texture = match material.texture {
+            Some((texture, _)) => self.texture(texture, material.base_colour)?,
+            None => None,
+        };
+        if let Some(uvs) = attribute(&format!(
+            "TEXCOORD_{}",
+            material.texture.map_or(0, |(_, set)| set)
+        ))? {
+            // glTF puts V = 0 at the top of images, and Texture::Image at the bottom
+            let uvs = self
+                .accessor(uvs)?
+                .0
+                .chunks_exact(2)
+                .map(|uv| (uv[0], 1. - uv[1]))
+                .collect();
+            mesh = mesh.with_uvs(uvs).ok_or_else(count_mismatch)?;
+        }
+        let has_colours = if let Some(colours) = attribute("COLOR_0")? {
+            let (colours, components) = self.accessor(colours)?;
+            if components < 3 {
+                return Err(GltfError::Malformed("COLOR_0 is not a VEC3 or VEC4"));
+            }
+            let colours = colours
+                .chunks_exact(components)
+                .map(|colour| Colour::new(colour[0], colour[1], colour[2]) * material.base_colour)
+                .collect();
+            mesh = mesh.with_colours(colours).ok_or_else(count_mismatch)?;
+            true
+        } else {
+            false
+        };
+
+        let object_material = if material.transmission >= 0.5 {
+            ObjectMaterial::Dialectric {
+                refraction_index: material.refraction_index,
+            }
+        } else if material.metallic >= 0.5 {
+            ObjectMaterial::Metal {
+                albedo: material.base_colour,
+                fuzzy_scatter: material.roughness.clamp(0., 1.),
+            }
+        } else {
+            ObjectMaterial::Lambert {
+                albedo: match texture {
+                    Some(image) => Texture::Image(image),
+                    None if has_colours => Texture::VertexColour,
+                    None => Texture::Solid(material.base_colour),
+                },
+            }
+        };
+        Ok(Some(Object::new_mesh(mesh, object_material)))
+    }
+
+    /// Image of texture `index` with its colours multiplied by `factor`, `None` if the image is not PNG.
+    fn texture(&mut self, index: usize, factor: Colour) -> Result<Option<Arc<Image>>, GltfError> {
+        let Some(source) = self
+            .json
+            .items("textures")
+            .get(index)
+            .ok_or(GltfError::Malformed("texture index out of range"))?
+            .get("source")
+            .and_then(Json::as_usize)
+        else {
+            return Ok(None);
+        };
+        let Some(image) = self.image(source)? else {
+            return Ok(None);
+        };
+        if factor == Colour::new(1., 1., 1.) {
+            return Ok(Some(image));
+        }
+        let (width, height) = image.get_dimensions();
+        let mut tinted = Image::new(width, height);
+        for (tinted, pixel) in tinted.get_pixels_mut().iter_mut().zip(image.get_pixels()) {
+            *tinted = *pixel * factor;
+        }
+        Ok(Some(Arc::new(tinted)))
+    }
+
+    fn image(&mut self, index: usize) -> Result<Option<Arc<Image>>, GltfError> {
+        if let Some(image) = self.images.get(&index) {
+            return Ok(image.clone());
+        }
+        let image = self
+            .json
+            .items("images")
+            .get(index)
+            .ok_or(GltfError::Malformed("image index out of range"))?;
+        let bytes = match (
+            image.get("uri").and_then(Json::as_str),
+            image.get("bufferView").and_then(Json::as_usize),
+        ) {
+            (Some(uri), _) => Cow::Owned(read_uri(uri, self.directory)?),
+            (None, Some(view)) => Cow::Borrowed(self.buffer_view(view)?.0),
+            (None, None) => return Err(GltfError::Malformed("image has no data")),
+        };
+        let image = if bytes.starts_with(&crate::png::SIGNATURE) {
+            let mut image = crate::png::png_to_image(&bytes)?;
+            for pixel in image.get_pixels_mut() {
+                *pixel = pixel.gamma_to_linear();
+            }
+            Some(Arc::new(image))
+        } else {
+            None
+        };
+        self.images.insert(index, image.clone());
+        Ok(image)
+    }
+
+    /// Bytes of buffer view `index`, and the stride between its elements if it sets one.
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), GltfError> {
+        let view = self
+            .json
+            .items("bufferViews")
+            .get(index)
+            .ok_or(GltfError::Malformed("buffer view index out of range"))?;
+        let number = |key: &str| view.get(key).and_then(Json::as_usize);
+        let buffer = number("buffer")
+            .and_then(|buffer| self.buffers.get(buffer))
+            .ok_or(GltfError::Malformed("buffer index out of range"))?;
+        let offset = number("byteOffset").unwrap_or(0);
+        let length =
+            number("byteLength").ok_or(GltfError::Malformed("buffer view has no length"))?;
+        let bytes = buffer
+            .get(offset..offset + length)
+            .ok_or(GltfError::Malformed("buffer view is outside of its buffer"))?;
+        Ok((bytes, number("byteStride")))
+    }
+
+    /// Values of accessor `index`, element by element, and the number of components of each element.</br>
+    /// Normalized integers are mapped to 0 to 1, or -1 to 1 when signed.
+    fn accessor(&self, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+        let accessor = self
+            .json
+            .items("accessors")
+            .get(index)
+            .ok_or(GltfError::Malformed("accessor index out of range"))?;
+        if accessor.get("sparse").is_some() {
+            return Err(GltfError::Unsupported("sparse accessors"));
+        }
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2" | "MAT3" | "MAT4") => {
+                return Err(GltfError::Unsupported("matrix accessors"))
+            }
+            _ => return Err(GltfError::Malformed("unknown accessor type")),
+        };
+        let component_type = accessor
+            .get("componentType")
+            .and_then(Json::as_usize)
+            .ok_or(GltfError::Malformed("accessor has no component type"))?;
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(GltfError::Malformed("unknown accessor component type")),
+        };
+        let count = accessor
+            .get("count")
+            .and_then(Json::as_usize)
+            .ok_or(GltfError::Malformed("accessor has no count"))?;
+        let normalized = accessor.get("normalized") == Some(&Json::Bool(true));
+
+        let Some(view) = accessor.get("bufferView") else {
+            // Accessors without a buffer view are zeros
+            return Ok((vec![0.; count * components], components));
+        };
+        let (bytes, stride) = self.buffer_view(
+            view.as_usize()
+                .ok_or(GltfError::Malformed("invalid buffer view index"))?,
+        )?;
+        let offset = accessor
+            .get("byteOffset")
+            .and_then(Json::as_usize)
+            .unwrap_or(0);
+        let stride = stride.unwrap_or(size * components);
+
+        let mut values = Vec::new();
+        for element in 0..count {
+            for component in 0..components {
+                let start = offset + element * stride + component * size;
+                let bytes = bytes.get(start..start + size).ok_or(GltfError::Malformed(
+                    "accessor is outside of its buffer view",
+                ))?;
+                let value = match (component_type, normalized) {
+                    (5120, false) => f64::from(bytes[0] as i8),
+                    (5120, true) => (f64::from(bytes[0] as i8) / 127.).max(-1.),
+                    (5121, false) => f64::from(bytes[0]),
+                    (5121, true) => f64::from(bytes[0]) / 255.,
+                    (5122, false) => f64::from(i16::from_le_bytes([bytes[0], bytes[1]])),
+                    (5122, true) => {
+                        (f64::from(i16::from_le_bytes([bytes[0], bytes[1]])) / 32767.).max(-1.)
+                    }
+                    (5123, false) => f64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+                    (5123, true) => f64::from(u16::from_le_bytes([bytes[0], bytes[1]])) / 65535.,
+                    (5125, _) => {
+                        f64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    }
+                    _ => f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+                };
+                values.push(value);
+            }
+        }
+        Ok((values, components))
+    }
+}
+
+#[derive(Debug)]
+/// Errors when reading a glTF scene.
+pub enum GltfError {
+    Io(std::io::Error),
+    /// The contents do not follow the format.
+    Malformed(&'static str),
+    /// The contents use a feature of the format that is not supported.
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for GltfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read glTF: {error}"),
+            Self::Malformed(message) => write!(f, "malformed glTF: {message}"),
+            Self::Unsupported(message) => write!(f, "unsupported glTF: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<std::io::Error> for GltfError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ImageError> for GltfError {
+    fn from(error: ImageError) -> Self {
+        match error {
+            ImageError::Io(error) => Self::Io(error),
+            ImageError::Malformed(message) => Self::Malformed(message),
+            ImageError::Unsupported(message) => Self::Unsupported(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    #[test]
+    fn gltf_to_scene_test() {
+        let gltf = r#"{
+            "asset": {"version": "2.0"},
+            "scene": 0,
+            "scenes": [{"nodes": [0]}],
+            "nodes": [
+                {"mesh": 0, "translation": [0, 0, -5], "children": [1]},
+                {"camera": 0, "translation": [0, 0, 5]}
+            ],
+            "cameras": [{"type": "perspective", "perspective": {"yfov": 1.0, "znear": 0.1}}],
+            "meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "indices": 1, "material": 0}]}],
+            "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [0.5, 0.5, 0.5, 1], "metallicFactor": 0}}],
+            "buffers": [{
+                "byteLength": 42,
+                "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIA"
+            }],
+            "bufferViews": [
+                {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+                {"buffer": 0, "byteOffset": 36, "byteLength": 6}
+            ],
+            "accessors": [
+                {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+                {"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+            ]
+        }"#;
+        let scene = gltf_to_scene(gltf.as_bytes(), Path::new(".")).expect("Valid glTF");
+
+        assert_eq!(scene.cameras.len(), 1);
+        let camera = scene.cameras[0];
+        assert!(camera.center.point_towards(Point::default()).length() < 1e-9);
+        assert_eq!(camera.forward, Direction::new(0., 0., -1.));
+        assert!(matches!(
+            camera.projection,
+            GltfProjection::Perspective { field_of_view, aspect_ratio: None }
+                if (field_of_view - 1f64.to_degrees()).abs() < 1e-9
+        ));
+
+        assert_eq!(scene.objects.len(), 1);
+        let ray = Ray::new(Point::default(), Direction::new(0.25, 0.25, -5.));
+        let hit = scene.objects[0]
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the triangle");
+        assert!((hit.distance_from_ray - 1.).abs() < 1e-9);
+        assert!(matches!(
+            hit.material,
+            ObjectMaterial::Lambert { albedo: Texture::Solid(colour) } if *colour == Colour::new(0.5, 0.5, 0.5)
+        ));
+
+        assert!(matches!(
+            gltf_to_scene(b"{\"asset\": {\"version\": \"1.0\"}}", Path::new(".")),
+            Err(GltfError::Unsupported(_))
+        ));
+
+        // Nodes listed twice as children, or in a cycle, are rejected instead of instanced again
+        for nodes in [
+            r#"[{"children": [1, 1]}, {"children": [2, 2]}, {}]"#,
+            r#"[{"children": [1]}, {"children": [0]}]"#,
+        ] {
+            let gltf = format!(
+                r#"{{"asset": {{"version": "2.0"}}, "scenes": [{{"nodes": [0]}}], "nodes": {nodes}}}"#
+            );
+            assert!(matches!(
+                gltf_to_scene(gltf.as_bytes(), Path::new(".")),
+                Err(GltfError::Malformed(_))
+            ));
+        }
+    }
+}
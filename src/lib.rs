@@ -1,8 +1,17 @@
+pub mod animation;
 pub mod camera;
+pub mod description;
+pub mod distributed;
+pub mod gltf;
 pub mod object;
 pub mod object_storage;
+pub mod ply;
+pub mod png;
 pub mod ppm;
+pub mod random;
 pub mod ray;
+pub mod stl;
+pub mod transform;
 pub mod vector;
 
 use vector::Colour;
@@ -13,6 +22,15 @@ pub struct Image {
     height: usize,
 }
 
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Image {
     #[must_use]
     pub fn new(width: usize, height: usize) -> Self {
@@ -34,4 +52,57 @@ impl Image {
     pub fn get_dimensions(&self) -> (usize, usize) {
         (self.width, self.height)
     }
+
+    /// Reads a PNG, PPM, or PGM image from `path`, choosing the format from the first bytes of the file.
+    /// # Errors
+    /// Fails if the file cannot be read, or if its contents are not a supported image.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, ImageError> {
+        let bytes = std::fs::read(path)?;
+        if bytes.starts_with(&png::SIGNATURE) {
+            png::png_to_image(&bytes)
+        } else {
+            ppm::ppm_to_image(&bytes)
+        }
+    }
+
+    /// Copies `source` over this Image, with the top-left of `source` placed at (`x`, `y`).
+    /// Pixels that fall outside of this Image are ignored.
+    pub fn paste(&mut self, source: &Image, x: usize, y: usize) {
+        let columns = source.width.min(self.width.saturating_sub(x));
+        let rows = source.height.min(self.height.saturating_sub(y));
+        for row in 0..rows {
+            let source_start = row * source.width;
+            let target_start = (y + row) * self.width + x;
+            self.pixels[target_start..target_start + columns]
+                .copy_from_slice(&source.pixels[source_start..source_start + columns]);
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Errors when reading an Image.
+pub enum ImageError {
+    Io(std::io::Error),
+    /// The contents do not follow the format.
+    Malformed(&'static str),
+    /// The contents use a feature of the format that is not supported.
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read image: {error}"),
+            Self::Malformed(message) => write!(f, "malformed image: {message}"),
+            Self::Unsupported(message) => write!(f, "unsupported image: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+impl From<std::io::Error> for ImageError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
 }
@@ -0,0 +1,124 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    ray::Ray,
+    transform::Transform,
+    vector::{Direction, Point},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Axis-aligned box that contains a geometry.
+pub struct Aabb {
+    pub minimum: Point,
+    pub maximum: Point,
+}
+
+impl Aabb {
+    /// Creates the Aabb with opposite corners at `a` and `b`.
+    pub fn new(a: Point, b: Point) -> Self {
+        Self {
+            minimum: Point::new(a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])),
+            maximum: Point::new(a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])),
+        }
+    }
+
+    /// Creates the Aabb that extends `extent` from `center` on each axis.
+    pub fn around(center: Point, extent: Direction) -> Self {
+        Self::new(center + -extent, center + extent)
+    }
+
+    /// Creates the smallest Aabb that contains all `points`, `None` if there are no points.
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        points
+            .into_iter()
+            .map(|point| Self::new(point, point))
+            .reduce(|lhs, rhs| lhs.union(&rhs))
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            minimum: Point::new(
+                self.minimum[0].min(other.minimum[0]),
+                self.minimum[1].min(other.minimum[1]),
+                self.minimum[2].min(other.minimum[2]),
+            ),
+            maximum: Point::new(
+                self.maximum[0].max(other.maximum[0]),
+                self.maximum[1].max(other.maximum[1]),
+                self.maximum[2].max(other.maximum[2]),
+            ),
+        }
+    }
+
+    /// Creates the Aabb of the space inside of both Aabbs, which is empty if the Aabbs do not overlap.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            minimum: Point::new(
+                self.minimum[0].max(other.minimum[0]),
+                self.minimum[1].max(other.minimum[1]),
+                self.minimum[2].max(other.minimum[2]),
+            ),
+            maximum: Point::new(
+                self.maximum[0].min(other.maximum[0]),
+                self.maximum[1].min(other.maximum[1]),
+                self.maximum[2].min(other.maximum[2]),
+            ),
+        }
+    }
+
+    pub fn center(&self) -> Point {
+        (self.minimum + self.maximum) / 2.
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        std::array::from_fn(|index| {
+            let pick = |axis: usize| {
+                if index & (1 << axis) == 0 {
+                    self.minimum[axis]
+                } else {
+                    self.maximum[axis]
+                }
+            };
+            Point::new(pick(0), pick(1), pick(2))
+        })
+    }
+
+    /// Creates the Aabb that contains this Aabb moved by `transform`.
+    #[must_use]
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| transform.apply_point(corner)),
+        )
+        .unwrap_or(*self)
+    }
+
+    /// Checks if `ray` passes through the Aabb within `ray_length_min_max`.
+    pub fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> bool {
+        self.hit_range(ray, ray_length_min_max).is_some()
+    }
+
+    /// Part of `ray_length_min_max` where `ray` is inside of the Aabb, `None` if the Ray misses the Aabb.
+    pub fn hit_range(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<RangeInclusive<f64>> {
+        let (mut near, mut far) = (*ray_length_min_max.start(), *ray_length_min_max.end());
+        for axis in 0..3 {
+            let inverse_direction = 1. / ray.direction()[axis];
+            let first = (self.minimum[axis] - ray.origin()[axis]) * inverse_direction;
+            let second = (self.maximum[axis] - ray.origin()[axis]) * inverse_direction;
+            // `min` and `max` ignore the NaN of rays that lie on a side of the box
+            near = near.max(first.min(second));
+            far = far.min(first.max(second));
+            if near > far {
+                return None;
+            }
+        }
+        Some(near..=far)
+    }
+}
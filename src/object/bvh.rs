@@ -0,0 +1,135 @@
+use std::ops::RangeInclusive;
+
+use crate::ray::Ray;
+
+use super::Aabb;
+
+/// Most primitives kept in a leaf of a Bvh.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy, a tree of Aabbs over primitives so a Ray only tests the primitives near it.
+#[derive(Debug)]
+pub(crate) struct Bvh {
+    /// Nodes in depth first order, the first child of a branch is the node after it.
+    nodes: Vec<BvhNode>,
+    /// Indices of the primitives, ordered so each leaf holds a contiguous range.
+    order: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    /// For leaves, the range of `order` with their primitives. For branches, an empty range starting at the
+    /// second child.
+    start: usize,
+    count: usize,
+}
+
+impl Bvh {
+    /// Builds the Bvh over primitives with the boxes `bounds`, splitting at the median of the longest axis.
+    pub(crate) fn new(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            order: (0..bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    fn build(&mut self, bounds: &[Aabb], start: usize, end: usize) -> usize {
+        let node = self.nodes.len();
+        let node_bounds = self.order[start..end]
+            .iter()
+            .map(|primitive| bounds[*primitive])
+            .reduce(|lhs, rhs| lhs.union(&rhs))
+            .expect("Nodes have at least one primitive");
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            start,
+            count: end - start,
+        });
+        if end - start <= MAX_LEAF_SIZE {
+            return node;
+        }
+
+        let centers = Aabb::from_points(
+            self.order[start..end]
+                .iter()
+                .map(|primitive| bounds[*primitive].center()),
+        )
+        .expect("Nodes have at least one primitive");
+        let extent = centers.minimum.point_towards(centers.maximum);
+        let axis = (0..3)
+            .max_by(|lhs, rhs| extent[*lhs].total_cmp(&extent[*rhs]))
+            .unwrap_or(0);
+        let middle = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(middle - start, |lhs, rhs| {
+            bounds[*lhs].center()[axis].total_cmp(&bounds[*rhs].center()[axis])
+        });
+
+        self.build(bounds, start, middle);
+        let second = self.build(bounds, middle, end);
+        self.nodes[node].start = second;
+        self.nodes[node].count = 0;
+        node
+    }
+
+    /// Box that contains every primitive, `None` if there are no primitives.
+    pub(crate) fn bounding_box(&self) -> Option<Aabb> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Calls `visit` with the index of each primitive whose box `ray` passes through within
+    /// `ray_length_min_max`, nearer boxes first.</br>
+    /// When `visit` returns a distance, boxes beyond it are skipped, which finds the closest hit quickly.
+    pub(crate) fn traverse(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+        mut visit: impl FnMut(usize) -> Option<f64>,
+    ) {
+        let (start, mut end) = (*ray_length_min_max.start(), *ray_length_min_max.end());
+        let mut stack = Vec::new();
+        if let Some(range) = self
+            .nodes
+            .first()
+            .and_then(|root| root.bounds.hit_range(ray, start..=end))
+        {
+            stack.push((0, *range.start()));
+        }
+
+        while let Some((node, entry)) = stack.pop() {
+            if entry > end {
+                continue;
+            }
+            let BvhNode {
+                start: first,
+                count,
+                ..
+            } = self.nodes[node];
+            if count > 0 {
+                for primitive in &self.order[first..first + count] {
+                    if let Some(distance) = visit(*primitive) {
+                        end = end.min(distance);
+                    }
+                }
+                continue;
+            }
+
+            let mut children = [node + 1, first].map(|child| {
+                self.nodes[child]
+                    .bounds
+                    .hit_range(ray, start..=end)
+                    .map(|range| (child, *range.start()))
+            });
+            // The nearest child is pushed last so it is visited first
+            children.sort_by(|lhs, rhs| match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => rhs.1.total_cmp(&lhs.1),
+                _ => lhs.is_some().cmp(&rhs.is_some()),
+            });
+            stack.extend(children.into_iter().flatten());
+        }
+    }
+}
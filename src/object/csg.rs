@@ -0,0 +1,101 @@
+use crate::ray::Ray;
+
+use super::GeometryHit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How the insides of the two children of [`ObjectGeometry::Csg`](super::ObjectGeometry::Csg) are combined.
+/// # Note
+/// The children must be closed, like spheres, boxes, capped cylinders, and tori, so every Ray enters and
+/// leaves them. Open geometries, like quads and uncapped cylinders, give surfaces with holes. A plane is the
+/// half space behind its normal.
+pub enum CsgOperation {
+    /// Inside of either child.
+    Union,
+    /// Inside of both children.
+    Intersection,
+    /// Inside of the left child but not of the right child.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            Self::Union => inside_left || inside_right,
+            Self::Intersection => inside_left && inside_right,
+            Self::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+/// Hits on the surface of the combination of two children, from every hit of each child along `ray`.
+pub(super) fn combine(
+    operation: CsgOperation,
+    ray: &Ray,
+    left: Vec<GeometryHit>,
+    right: Vec<GeometryHit>,
+) -> Vec<GeometryHit> {
+    let entering = |hit: &GeometryHit| hit.normal.dot(*ray.direction()) < 0.;
+    // A Ray whose first hit leaves a child started inside of it
+    let starts_inside = |hits: &[GeometryHit]| hits.first().is_some_and(|hit| !entering(hit));
+    let mut inside = [starts_inside(&left), starts_inside(&right)];
+    let mut inside_combination = operation.contains(inside[0], inside[1]);
+
+    let mut hits = left
+        .into_iter()
+        .map(|hit| (0, hit))
+        .chain(right.into_iter().map(|hit| (1, hit)))
+        .collect::<Vec<_>>();
+    hits.sort_by(|(_, lhs), (_, rhs)| lhs.distance_from_ray.total_cmp(&rhs.distance_from_ray));
+
+    let mut combination = Vec::new();
+    for (child, mut hit) in hits {
+        inside[child] = entering(&hit);
+        let inside_after = operation.contains(inside[0], inside[1]);
+        if inside_after != inside_combination {
+            inside_combination = inside_after;
+            // The surface of a subtracted child faces into it
+            if operation == CsgOperation::Difference && child == 1 {
+                hit.normal = -hit.normal;
+            }
+            combination.push(hit);
+        }
+    }
+    combination
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        object::ObjectGeometry,
+        vector::{Direction, Point},
+    };
+
+    use super::*;
+
+    #[test]
+    fn csg_difference_test() {
+        let sphere_minus_box = ObjectGeometry::Sphere {
+            center: Point::new(0., 0., 0.),
+            radius: 1.,
+        }
+        .difference(ObjectGeometry::Box {
+            minimum: Point::new(0., -2., -2.),
+            maximum: Point::new(2., 2., 2.),
+        });
+        let ray = Ray::new(Point::new(-3., 0., 0.), Direction::new(1., 0., 0.));
+
+        let hits = sphere_minus_box.hits(&ray, 0.001..=f64::INFINITY);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].distance_from_ray, 2.);
+        assert_eq!(hits[0].normal, Direction::new(-1., 0., 0.));
+        // Leaving through the cut, the normal of the box faces out of the remaining half sphere
+        assert_eq!(hits[1].distance_from_ray, 3.);
+        assert_eq!(hits[1].normal, Direction::new(1., 0., 0.));
+
+        // The far side of the sphere was cut away
+        let hit = sphere_minus_box
+            .hit(&ray, 3.5..=f64::INFINITY)
+            .map(|hit| hit.distance_from_ray);
+        assert_eq!(hit, None);
+    }
+}
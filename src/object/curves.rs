@@ -0,0 +1,343 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use crate::{
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+use super::{bvh::Bvh, geometry::orthonormal_basis, Aabb, GeometryHit};
+
+/// Most times a curve is split in half before it is tested as a line.
+const MAX_SUBDIVISIONS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Cross-section of the curves of [`Curves`].
+pub enum CurveShape {
+    /// Flat strip that always faces the Ray, the cheapest shape for hair, fur, and grass seen from afar.
+    Ribbon,
+    /// Strip that faces the Ray with normals bent across its width, so it shades like a tube.
+    Cylinder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Cubic Bézier curve with a width that changes linearly from its start to its end.
+pub struct BezierCurve {
+    /// The curve starts at the first point, ends at the last point, and is pulled towards the other two.
+    pub control_points: [Point; 4],
+    /// Widths at the start and at the end.
+    pub widths: (f64, f64),
+}
+
+impl BezierCurve {
+    pub fn new(control_points: [Point; 4], widths: (f64, f64)) -> Self {
+        Self {
+            control_points,
+            widths,
+        }
+    }
+
+    /// Width at `u`, from 0 at the start to 1 at the end.
+    pub fn width(&self, u: f64) -> f64 {
+        self.widths.0 + (self.widths.1 - self.widths.0) * u
+    }
+
+    /// Box that contains the curve, since a Bézier curve stays inside its control points.
+    fn bounding_box(&self) -> Aabb {
+        let radius = self.widths.0.max(self.widths.1) / 2.;
+        let bounds = Aabb::from_points(self.control_points).expect("Curves have control points");
+        Aabb::new(
+            bounds.minimum + Direction::new(-radius, -radius, -radius),
+            bounds.maximum + Direction::new(radius, radius, radius),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Many curves with the same [`CurveShape`], such as the strands of hair or fur, under a Bvh.</br>
+/// The curves are shared between clones.
+pub struct Curves {
+    shape: CurveShape,
+    curves: Arc<[BezierCurve]>,
+    bvh: Arc<Bvh>,
+}
+
+/// Frame where a Ray starts at the origin and goes along +Z, so curves can be tested in 2D.
+struct RayFrame {
+    origin: Point,
+    axes: [Direction; 3],
+    /// Length of the direction of the Ray, distances along the +Z axis are divided by it.
+    length: f64,
+}
+
+impl RayFrame {
+    fn new(ray: &Ray) -> Option<Self> {
+        let length = ray.direction().length();
+        if length == 0. || !length.is_finite() {
+            return None;
+        }
+        let forward = *ray.direction() / length;
+        let (side, up) = orthonormal_basis(forward);
+        Some(Self {
+            origin: *ray.origin(),
+            axes: [side, up, forward],
+            length,
+        })
+    }
+
+    fn to_frame(&self, point: Point) -> Direction {
+        let offset = self.origin.point_towards(point);
+        Direction::new(
+            offset.dot(self.axes[0]),
+            offset.dot(self.axes[1]),
+            offset.dot(self.axes[2]),
+        )
+    }
+}
+
+/// Point where a Ray passes through a curve, in the frame of the Ray.
+struct CurveHit {
+    u: f64,
+    /// Distance from the center line, from -1 to 1 across the width.
+    offset: f64,
+    depth: f64,
+}
+
+impl Curves {
+    pub fn new(curves: Vec<BezierCurve>, shape: CurveShape) -> Self {
+        let bounds = curves
+            .iter()
+            .map(BezierCurve::bounding_box)
+            .collect::<Vec<_>>();
+        Self {
+            shape,
+            bvh: Arc::new(Bvh::new(&bounds)),
+            curves: curves.into(),
+        }
+    }
+
+    pub fn shape(&self) -> CurveShape {
+        self.shape
+    }
+
+    pub fn curves(&self) -> &[BezierCurve] {
+        &self.curves
+    }
+
+    /// Box that contains every curve, `None` if there are no curves.
+    pub(super) fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.bounding_box()
+    }
+
+    /// First hit of `ray` with the curves within `ray_length_min_max`.</br>
+    /// U goes along the curve and V across its width, and the tangent follows the curve.
+    pub(super) fn hit(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let frame = RayFrame::new(ray)?;
+        let mut closest: Option<(usize, CurveHit)> = None;
+        self.bvh.traverse(ray, ray_length_min_max.clone(), |curve| {
+            let end = closest
+                .as_ref()
+                .map_or(*ray_length_min_max.end(), |hit| hit.1.depth / frame.length);
+            let hit = self.hit_curve(curve, &frame, *ray_length_min_max.start()..=end)?;
+            let distance = hit.depth / frame.length;
+            closest = Some((curve, hit));
+            Some(distance)
+        });
+        let (curve, hit) = closest?;
+
+        let distance_from_ray = hit.depth / frame.length;
+        let points = self.curves[curve]
+            .control_points
+            .map(|point| Point::default().point_towards(point));
+        let (_, tangent) = evaluate(points, hit.u);
+        let facing = -frame.axes[2];
+        let across = facing.cross(tangent);
+        let normal = match self.shape {
+            CurveShape::Cylinder if !across.is_zero() => {
+                across.unit_vector() * hit.offset
+                    + facing * (1. - hit.offset * hit.offset).max(0.).sqrt()
+            }
+            _ => facing,
+        };
+        Some(GeometryHit {
+            distance_from_ray,
+            point_of_intersection: ray.at(distance_from_ray),
+            normal,
+            uv: (hit.u, 0.5 + 0.5 * hit.offset),
+            vertex_colour: None,
+            tangent: (!tangent.is_zero()).then(|| tangent.unit_vector()),
+        })
+    }
+
+    fn hit_curve(
+        &self,
+        curve: usize,
+        frame: &RayFrame,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<CurveHit> {
+        let curve = &self.curves[curve];
+        let points = curve.control_points.map(|point| frame.to_frame(point));
+
+        // Splits until the pieces are about as straight as a twentieth of the width
+        let bend = (0..2)
+            .flat_map(|index| {
+                (0..3).map(move |axis| {
+                    (points[index][axis] - 2. * points[index + 1][axis] + points[index + 2][axis])
+                        .abs()
+                })
+            })
+            .fold(0., f64::max);
+        let tolerance = curve.widths.0.max(curve.widths.1) * 0.05;
+        let subdivisions = if bend > 0. && tolerance > 0. {
+            ((std::f64::consts::SQRT_2 * 6. * bend / (8. * tolerance)).log2() / 2.)
+                .floor()
+                .clamp(0., f64::from(MAX_SUBDIVISIONS)) as u32
+        } else {
+            0
+        };
+        hit_piece(
+            curve,
+            points,
+            (0., 1.),
+            subdivisions,
+            (
+                *ray_length_min_max.start() * frame.length,
+                *ray_length_min_max.end() * frame.length,
+            ),
+        )
+    }
+}
+
+/// Nearest hit with the piece of `curve` between `u_range`, whose control points in the frame of the Ray are
+/// `points`, with a depth within `depth_range`.
+fn hit_piece(
+    curve: &BezierCurve,
+    points: [Direction; 4],
+    (u_start, u_end): (f64, f64),
+    subdivisions: u32,
+    (depth_min, depth_max): (f64, f64),
+) -> Option<CurveHit> {
+    let radius = curve.width(u_start).max(curve.width(u_end)) / 2.;
+    let outside = |axis: usize, low: f64, high: f64| {
+        let (minimum, maximum) = points.iter().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(minimum, maximum), point| (minimum.min(point[axis]), maximum.max(point[axis])),
+        );
+        minimum - radius > high || maximum + radius < low
+    };
+    if outside(0, 0., 0.) || outside(1, 0., 0.) || outside(2, depth_min, depth_max) {
+        return None;
+    }
+
+    if subdivisions > 0 {
+        let (first, second) = split(points);
+        let u_middle = (u_start + u_end) / 2.;
+        let near = hit_piece(
+            curve,
+            first,
+            (u_start, u_middle),
+            subdivisions - 1,
+            (depth_min, depth_max),
+        );
+        let depth_max = near.as_ref().map_or(depth_max, |hit| hit.depth);
+        let far = hit_piece(
+            curve,
+            second,
+            (u_middle, u_end),
+            subdivisions - 1,
+            (depth_min, depth_max),
+        );
+        return far.or(near);
+    }
+
+    // The Ray must be past the ends of the piece, or the pieces on both sides of a split would be hit
+    let [first, second, third, last] = points;
+    if (second[1] - first[1]) * -first[1] + first[0] * (first[0] - second[0]) < 0.
+        || (third[1] - last[1]) * -last[1] + last[0] * (last[0] - third[0]) < 0.
+    {
+        return None;
+    }
+
+    // Closest point to the Ray on the line from the start to the end of the piece
+    let line = last - first;
+    let length_squared = line[0] * line[0] + line[1] * line[1];
+    if length_squared == 0. {
+        return None;
+    }
+    let w = ((-first[0] * line[0] - first[1] * line[1]) / length_squared).clamp(0., 1.);
+    let u = u_start + (u_end - u_start) * w;
+    let width = curve.width(u);
+    let (point, tangent) = evaluate(points, w);
+    let distance_squared = point[0] * point[0] + point[1] * point[1];
+    if distance_squared > width * width / 4. || !(depth_min..=depth_max).contains(&point[2]) {
+        return None;
+    }
+    // Positive on the side the tangent turns to when seen from the Ray
+    let side = point[1] * tangent[0] - point[0] * tangent[1];
+    let offset = (distance_squared.sqrt() / (width / 2.)).min(1.);
+    Some(CurveHit {
+        u,
+        offset: if side >= 0. { offset } else { -offset },
+        depth: point[2],
+    })
+}
+
+/// Splits a Bézier curve in half.
+fn split(points: [Direction; 4]) -> ([Direction; 4], [Direction; 4]) {
+    let [a, b, c, d] = points;
+    let (ab, bc, cd) = ((a + b) / 2., (b + c) / 2., (c + d) / 2.);
+    let (abc, bcd) = ((ab + bc) / 2., (bc + cd) / 2.);
+    let middle = (abc + bcd) / 2.;
+    ([a, ab, abc, middle], [middle, bcd, cd, d])
+}
+
+/// Point and derivative of a Bézier curve at `t`.
+fn evaluate(points: [Direction; 4], t: f64) -> (Direction, Direction) {
+    let lerp = |lhs: Direction, rhs: Direction| lhs * (1. - t) + rhs * t;
+    let [a, b, c, d] = points;
+    let (ab, bc, cd) = (lerp(a, b), lerp(b, c), lerp(c, d));
+    let (abc, bcd) = (lerp(ab, bc), lerp(bc, cd));
+    let derivative = (bcd - abc) * 3.;
+    (
+        lerp(abc, bcd),
+        if derivative.is_zero() {
+            d - a
+        } else {
+            derivative
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_hit_test() {
+        // Straight curve along X, 0.2 wide at the start and 0 at the end
+        let curves = Curves::new(
+            vec![BezierCurve::new(
+                [0., 1., 2., 3.].map(|x| Point::new(x, 0., 0.)),
+                (0.2, 0.),
+            )],
+            CurveShape::Cylinder,
+        );
+        let ray = Ray::new(Point::new(0.75, 0.05, 5.), Direction::new(0., 0., -1.));
+        let hit = curves
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the curve");
+        assert!((hit.distance_from_ray - 5.).abs() < 1e-9);
+        assert!((hit.uv.0 - 0.25).abs() < 1e-9);
+        // Halfway from the center line to the edge, where the curve is 0.15 wide
+        assert!(((hit.uv.1 - 0.5).abs() - 1. / 3.).abs() < 1e-9);
+        assert!((hit.normal.length() - 1.).abs() < 1e-9 && hit.normal[2] > 0.);
+        assert_eq!(hit.tangent, Some(Direction::new(1., 0., 0.)));
+
+        // Past the thin end
+        let ray = Ray::new(Point::new(2.9, 0.05, 5.), Direction::new(0., 0., -1.));
+        assert!(curves.hit(&ray, 0.001..=f64::INFINITY).is_none());
+    }
+}
@@ -0,0 +1,715 @@
+use std::sync::Arc;
+
+use crate::{
+    description::{describe_list, read_list, Describe, DescriptionError, Tokens},
+    Image,
+};
+
+use super::{
+    BezierCurve, BezierPatch, CsgOperation, CurveShape, Curves, DisplacedMesh, Heightfield, Object,
+    ObjectGeometry, ObjectMaterial, Sdf, SdfTracing, SubdivisionSurface, Texture, TriangleMesh,
+};
+
+impl Describe for Object {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.geometry.describe(description);
+        self.material.describe(description);
+        if let Some(transform) = &self.transform {
+            description.push("transform".to_owned());
+            transform.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok(Self {
+            geometry: Describe::read(tokens)?,
+            material: Describe::read(tokens)?,
+            transform: match tokens.optional_word() {
+                Some("transform") => Some(Describe::read(tokens)?),
+                Some(word) => return tokens.unknown_variant("object modifier", word),
+                None => None,
+            },
+        })
+    }
+}
+
+impl Describe for ObjectGeometry {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Sphere { center, radius } => {
+                description.push("sphere".to_owned());
+                center.describe(description);
+                radius.describe(description);
+            }
+            Self::Plane { point, normal } => {
+                description.push("plane".to_owned());
+                point.describe(description);
+                normal.describe(description);
+            }
+            Self::Disk {
+                center,
+                normal,
+                radius,
+            } => {
+                description.push("disk".to_owned());
+                center.describe(description);
+                normal.describe(description);
+                radius.describe(description);
+            }
+            Self::Quad { corner, u, v } => {
+                description.push("quad".to_owned());
+                corner.describe(description);
+                u.describe(description);
+                v.describe(description);
+            }
+            Self::BilinearPatch { corners } => {
+                description.push("bilinear_patch".to_owned());
+                corners.describe(description);
+            }
+            Self::BezierPatch { patch } => {
+                description.push("bezier_patch".to_owned());
+                patch.control_points().describe(description);
+            }
+            Self::Box { minimum, maximum } => {
+                description.push("box".to_owned());
+                minimum.describe(description);
+                maximum.describe(description);
+            }
+            Self::Cylinder {
+                base,
+                axis,
+                radius,
+                capped,
+            } => {
+                description.push("cylinder".to_owned());
+                base.describe(description);
+                axis.describe(description);
+                radius.describe(description);
+                describe_capped(*capped, description);
+            }
+            Self::Cone {
+                base,
+                axis,
+                base_radius,
+                top_radius,
+                capped,
+            } => {
+                description.push("cone".to_owned());
+                base.describe(description);
+                axis.describe(description);
+                base_radius.describe(description);
+                top_radius.describe(description);
+                describe_capped(*capped, description);
+            }
+            Self::Capsule { start, end, radius } => {
+                description.push("capsule".to_owned());
+                start.describe(description);
+                end.describe(description);
+                radius.describe(description);
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => {
+                description.push("torus".to_owned());
+                center.describe(description);
+                axis.describe(description);
+                major_radius.describe(description);
+                minor_radius.describe(description);
+            }
+            Self::SignedDistance { function, tracing } => {
+                description.push("sdf".to_owned());
+                tracing.describe(description);
+                function.describe(description);
+            }
+            Self::Heightfield {
+                heights,
+                minimum,
+                size,
+            } => {
+                description.push("heightfield".to_owned());
+                minimum.describe(description);
+                size.describe(description);
+                heights.columns().describe(description);
+                heights.rows().describe(description);
+                for height in heights.heights() {
+                    height.describe(description);
+                }
+            }
+            Self::Mesh { mesh } => {
+                description.push("mesh".to_owned());
+                mesh.describe(description);
+            }
+            Self::DisplacedMesh { mesh } => {
+                description.push("displaced_mesh".to_owned());
+                mesh.describe(description);
+            }
+            Self::Subdivision { surface } => {
+                description.push("subdivision".to_owned());
+                surface.describe(description);
+            }
+            Self::Curves { curves } => {
+                description.push("curves".to_owned());
+                curves.describe(description);
+            }
+            // Only the kind is written, reading it back fails
+            Self::Custom { .. } => description.push("custom".to_owned()),
+            Self::Csg {
+                operation,
+                left,
+                right,
+            } => {
+                description.push("csg".to_owned());
+                description.push(
+                    match operation {
+                        CsgOperation::Union => "union",
+                        CsgOperation::Intersection => "intersection",
+                        CsgOperation::Difference => "difference",
+                    }
+                    .to_owned(),
+                );
+                left.describe(description);
+                right.describe(description);
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "sphere" => Ok(Self::Sphere {
+                center: Describe::read(tokens)?,
+                radius: tokens.value()?,
+            }),
+            "plane" => Ok(Self::Plane {
+                point: Describe::read(tokens)?,
+                normal: Describe::read(tokens)?,
+            }),
+            "disk" => Ok(Self::Disk {
+                center: Describe::read(tokens)?,
+                normal: Describe::read(tokens)?,
+                radius: tokens.value()?,
+            }),
+            "quad" => Ok(Self::Quad {
+                corner: Describe::read(tokens)?,
+                u: Describe::read(tokens)?,
+                v: Describe::read(tokens)?,
+            }),
+            "bilinear_patch" => Ok(Self::BilinearPatch {
+                corners: Describe::read(tokens)?,
+            }),
+            "bezier_patch" => Ok(Self::BezierPatch {
+                patch: BezierPatch::new(Describe::read(tokens)?),
+            }),
+            "box" => Ok(Self::Box {
+                minimum: Describe::read(tokens)?,
+                maximum: Describe::read(tokens)?,
+            }),
+            "cylinder" => Ok(Self::Cylinder {
+                base: Describe::read(tokens)?,
+                axis: Describe::read(tokens)?,
+                radius: tokens.value()?,
+                capped: read_capped(tokens)?,
+            }),
+            "cone" => Ok(Self::Cone {
+                base: Describe::read(tokens)?,
+                axis: Describe::read(tokens)?,
+                base_radius: tokens.value()?,
+                top_radius: tokens.value()?,
+                capped: read_capped(tokens)?,
+            }),
+            "capsule" => Ok(Self::Capsule {
+                start: Describe::read(tokens)?,
+                end: Describe::read(tokens)?,
+                radius: tokens.value()?,
+            }),
+            "torus" => Ok(Self::Torus {
+                center: Describe::read(tokens)?,
+                axis: Describe::read(tokens)?,
+                major_radius: tokens.value()?,
+                minor_radius: tokens.value()?,
+            }),
+            "sdf" => {
+                let tracing = Describe::read(tokens)?;
+                Ok(Self::SignedDistance {
+                    function: Describe::read(tokens)?,
+                    tracing,
+                })
+            }
+            "heightfield" => {
+                let minimum = Describe::read(tokens)?;
+                let size = Describe::read(tokens)?;
+                let columns: usize = tokens.value()?;
+                let rows: usize = tokens.value()?;
+                let heights = (0..columns.saturating_mul(rows))
+                    .map(|_| tokens.value())
+                    .collect::<Result<_, _>>()?;
+                Ok(Self::Heightfield {
+                    heights: Heightfield::new(columns, rows, heights).ok_or_else(|| {
+                        tokens.error("heightfield needs at least 2 columns and rows")
+                    })?,
+                    minimum,
+                    size,
+                })
+            }
+            "mesh" => Ok(Self::Mesh {
+                mesh: Describe::read(tokens)?,
+            }),
+            "displaced_mesh" => Ok(Self::DisplacedMesh {
+                mesh: Describe::read(tokens)?,
+            }),
+            "subdivision" => Ok(Self::Subdivision {
+                surface: Describe::read(tokens)?,
+            }),
+            "curves" => Ok(Self::Curves {
+                curves: Describe::read(tokens)?,
+            }),
+            "custom" => Err(tokens.error("custom geometry cannot be read from a description")),
+            "csg" => Ok(Self::Csg {
+                operation: match tokens.word()? {
+                    "union" => CsgOperation::Union,
+                    "intersection" => CsgOperation::Intersection,
+                    "difference" => CsgOperation::Difference,
+                    word => return tokens.unknown_variant("csg operation", word),
+                },
+                left: Box::new(Describe::read(tokens)?),
+                right: Box::new(Describe::read(tokens)?),
+            }),
+            word => tokens.unknown_variant("geometry", word),
+        }
+    }
+}
+
+impl Describe for SdfTracing {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.epsilon.describe(description);
+        self.max_steps.describe(description);
+        self.max_distance.describe(description);
+        self.step_scale.describe(description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok(Self {
+            epsilon: tokens.value()?,
+            max_steps: tokens.value()?,
+            max_distance: tokens.value()?,
+            step_scale: tokens.value()?,
+        })
+    }
+}
+
+impl Describe for Sdf {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Sphere { radius } => {
+                description.push("sphere".to_owned());
+                radius.describe(description);
+            }
+            Self::Box {
+                half_extents,
+                rounding,
+            } => {
+                description.push("box".to_owned());
+                half_extents.describe(description);
+                rounding.describe(description);
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                description.push("torus".to_owned());
+                major_radius.describe(description);
+                minor_radius.describe(description);
+            }
+            Self::Mandelbulb { power, iterations } => {
+                description.push("mandelbulb".to_owned());
+                power.describe(description);
+                iterations.describe(description);
+            }
+            Self::MengerSponge { iterations } => {
+                description.push("menger".to_owned());
+                iterations.describe(description);
+            }
+            Self::Transform { transform, child } => {
+                description.push("transform".to_owned());
+                transform.describe(description);
+                child.describe(description);
+            }
+            Self::Scale { factor, child } => {
+                description.push("scale".to_owned());
+                factor.describe(description);
+                child.describe(description);
+            }
+            Self::Union { left, right } => {
+                description.push("union".to_owned());
+                left.describe(description);
+                right.describe(description);
+            }
+            Self::Intersection { left, right } => {
+                description.push("intersection".to_owned());
+                left.describe(description);
+                right.describe(description);
+            }
+            Self::Difference { left, right } => {
+                description.push("difference".to_owned());
+                left.describe(description);
+                right.describe(description);
+            }
+            Self::SmoothUnion {
+                left,
+                right,
+                smoothness,
+            } => {
+                description.push("smooth_union".to_owned());
+                smoothness.describe(description);
+                left.describe(description);
+                right.describe(description);
+            }
+            Self::Repeat { period, child } => {
+                description.push("repeat".to_owned());
+                period.describe(description);
+                child.describe(description);
+            }
+            Self::Twist { rate, child } => {
+                description.push("twist".to_owned());
+                rate.describe(description);
+                child.describe(description);
+            }
+            Self::Displace {
+                amplitude,
+                frequency,
+                child,
+            } => {
+                description.push("displace".to_owned());
+                amplitude.describe(description);
+                frequency.describe(description);
+                child.describe(description);
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let child = |tokens: &mut Tokens| Sdf::read(tokens).map(Box::new);
+        match tokens.word()? {
+            "sphere" => Ok(Self::Sphere {
+                radius: tokens.value()?,
+            }),
+            "box" => Ok(Self::Box {
+                half_extents: Describe::read(tokens)?,
+                rounding: tokens.value()?,
+            }),
+            "torus" => Ok(Self::Torus {
+                major_radius: tokens.value()?,
+                minor_radius: tokens.value()?,
+            }),
+            "mandelbulb" => Ok(Self::Mandelbulb {
+                power: tokens.value()?,
+                iterations: tokens.value()?,
+            }),
+            "menger" => Ok(Self::MengerSponge {
+                iterations: tokens.value()?,
+            }),
+            "transform" => Ok(Self::Transform {
+                transform: Describe::read(tokens)?,
+                child: child(tokens)?,
+            }),
+            "scale" => Ok(Self::Scale {
+                factor: tokens.value()?,
+                child: child(tokens)?,
+            }),
+            "union" => Ok(Self::Union {
+                left: child(tokens)?,
+                right: child(tokens)?,
+            }),
+            "intersection" => Ok(Self::Intersection {
+                left: child(tokens)?,
+                right: child(tokens)?,
+            }),
+            "difference" => Ok(Self::Difference {
+                left: child(tokens)?,
+                right: child(tokens)?,
+            }),
+            "smooth_union" => Ok(Self::SmoothUnion {
+                smoothness: tokens.value()?,
+                left: child(tokens)?,
+                right: child(tokens)?,
+            }),
+            "repeat" => Ok(Self::Repeat {
+                period: Describe::read(tokens)?,
+                child: child(tokens)?,
+            }),
+            "twist" => Ok(Self::Twist {
+                rate: tokens.value()?,
+                child: child(tokens)?,
+            }),
+            "displace" => Ok(Self::Displace {
+                amplitude: tokens.value()?,
+                frequency: tokens.value()?,
+                child: child(tokens)?,
+            }),
+            word => tokens.unknown_variant("sdf", word),
+        }
+    }
+}
+
+impl Describe for TriangleMesh {
+    fn describe(&self, description: &mut Vec<String>) {
+        describe_list(self.positions(), description);
+        describe_list(self.triangles(), description);
+        describe_attribute("normals", self.normals(), description);
+        describe_attribute("uvs", self.uvs(), description);
+        describe_attribute("colours", self.colours(), description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let positions = read_list(tokens)?;
+        let mut mesh = TriangleMesh::new(positions, read_list(tokens)?)
+            .ok_or_else(|| tokens.error("mesh has a triangle with a missing vertex"))?;
+        let mismatch = |tokens: &Tokens| tokens.error("mesh needs one value per vertex");
+        if let Some(normals) = read_attribute(tokens, "normals")? {
+            mesh = mesh.with_normals(normals).ok_or_else(|| mismatch(tokens))?;
+        }
+        if let Some(uvs) = read_attribute(tokens, "uvs")? {
+            mesh = mesh.with_uvs(uvs).ok_or_else(|| mismatch(tokens))?;
+        }
+        if let Some(colours) = read_attribute(tokens, "colours")? {
+            mesh = mesh.with_colours(colours).ok_or_else(|| mismatch(tokens))?;
+        }
+        Ok(mesh)
+    }
+}
+
+impl Describe for DisplacedMesh {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.scale().describe(description);
+        self.subdivisions().describe(description);
+        self.displacement().describe(description);
+        self.mesh().describe(description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let scale = tokens.value()?;
+        let subdivisions = tokens.value()?;
+        let displacement = Describe::read(tokens)?;
+        Ok(DisplacedMesh::new(
+            Describe::read(tokens)?,
+            displacement,
+            scale,
+            subdivisions,
+        ))
+    }
+}
+
+impl Describe for SubdivisionSurface {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.level().describe(description);
+        describe_list(self.positions(), description);
+        describe_list(self.faces(), description);
+        describe_list(self.creases(), description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let level = tokens.value()?;
+        let positions = read_list(tokens)?;
+        SubdivisionSurface::new(positions, read_list(tokens)?, level)
+            .ok_or_else(|| tokens.error("subdivision face needs 3 vertices that are in the cage"))?
+            .with_creases(read_list(tokens)?)
+            .ok_or_else(|| tokens.error("subdivision crease has a missing vertex"))
+    }
+}
+
+impl Describe for Curves {
+    fn describe(&self, description: &mut Vec<String>) {
+        description.push(
+            match self.shape() {
+                CurveShape::Ribbon => "ribbon",
+                CurveShape::Cylinder => "cylinder",
+            }
+            .to_owned(),
+        );
+        describe_list(self.curves(), description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let shape = match tokens.word()? {
+            "ribbon" => CurveShape::Ribbon,
+            "cylinder" => CurveShape::Cylinder,
+            word => return tokens.unknown_variant("curve shape", word),
+        };
+        Ok(Curves::new(read_list(tokens)?, shape))
+    }
+}
+
+impl Describe for BezierCurve {
+    fn describe(&self, description: &mut Vec<String>) {
+        self.control_points.describe(description);
+        self.widths.describe(description);
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        Ok(BezierCurve::new(
+            Describe::read(tokens)?,
+            Describe::read(tokens)?,
+        ))
+    }
+}
+
+/// Writes `name` and the values of an attribute of the vertices of a mesh, or `none` if the mesh does not have
+/// the attribute.
+fn describe_attribute<T: Describe>(
+    name: &str,
+    values: Option<&[T]>,
+    description: &mut Vec<String>,
+) {
+    match values {
+        Some(values) => {
+            description.push(name.to_owned());
+            describe_list(values, description);
+        }
+        None => description.push("none".to_owned()),
+    }
+}
+
+fn read_attribute<T: Describe>(
+    tokens: &mut Tokens,
+    name: &str,
+) -> Result<Option<Vec<T>>, DescriptionError> {
+    match tokens.word()? {
+        "none" => Ok(None),
+        word if word == name => read_list(tokens).map(Some),
+        word => tokens.unknown_variant(name, word),
+    }
+}
+
+impl Describe for Texture {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Solid(colour) => {
+                description.push("solid".to_owned());
+                colour.describe(description);
+            }
+            Self::VertexColour => description.push("vertex_colour".to_owned()),
+            Self::Image(image) => {
+                description.push("image".to_owned());
+                let (width, height) = image.get_dimensions();
+                width.describe(description);
+                height.describe(description);
+                for pixel in image.get_pixels() {
+                    pixel.describe(description);
+                }
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "solid" => Ok(Self::Solid(Describe::read(tokens)?)),
+            "vertex_colour" => Ok(Self::VertexColour),
+            "image" => {
+                let (width, height): (usize, usize) = (tokens.value()?, tokens.value()?);
+                // Each pixel takes three values, so the size can be checked before the image is allocated
+                width
+                    .checked_mul(height)
+                    .and_then(|pixels| pixels.checked_mul(3))
+                    .filter(|values| *values <= tokens.remaining())
+                    .ok_or_else(|| tokens.error("image texture has more pixels than values"))?;
+                let mut image = Image::new(width, height);
+                for pixel in image.get_pixels_mut() {
+                    *pixel = Describe::read(tokens)?;
+                }
+                Ok(Self::Image(Arc::new(image)))
+            }
+            word => tokens.unknown_variant("texture", word),
+        }
+    }
+}
+
+fn describe_capped(capped: bool, description: &mut Vec<String>) {
+    description.push(if capped { "capped" } else { "open" }.to_owned());
+}
+
+fn read_capped(tokens: &mut Tokens) -> Result<bool, DescriptionError> {
+    match tokens.word()? {
+        "capped" => Ok(true),
+        "open" => Ok(false),
+        word => tokens.unknown_variant("cap", word),
+    }
+}
+
+impl Describe for ObjectMaterial {
+    fn describe(&self, description: &mut Vec<String>) {
+        match self {
+            Self::Lambert { albedo } => {
+                description.push("lambert".to_owned());
+                albedo.describe(description);
+            }
+            Self::Metal {
+                albedo,
+                fuzzy_scatter,
+            } => {
+                description.push("metal".to_owned());
+                albedo.describe(description);
+                fuzzy_scatter.describe(description);
+            }
+            Self::Dialectric { refraction_index } => {
+                description.push("dialectric".to_owned());
+                refraction_index.describe(description);
+            }
+            Self::Hair {
+                absorption,
+                longitudinal_roughness,
+                azimuthal_roughness,
+                refraction_index,
+            } => {
+                description.push("hair".to_owned());
+                absorption.describe(description);
+                longitudinal_roughness.describe(description);
+                azimuthal_roughness.describe(description);
+                refraction_index.describe(description);
+            }
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        match tokens.word()? {
+            "lambert" => Ok(Self::Lambert {
+                albedo: Describe::read(tokens)?,
+            }),
+            "metal" => Ok(Self::Metal {
+                albedo: Describe::read(tokens)?,
+                fuzzy_scatter: tokens.value()?,
+            }),
+            "dialectric" => Ok(Self::Dialectric {
+                refraction_index: tokens.value()?,
+            }),
+            "hair" => Ok(Self::Hair {
+                absorption: Describe::read(tokens)?,
+                longitudinal_roughness: tokens.value()?,
+                azimuthal_roughness: tokens.value()?,
+                refraction_index: tokens.value()?,
+            }),
+            word => tokens.unknown_variant("material", word),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Colour;
+
+    #[test]
+    fn image_texture_read_test() {
+        let read = |text: &str| Texture::read(&mut Tokens::new(text, 1));
+
+        let Ok(Texture::Image(image)) = read("image 2 1 0 0 0 1 1 1") else {
+            panic!("Expect image texture read");
+        };
+        assert_eq!(image.get_dimensions(), (2, 1));
+        assert_eq!(image.get_pixels()[1], Colour::new(1., 1., 1.));
+        // The size is checked before the image is allocated
+        assert!(read("image 4294967296 4294967296 0 0 0").is_err());
+        assert!(read("image 2 1 0 0 0").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    ray::Ray,
+    vector::{Colour, Direction, Point},
+};
+
+use super::{mesh::vertex_normals, Aabb, GeometryHit, Texture, TriangleMesh};
+
+/// Most times each triangle is split in four, a triangle becomes at most 4096 triangles.
+const MAX_SUBDIVISIONS: u32 = 6;
+
+#[derive(Debug, Clone)]
+/// TriangleMesh pushed along its normals by the height of a Texture, the average of its channels.</br>
+/// The mesh is subdivided and displaced the first time a Ray comes near it, and the result is shared between
+/// clones.
+pub struct DisplacedMesh {
+    mesh: TriangleMesh,
+    displacement: Texture,
+    scale: f64,
+    subdivisions: u32,
+    displaced: Arc<OnceLock<TriangleMesh>>,
+}
+
+/// Vertex of the mesh while it is subdivided, blended into new vertices halfway along the edges.
+#[derive(Clone, Copy)]
+struct Vertex {
+    position: Direction,
+    normal: Direction,
+    uv: (f64, f64),
+    colour: Option<Colour>,
+}
+
+impl Vertex {
+    fn halfway(&self, other: &Self) -> Self {
+        let normal = self.normal + other.normal;
+        Self {
+            position: (self.position + other.position) / 2.,
+            normal: if normal.is_zero() {
+                normal
+            } else {
+                normal.unit_vector()
+            },
+            uv: ((self.uv.0 + other.uv.0) / 2., (self.uv.1 + other.uv.1) / 2.),
+            colour: self
+                .colour
+                .zip(other.colour)
+                .map(|(lhs, rhs)| (lhs + rhs) * 0.5),
+        }
+    }
+}
+
+impl DisplacedMesh {
+    /// Creates the DisplacedMesh from `mesh`, with each triangle split in four `subdivisions` times, up to 6
+    /// times, before the vertices are moved along their normals by `scale` times the height of `displacement`.
+    /// </br>
+    /// The normals of the mesh, or the average normals of its triangles if it has none, give the directions of
+    /// the displacement. The displaced mesh gets new normals from its triangles.
+    /// # Note
+    /// Vertices repeated to split the normals or UVs of the mesh are displaced separately, so the surface can
+    /// crack open along those seams. Meshes without UVs are displaced by the texture at UV (0, 0).
+    pub fn new(mesh: TriangleMesh, displacement: Texture, scale: f64, subdivisions: u32) -> Self {
+        Self {
+            mesh,
+            displacement,
+            scale,
+            subdivisions: subdivisions.min(MAX_SUBDIVISIONS),
+            displaced: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Mesh before it is displaced.
+    pub fn mesh(&self) -> &TriangleMesh {
+        &self.mesh
+    }
+
+    pub fn displacement(&self) -> &Texture {
+        &self.displacement
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn subdivisions(&self) -> u32 {
+        self.subdivisions
+    }
+
+    /// Mesh after it is subdivided and displaced, which is built by the first call.
+    pub fn displaced(&self) -> &TriangleMesh {
+        self.displaced.get_or_init(|| self.displace())
+    }
+
+    /// Box that contains the mesh pushed by the highest displacement the texture can give, so the mesh does
+    /// not need to be displaced yet.
+    pub(super) fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = self.mesh.bounding_box()?;
+        let height = match &self.displacement {
+            Texture::Solid(colour) => height(*colour).abs(),
+            Texture::VertexColour => self.mesh.colours().map_or(1., |colours| {
+                colours
+                    .iter()
+                    .map(|colour| height(*colour).abs())
+                    .fold(0., f64::max)
+            }),
+            Texture::Image(image) => image
+                .get_pixels()
+                .iter()
+                .map(|colour| height(*colour).abs())
+                .fold(0., f64::max),
+        };
+        let reach = (self.scale * height).abs();
+        Some(Aabb::new(
+            bounds.minimum + Direction::new(-reach, -reach, -reach),
+            bounds.maximum + Direction::new(reach, reach, reach),
+        ))
+    }
+
+    pub(super) fn hit(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        self.displaced().hit(ray, ray_length_min_max)
+    }
+
+    /// Every hit of `ray` with the displaced mesh within `ray_length_min_max`, in order along the Ray.
+    pub(super) fn hits(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Vec<GeometryHit> {
+        self.displaced().hits(ray, ray_length_min_max)
+    }
+
+    fn displace(&self) -> TriangleMesh {
+        let positions = self.mesh.positions();
+        let normals = self.mesh.normals().map_or_else(
+            || vertex_normals(positions, self.mesh.triangles()),
+            |normals| {
+                normals
+                    .iter()
+                    .map(|normal| {
+                        if normal.is_zero() {
+                            *normal
+                        } else {
+                            normal.unit_vector()
+                        }
+                    })
+                    .collect()
+            },
+        );
+        let mut vertices = (0..positions.len())
+            .map(|index| Vertex {
+                position: Point::default().point_towards(positions[index]),
+                normal: normals[index],
+                uv: self.mesh.uvs().map_or((0., 0.), |uvs| uvs[index]),
+                colour: self.mesh.colours().map(|colours| colours[index]),
+            })
+            .collect::<Vec<_>>();
+        let mut triangles = self.mesh.triangles().to_vec();
+        for _ in 0..self.subdivisions {
+            triangles = subdivide(&mut vertices, &triangles);
+        }
+
+        let positions = vertices
+            .iter()
+            .map(|vertex| {
+                let height = height(self.displacement.value_at(vertex.uv, vertex.colour));
+                Point::default() + vertex.position + vertex.normal * (self.scale * height)
+            })
+            .collect::<Vec<_>>();
+        let normals = vertex_normals(&positions, &triangles);
+        let mut mesh = TriangleMesh::new(positions, triangles)
+            .and_then(|mesh| mesh.with_normals(normals))
+            .expect("Subdivision keeps the triangles within the vertices");
+        if self.mesh.uvs().is_some() {
+            mesh = mesh
+                .with_uvs(vertices.iter().map(|vertex| vertex.uv).collect())
+                .expect("Subdivision keeps one UV per vertex");
+        }
+        if self.mesh.colours().is_some() {
+            mesh = mesh
+                .with_colours(vertices.iter().filter_map(|vertex| vertex.colour).collect())
+                .expect("Subdivision keeps one colour per vertex");
+        }
+        mesh
+    }
+}
+
+/// Height of a colour of the displacement texture.
+fn height(colour: Colour) -> f64 {
+    (colour[0] + colour[1] + colour[2]) / 3.
+}
+
+/// Splits each of `triangles` in four at the middle of its edges, adding the new vertices to `vertices`.
+/// Triangles that share an edge share the vertex at its middle.
+fn subdivide(vertices: &mut Vec<Vertex>, triangles: &[[usize; 3]]) -> Vec<[usize; 3]> {
+    let mut middles = HashMap::new();
+    let mut middle = |vertices: &mut Vec<Vertex>, a: usize, b: usize| {
+        *middles.entry((a.min(b), a.max(b))).or_insert_with(|| {
+            vertices.push(vertices[a].halfway(&vertices[b]));
+            vertices.len() - 1
+        })
+    };
+    let mut split = Vec::with_capacity(triangles.len() * 4);
+    for &[a, b, c] in triangles {
+        let (ab, bc, ca) = (
+            middle(vertices, a, b),
+            middle(vertices, b, c),
+            middle(vertices, c, a),
+        );
+        split.extend([[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]]);
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displaced_mesh_test() {
+        // Square in the XY plane facing +Z, pushed up by half of a solid grey
+        let square = TriangleMesh::new(
+            vec![
+                Point::new(0., 0., 0.),
+                Point::new(1., 0., 0.),
+                Point::new(1., 1., 0.),
+                Point::new(0., 1., 0.),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+        .expect("Square should be a valid mesh");
+        let displaced = DisplacedMesh::new(square, Colour::new(0.4, 0.4, 0.4).into(), 0.5, 2);
+        let bounds = displaced
+            .bounding_box()
+            .expect("Displaced square should be bounded");
+        assert!((bounds.maximum[2] - 0.2).abs() < 1e-9);
+
+        assert_eq!(displaced.displaced().triangles().len(), 32);
+        // Shared edges are split once, so the 5 by 5 grid of vertices has no cracks
+        assert_eq!(displaced.displaced().positions().len(), 25);
+        let ray = Ray::new(Point::new(0.3, 0.6, 5.), Direction::new(0., 0., -1.));
+        let hit = displaced
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the displaced square");
+        assert!((hit.distance_from_ray - 4.8).abs() < 1e-9);
+        assert!((hit.normal - Direction::new(0., 0., 1.)).length() < 1e-9);
+    }
+}
@@ -1,24 +1,428 @@
-use std::ops::RangeInclusive;
+use std::{f64::consts::PI, ops::RangeInclusive, sync::Arc};
 
 use crate::{
     ray::Ray,
-    vector::{Direction, Point},
+    vector::{Colour, Direction, Point},
 };
 
+use super::{
+    csg, patch, polynomial::solve_quadratic, quadric, sdf, Aabb, BezierPatch, CsgOperation, Curves,
+    DisplacedMesh, Heightfield, Hittable, Sdf, SdfTracing, SubdivisionSurface, TriangleMesh,
+};
+
+#[derive(Debug, Clone)]
 pub enum ObjectGeometry {
-    Sphere { center: Point, radius: f64 },
+    Sphere {
+        center: Point,
+        radius: f64,
+    },
+    /// Infinite plane through `point`, facing `normal`.
+    Plane {
+        point: Point,
+        normal: Direction,
+    },
+    /// Flat circle at `center`, facing `normal`.
+    Disk {
+        center: Point,
+        normal: Direction,
+        radius: f64,
+    },
+    /// Parallelogram with a corner at `corner` and sides `u` and `v`, facing `u` cross `v`.
+    Quad {
+        corner: Point,
+        u: Direction,
+        v: Direction,
+    },
+    /// Axis-aligned box between the corners `minimum` and `maximum`, the six sides face outwards.
+    Box {
+        minimum: Point,
+        maximum: Point,
+    },
+    /// Cylinder from `base` to `base + axis`, closed at both ends if `capped`.
+    Cylinder {
+        base: Point,
+        axis: Direction,
+        radius: f64,
+        capped: bool,
+    },
+    /// Cone frustum from `base` to `base + axis`, a radius of zero at one end makes a full cone.
+    Cone {
+        base: Point,
+        axis: Direction,
+        base_radius: f64,
+        top_radius: f64,
+        capped: bool,
+    },
+    /// Cylinder from `start` to `end` closed by half spheres.
+    Capsule {
+        start: Point,
+        end: Point,
+        radius: f64,
+    },
+    /// Ring around `axis` through `center`, the tube of `minor_radius` is at `major_radius` from the center.
+    Torus {
+        center: Point,
+        axis: Direction,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    /// Surface where `function` is zero, found by sphere tracing with the settings of `tracing`.
+    SignedDistance {
+        function: Sdf,
+        tracing: SdfTracing,
+    },
+    /// Terrain from `heights`, stretched over `minimum` to `minimum + size` on X and Z, where a height of 1
+    /// rises `size` on Y.
+    Heightfield {
+        heights: Heightfield,
+        minimum: Point,
+        size: Direction,
+    },
+    /// Bilinear patch through `corners`, at UV (0, 0), (1, 0), (0, 1), and (1, 1), facing the derivative along U
+    /// cross the derivative along V. Four corners on a plane make a quad, any other four a curved patch.
+    BilinearPatch {
+        corners: [Point; 4],
+    },
+    /// Bicubic Bézier `patch`, hit through its tessellation.
+    BezierPatch {
+        patch: BezierPatch,
+    },
+    /// Triangles of `mesh`, a closed mesh can be combined with CSG.
+    Mesh {
+        mesh: TriangleMesh,
+    },
+    /// Triangles of `mesh` after they are subdivided and displaced, a closed mesh can be combined with CSG.
+    DisplacedMesh {
+        mesh: DisplacedMesh,
+    },
+    /// Smooth surface refined from the cage of `surface`, a closed cage can be combined with CSG.
+    Subdivision {
+        surface: SubdivisionSurface,
+    },
+    /// Strands of `curves`, for hair, fur, and grass. Curves are open, so they are not combined with CSG.
+    Curves {
+        curves: Curves,
+    },
+    /// Geometry defined outside of this crate, which can be combined with CSG if it gives every hit with
+    /// [`Hittable::hits`].
+    /// # Note
+    /// Custom geometry cannot be read back from a scene description, so it cannot be sent to the workers of a
+    /// distributed render.
+    Custom {
+        geometry: Arc<dyn Hittable>,
+    },
+    /// Combination of two closed geometries, see [`CsgOperation`].
+    Csg {
+        operation: CsgOperation,
+        left: Box<ObjectGeometry>,
+        right: Box<ObjectGeometry>,
+    },
+}
+
+/// Intersection of a Ray with an ObjectGeometry.
+pub struct GeometryHit {
+    pub distance_from_ray: f64,
+    pub point_of_intersection: Point,
+    /// Normal facing out of the surface, not necessarily against the Ray.
+    pub normal: Direction,
+    /// Surface coordinates of the point of intersection, between [0.0..1.0] on bounded surfaces.
+    pub uv: (f64, f64),
+    /// Colour of the vertices interpolated at the point of intersection, for meshes with vertex colours.
+    pub vertex_colour: Option<Colour>,
+    /// Direction along the surface where U grows, for curves.
+    pub tangent: Option<Direction>,
 }
 
 impl ObjectGeometry {
-    pub fn hit(
-        &self,
-        ray: &Ray,
-        ray_length_min_max: RangeInclusive<f64>,
-    ) -> Option<(f64, Point, Direction)> {
+    pub fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Option<GeometryHit> {
         match self {
             Self::Sphere { center, radius } => {
                 Self::hit_sphere(center, *radius, ray, ray_length_min_max)
             }
+            Self::Plane { point, normal } => {
+                Self::hit_plane(point, normal, ray, ray_length_min_max).map(
+                    |(distance_from_ray, point_of_intersection, normal)| {
+                        // Planes are unbounded, so the UV is the position on the plane in world units
+                        let (tangent, bitangent) = orthonormal_basis(normal);
+                        let on_plane = point.point_towards(point_of_intersection);
+                        GeometryHit {
+                            distance_from_ray,
+                            point_of_intersection,
+                            normal,
+                            uv: (on_plane.dot(tangent), on_plane.dot(bitangent)),
+                            vertex_colour: None,
+                            tangent: None,
+                        }
+                    },
+                )
+            }
+            Self::Disk {
+                center,
+                normal,
+                radius,
+            } => Self::hit_disk(center, normal, *radius, ray, ray_length_min_max),
+            Self::Quad { corner, u, v } => Self::hit_quad(corner, u, v, ray, ray_length_min_max),
+            Self::BilinearPatch { corners } => {
+                patch::hit_bilinear_patch(corners, ray, ray_length_min_max)
+            }
+            Self::BezierPatch { patch } => patch.hit(ray, ray_length_min_max),
+            Self::Box { minimum, maximum } => Self::box_crossings(minimum, maximum, ray)?
+                .into_iter()
+                .find(|(root, _)| ray_length_min_max.contains(root))
+                .map(|(root, axis)| Self::box_hit_at(minimum, maximum, ray, root, axis)),
+            Self::Cylinder {
+                base,
+                axis,
+                radius,
+                capped,
+            } => quadric::hit_cone(
+                base,
+                axis,
+                *radius,
+                *radius,
+                *capped,
+                ray,
+                ray_length_min_max,
+            ),
+            Self::Cone {
+                base,
+                axis,
+                base_radius,
+                top_radius,
+                capped,
+            } => quadric::hit_cone(
+                base,
+                axis,
+                *base_radius,
+                *top_radius,
+                *capped,
+                ray,
+                ray_length_min_max,
+            ),
+            Self::Capsule { start, end, radius } => {
+                quadric::hit_capsule(start, end, *radius, ray, ray_length_min_max)
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => quadric::hit_torus(
+                center,
+                axis,
+                *major_radius,
+                *minor_radius,
+                ray,
+                ray_length_min_max,
+            ),
+            // Combining needs every hit of the children
+            Self::Csg { .. } => self.hits(ray, ray_length_min_max).into_iter().next(),
+            Self::Mesh { mesh } => mesh.hit(ray, ray_length_min_max),
+            Self::DisplacedMesh { mesh } => mesh.hit(ray, ray_length_min_max),
+            Self::Subdivision { surface } => surface.hit(ray, ray_length_min_max),
+            Self::Curves { curves } => curves.hit(ray, ray_length_min_max),
+            Self::Custom { geometry } => geometry.hit(ray, ray_length_min_max),
+            Self::SignedDistance { function, tracing } => {
+                sdf::trace(function, tracing, ray, ray_length_min_max)
+            }
+            Self::Heightfield {
+                heights,
+                minimum,
+                size,
+            } => heights.hit(minimum, size, ray, ray_length_min_max),
+        }
+    }
+
+    /// Every hit of `ray` with the geometry within `ray_length_min_max`, in order along the Ray.</br>
+    /// Closed geometries alternate between hits entering, with the normal against the Ray, and hits leaving.
+    /// # Note
+    /// Sphere tracing stops at the first hit, so SignedDistance only gives one hit. Combine Sdfs with their
+    /// own operators instead of with CSG.
+    pub fn hits(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Vec<GeometryHit> {
+        match self {
+            Self::Sphere { center, radius } => {
+                Self::sphere_hits(center, *radius, ray, ray_length_min_max)
+            }
+            Self::Plane { .. }
+            | Self::Disk { .. }
+            | Self::Quad { .. }
+            | Self::BilinearPatch { .. }
+            | Self::BezierPatch { .. }
+            | Self::SignedDistance { .. }
+            | Self::Heightfield { .. }
+            | Self::Curves { .. } => self.hit(ray, ray_length_min_max).into_iter().collect(),
+            Self::Custom { geometry } => geometry.hits(ray, ray_length_min_max),
+            Self::Box { minimum, maximum } => {
+                Self::box_hits(minimum, maximum, ray, ray_length_min_max)
+            }
+            Self::Cylinder {
+                base,
+                axis,
+                radius,
+                capped,
+            } => quadric::cone_hits(
+                base,
+                axis,
+                *radius,
+                *radius,
+                *capped,
+                ray,
+                ray_length_min_max,
+            ),
+            Self::Cone {
+                base,
+                axis,
+                base_radius,
+                top_radius,
+                capped,
+            } => quadric::cone_hits(
+                base,
+                axis,
+                *base_radius,
+                *top_radius,
+                *capped,
+                ray,
+                ray_length_min_max,
+            ),
+            Self::Capsule { start, end, radius } => {
+                quadric::capsule_hits(start, end, *radius, ray, ray_length_min_max)
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => quadric::torus_hits(
+                center,
+                axis,
+                *major_radius,
+                *minor_radius,
+                ray,
+                ray_length_min_max,
+            ),
+            Self::Mesh { mesh } => mesh.hits(ray, ray_length_min_max),
+            Self::DisplacedMesh { mesh } => mesh.hits(ray, ray_length_min_max),
+            Self::Subdivision { surface } => surface.hits(ray, ray_length_min_max),
+            Self::Csg {
+                operation,
+                left,
+                right,
+            } => {
+                // The children are hit along the whole Ray to know if the Ray starts inside of them
+                let everywhere = f64::NEG_INFINITY..=f64::INFINITY;
+                csg::combine(
+                    *operation,
+                    ray,
+                    left.hits(ray, everywhere.clone()),
+                    right.hits(ray, everywhere),
+                )
+                .into_iter()
+                .filter(|hit| ray_length_min_max.contains(&hit.distance_from_ray))
+                .collect()
+            }
+        }
+    }
+
+    /// Checks if the geometry is, or combines, custom geometry, which cannot be written to a scene description.
+    pub(crate) fn has_custom(&self) -> bool {
+        match self {
+            Self::Custom { .. } => true,
+            Self::Csg { left, right, .. } => left.has_custom() || right.has_custom(),
+            _ => false,
+        }
+    }
+
+    /// Creates the geometry inside of this geometry or `other`.
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        Self::csg(CsgOperation::Union, self, other)
+    }
+
+    /// Creates the geometry inside of both this geometry and `other`.
+    #[must_use]
+    pub fn intersection(self, other: Self) -> Self {
+        Self::csg(CsgOperation::Intersection, self, other)
+    }
+
+    /// Creates the geometry inside of this geometry but not inside of `other`.
+    #[must_use]
+    pub fn difference(self, other: Self) -> Self {
+        Self::csg(CsgOperation::Difference, self, other)
+    }
+
+    fn csg(operation: CsgOperation, left: Self, right: Self) -> Self {
+        Self::Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Box that contains the geometry, `None` if the geometry is unbounded.
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        match self {
+            Self::Sphere { center, radius } => Some(Aabb::around(
+                *center,
+                Direction::new(radius.abs(), radius.abs(), radius.abs()),
+            )),
+            Self::Plane { .. } => None,
+            Self::Disk {
+                center,
+                normal,
+                radius,
+            } => Some(Aabb::around(*center, disk_extent(*normal, *radius))),
+            Self::Quad { corner, u, v } => {
+                Aabb::from_points([*corner, *corner + *u, *corner + *v, *corner + *u + *v])
+            }
+            Self::BilinearPatch { corners } => Aabb::from_points(*corners),
+            Self::BezierPatch { patch } => patch.bounding_box(),
+            Self::Box { minimum, maximum } => Some(Aabb::new(*minimum, *maximum)),
+            Self::Cylinder {
+                base, axis, radius, ..
+            } => Some(cone_bounding_box(*base, *axis, *radius, *radius)),
+            Self::Cone {
+                base,
+                axis,
+                base_radius,
+                top_radius,
+                ..
+            } => Some(cone_bounding_box(*base, *axis, *base_radius, *top_radius)),
+            Self::Capsule { start, end, radius } => {
+                let extent = Direction::new(radius.abs(), radius.abs(), radius.abs());
+                Some(Aabb::around(*start, extent).union(&Aabb::around(*end, extent)))
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => Some(Aabb::around(
+                *center,
+                disk_extent(*axis, major_radius.abs()) + minor_radius.abs(),
+            )),
+            Self::SignedDistance { function, .. } => function.bounding_box(),
+            Self::Heightfield {
+                heights,
+                minimum,
+                size,
+            } => Some(heights.bounding_box(minimum, size)),
+            Self::Mesh { mesh } => mesh.bounding_box(),
+            Self::DisplacedMesh { mesh } => mesh.bounding_box(),
+            Self::Subdivision { surface } => surface.bounding_box(),
+            Self::Curves { curves } => curves.bounding_box(),
+            Self::Custom { geometry } => geometry.bounding_box(),
+            Self::Csg {
+                operation,
+                left,
+                right,
+            } => match (operation, left.bounding_box(), right.bounding_box()) {
+                (CsgOperation::Union, left, right) => Some(left?.union(&right?)),
+                (CsgOperation::Intersection, Some(left), Some(right)) => {
+                    Some(left.intersection(&right))
+                }
+                (CsgOperation::Intersection, left, right) => left.or(right),
+                (CsgOperation::Difference, left, _) => left,
+            },
         }
     }
 
@@ -27,7 +431,7 @@ impl ObjectGeometry {
         radius: f64,
         ray: &Ray,
         ray_length_min_max: RangeInclusive<f64>,
-    ) -> Option<(f64, Point, Direction)> {
+    ) -> Option<GeometryHit> {
         let ray_to_center_direction = center.point_towards(*ray.origin());
 
         let a = ray.direction().length_squared();
@@ -48,13 +452,445 @@ impl ObjectGeometry {
                     }
                 })
                 .filter(|root| ray_length_min_max.contains(root))
-                .map(|root| {
-                    let point_of_intersection = ray.at(root);
-                    // Divide by radius instead of calling Direction::unit_vector here
-                    // because the signal of `radius` is used
-                    let normal = center.point_towards(point_of_intersection) / radius;
-                    (root, point_of_intersection, normal)
-                })
+                .map(|root| Self::sphere_hit_at(center, radius, ray, root))
+        }
+    }
+
+    /// Every hit of a Ray with a sphere within `ray_length_min_max`, in order along the Ray.
+    fn sphere_hits(
+        center: &Point,
+        radius: f64,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Vec<GeometryHit> {
+        let ray_to_center_direction = center.point_towards(*ray.origin());
+        solve_quadratic(
+            ray.direction().length_squared(),
+            2. * ray_to_center_direction.dot(*ray.direction()),
+            ray_to_center_direction.length_squared() - radius.powi(2),
+        )
+        .into_iter()
+        .filter(|root| ray_length_min_max.contains(root))
+        .map(|root| Self::sphere_hit_at(center, radius, ray, root))
+        .collect()
+    }
+
+    fn sphere_hit_at(center: &Point, radius: f64, ray: &Ray, root: f64) -> GeometryHit {
+        let point_of_intersection = ray.at(root);
+        // Divide by radius instead of calling Direction::unit_vector here
+        // because the signal of `radius` is used
+        let normal = center.point_towards(point_of_intersection) / radius;
+        let outwards = normal * radius.signum();
+        GeometryHit {
+            distance_from_ray: root,
+            point_of_intersection,
+            normal,
+            uv: (
+                (f64::atan2(-outwards[2], outwards[0]) + PI) / (2. * PI),
+                (-outwards[1]).clamp(-1., 1.).acos() / PI,
+            ),
+            vertex_colour: None,
+            tangent: None,
+        }
+    }
+
+    /// Distance, point of intersection, and unit normal of a Ray hitting the plane through `point`.
+    fn hit_plane(
+        point: &Point,
+        normal: &Direction,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<(f64, Point, Direction)> {
+        if normal.is_zero() {
+            return None;
+        }
+        let normal = normal.unit_vector();
+        let denominator = normal.dot(*ray.direction());
+        if denominator.abs() < 1e-12 {
+            // The Ray is parallel to the plane
+            return None;
+        }
+
+        let root = normal.dot(ray.origin().point_towards(*point)) / denominator;
+        ray_length_min_max
+            .contains(&root)
+            .then(|| (root, ray.at(root), normal))
+    }
+
+    fn hit_disk(
+        center: &Point,
+        normal: &Direction,
+        radius: f64,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let (distance_from_ray, point_of_intersection, normal) =
+            Self::hit_plane(center, normal, ray, ray_length_min_max)?;
+        let from_center = center.point_towards(point_of_intersection);
+        let distance_from_center = from_center.length();
+        if distance_from_center > radius.abs() {
+            return None;
+        }
+
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        let angle = f64::atan2(from_center.dot(bitangent), from_center.dot(tangent));
+        Some(GeometryHit {
+            distance_from_ray,
+            point_of_intersection,
+            normal,
+            uv: (
+                (angle + PI) / (2. * PI),
+                distance_from_center / radius.abs().max(f64::EPSILON),
+            ),
+            vertex_colour: None,
+            tangent: None,
+        })
+    }
+
+    fn hit_quad(
+        corner: &Point,
+        u: &Direction,
+        v: &Direction,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let normal = u.cross(*v);
+        let (distance_from_ray, point_of_intersection, unit_normal) =
+            Self::hit_plane(corner, &normal, ray, ray_length_min_max)?;
+
+        // Coordinates of the hit along `u` and `v`, which do not need to be perpendicular
+        let w = normal / normal.length_squared();
+        let from_corner = corner.point_towards(point_of_intersection);
+        let alpha = w.dot(from_corner.cross(*v));
+        let beta = w.dot(u.cross(from_corner));
+        if !(0.0..=1.).contains(&alpha) || !(0.0..=1.).contains(&beta) {
+            return None;
+        }
+
+        Some(GeometryHit {
+            distance_from_ray,
+            point_of_intersection,
+            normal: unit_normal,
+            uv: (alpha, beta),
+            vertex_colour: None,
+            tangent: None,
+        })
+    }
+
+    /// Every hit of a Ray with a box within `ray_length_min_max`, in order along the Ray.
+    fn box_hits(
+        minimum: &Point,
+        maximum: &Point,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Vec<GeometryHit> {
+        Self::box_crossings(minimum, maximum, ray)
+            .into_iter()
+            .flatten()
+            .filter(|(root, _)| ray_length_min_max.contains(root))
+            .map(|(root, axis)| Self::box_hit_at(minimum, maximum, ray, root, axis))
+            .collect()
+    }
+
+    /// Distances along a Ray to where it enters and leaves a box, with the axis of the side crossed each time,
+    /// `None` if the Ray misses the box.
+    fn box_crossings(minimum: &Point, maximum: &Point, ray: &Ray) -> Option<[(f64, usize); 2]> {
+        // Intersect the slabs between each pair of opposite sides, keeping the axis of the last side entered
+        // and the first side left
+        let mut entry = (f64::NEG_INFINITY, 0);
+        let mut exit = (f64::INFINITY, 0);
+        for axis in 0..3 {
+            // A Ray parallel to a slab is either always or never between its sides, working it out with
+            // the inverse direction would give 0 * inf = NaN for a Ray starting on one of the sides
+            if ray.direction()[axis] == 0. {
+                if !(minimum[axis]..=maximum[axis]).contains(&ray.origin()[axis]) {
+                    return None;
+                }
+                continue;
+            }
+            let inverse_direction = 1. / ray.direction()[axis];
+            let near = (minimum[axis] - ray.origin()[axis]) * inverse_direction;
+            let far = (maximum[axis] - ray.origin()[axis]) * inverse_direction;
+            let (near, far) = if near <= far {
+                (near, far)
+            } else {
+                (far, near)
+            };
+            if near > entry.0 {
+                entry = (near, axis);
+            }
+            if far < exit.0 {
+                exit = (far, axis);
+            }
+        }
+        (entry.0 <= exit.0).then_some([entry, exit])
+    }
+
+    fn box_hit_at(
+        minimum: &Point,
+        maximum: &Point,
+        ray: &Ray,
+        root: f64,
+        axis: usize,
+    ) -> GeometryHit {
+        let point_of_intersection = ray.at(root);
+
+        let center = (minimum[axis] + maximum[axis]) / 2.;
+        let mut normal = Direction::default();
+        normal[axis] = if point_of_intersection[axis] < center {
+            -1.
+        } else {
+            1.
+        };
+
+        let (first, second) = ((axis + 1) % 3, (axis + 2) % 3);
+        let coordinate = |component: usize| {
+            let size = maximum[component] - minimum[component];
+            if size.abs() < f64::EPSILON {
+                0.
+            } else {
+                ((point_of_intersection[component] - minimum[component]) / size).clamp(0., 1.)
+            }
+        };
+        GeometryHit {
+            distance_from_ray: root,
+            point_of_intersection,
+            normal,
+            uv: (coordinate(first), coordinate(second)),
+            vertex_colour: None,
+            tangent: None,
+        }
+    }
+}
+
+/// Distance from the center to the sides of the Aabb of a disk facing `normal`, on each axis.
+fn disk_extent(normal: Direction, radius: f64) -> Direction {
+    let normal = if normal.is_zero() {
+        normal
+    } else {
+        normal.unit_vector()
+    };
+    let extent = |axis: usize| radius.abs() * (1. - normal[axis].powi(2)).max(0.).sqrt();
+    Direction::new(extent(0), extent(1), extent(2))
+}
+
+fn cone_bounding_box(base: Point, axis: Direction, base_radius: f64, top_radius: f64) -> Aabb {
+    Aabb::around(base, disk_extent(axis, base_radius))
+        .union(&Aabb::around(base + axis, disk_extent(axis, top_radius)))
+}
+
+/// Two unit Directions perpendicular to each other and to the unit `normal`.
+pub(super) fn orthonormal_basis(normal: Direction) -> (Direction, Direction) {
+    let helper = if normal[0].abs() > 0.9 {
+        Direction::new(0., 1., 0.)
+    } else {
+        Direction::new(1., 0., 0.)
+    };
+    let tangent = helper.cross(normal).unit_vector();
+    (tangent, normal.cross(tangent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_geometry_hit_test() {
+        let ray = Ray::new(Point::new(0.5, 0.25, 2.), Direction::new(0., 0., -1.));
+
+        let quad = ObjectGeometry::Quad {
+            corner: Point::new(0., 0., 0.),
+            u: Direction::new(2., 0., 0.),
+            v: Direction::new(0., 1., 0.),
+        };
+        let hit = quad
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Expect quad hit");
+        assert_eq!(hit.distance_from_ray, 2.);
+        assert_eq!(hit.normal, Direction::new(0., 0., 1.));
+        assert_eq!(hit.uv, (0.25, 0.25));
+
+        let cube = ObjectGeometry::Box {
+            minimum: Point::new(0., 0., -1.),
+            maximum: Point::new(1., 1., 0.),
+        };
+        let hit = cube
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Expect box hit");
+        assert_eq!(hit.distance_from_ray, 2.);
+        assert_eq!(hit.normal, Direction::new(0., 0., 1.));
+        // From inside the box the far side is hit, still facing outwards
+        let hit = cube.hit(&ray, 2.5..=f64::INFINITY).expect("Expect box hit");
+        assert_eq!(hit.distance_from_ray, 3.);
+        assert_eq!(hit.normal, Direction::new(0., 0., -1.));
+        // A Ray along one of the sides still hits the box
+        let along_side = Ray::new(Point::new(1., 0.25, 2.), Direction::new(0., 0., -1.));
+        let hit = cube
+            .hit(&along_side, 0.001..=f64::INFINITY)
+            .expect("Expect box hit");
+        assert_eq!(hit.distance_from_ray, 2.);
+        let beside = Ray::new(Point::new(1.5, 0.25, 2.), Direction::new(0., 0., -1.));
+        assert!(cube.hit(&beside, 0.001..=f64::INFINITY).is_none());
+
+        let disk = ObjectGeometry::Disk {
+            center: Point::new(0., 0., 0.),
+            normal: Direction::new(0., 0., 1.),
+            radius: 0.5,
+        };
+        assert!(disk.hit(&ray, 0.001..=f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn nearest_hit_test() {
+        let (base, axis) = (Point::new(0., -1., 0.), Direction::new(0., 2., 0.));
+        let geometries = [
+            ObjectGeometry::Box {
+                minimum: Point::new(-1., -1., -1.),
+                maximum: Point::new(1., 1., 1.),
+            },
+            ObjectGeometry::Cylinder {
+                base,
+                axis,
+                radius: 1.,
+                capped: true,
+            },
+            ObjectGeometry::Cone {
+                base,
+                axis,
+                base_radius: 1.,
+                top_radius: 0.5,
+                capped: true,
+            },
+            ObjectGeometry::Capsule {
+                start: base,
+                end: Point::new(0., 1., 0.),
+                radius: 0.5,
+            },
+            ObjectGeometry::Torus {
+                center: Point::new(0., 0., 0.),
+                axis,
+                major_radius: 1.,
+                minor_radius: 0.25,
+            },
+        ];
+        let rays = [
+            Ray::new(Point::new(0.1, 0.2, 5.), Direction::new(0., 0., -1.)),
+            Ray::new(Point::new(-4., 3., 0.9), Direction::new(1., -0.8, -0.1)),
+            Ray::new(Point::new(1., 0., 0.1), Direction::new(-1., 0., 0.)),
+        ];
+        // Outside of CSG only the nearest hit is worked out, which must be the first of every hit
+        for geometry in &geometries {
+            for ray in &rays {
+                let hit = geometry.hit(ray, 0.001..=f64::INFINITY);
+                let hits = geometry.hits(ray, 0.001..=f64::INFINITY);
+                assert_eq!(
+                    hit.map(|hit| hit.distance_from_ray),
+                    hits.first().map(|hit| hit.distance_from_ray)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn quadric_hit_test() {
+        let (base, axis) = (Point::new(0., 0., 0.), Direction::new(0., 2., 0.));
+        let cylinder = ObjectGeometry::Cylinder {
+            base,
+            axis,
+            radius: 1.,
+            capped: true,
+        };
+        let cone = ObjectGeometry::Cone {
+            base,
+            axis,
+            base_radius: 1.,
+            top_radius: 0.,
+            capped: true,
+        };
+        let capsule = ObjectGeometry::Capsule {
+            start: base,
+            end: Point::new(0., 2., 0.),
+            radius: 1.,
+        };
+        let torus = ObjectGeometry::Torus {
+            center: base,
+            axis,
+            major_radius: 2.,
+            minor_radius: 0.5,
+        };
+        let side = Ray::new(Point::new(0., 1., 5.), Direction::new(0., 0., -1.));
+        let down = |x: f64, z: f64| Ray::new(Point::new(x, 5., z), Direction::new(0., -1., 0.));
+        let up = |x: f64, z: f64| Ray::new(Point::new(x, -5., z), Direction::new(0., 1., 0.));
+        let slant = 1. / 5_f64.sqrt();
+
+        // U goes around the axis starting from -X, so +X is 0.5 and +Z is 0.75
+        let cases = [
+            (&cylinder, side, 4., Direction::new(0., 0., 1.), (0.75, 0.5)),
+            (
+                &cylinder,
+                down(0.5, 0.),
+                3.,
+                Direction::new(0., 1., 0.),
+                (0.5, 0.5),
+            ),
+            (
+                &cylinder,
+                up(0., 0.5),
+                5.,
+                Direction::new(0., -1., 0.),
+                (0.75, 0.5),
+            ),
+            (
+                &cone,
+                side,
+                4.5,
+                Direction::new(0., slant, 2. * slant),
+                (0.75, 0.5),
+            ),
+            (
+                &cone,
+                up(0., 0.5),
+                5.,
+                Direction::new(0., -1., 0.),
+                (0.75, 0.5),
+            ),
+            (&capsule, side, 4., Direction::new(0., 0., 1.), (0.75, 0.5)),
+            (
+                &capsule,
+                down(0., 0.),
+                2.,
+                Direction::new(0., 1., 0.),
+                (0.5, 1.),
+            ),
+            (
+                &capsule,
+                up(0., 0.),
+                4.,
+                Direction::new(0., -1., 0.),
+                (0.5, 0.),
+            ),
+            (
+                &torus,
+                Ray::new(Point::new(0., 0., 5.), Direction::new(0., 0., -1.)),
+                2.5,
+                Direction::new(0., 0., 1.),
+                (0.75, 0.5),
+            ),
+            (
+                &torus,
+                down(2., 0.),
+                4.5,
+                Direction::new(0., 1., 0.),
+                (0.5, 0.75),
+            ),
+        ];
+        for (geometry, ray, distance, normal, (u, v)) in cases {
+            let hit = geometry
+                .hit(&ray, 0.001..=f64::INFINITY)
+                .expect("Expect ray to hit the shape");
+            assert!((hit.distance_from_ray - distance).abs() < 1e-9);
+            assert!((hit.normal - normal).length() < 1e-9);
+            assert!((hit.uv.0 - u).abs() < 1e-9 && (hit.uv.1 - v).abs() < 1e-9);
         }
     }
 }
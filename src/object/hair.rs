@@ -0,0 +1,421 @@
+use std::f64::consts::PI;
+
+use crate::{
+    random::random,
+    ray::Ray,
+    vector::{Colour, Direction},
+};
+
+use super::{geometry::orthonormal_basis, HitRecord, RayScatter};
+
+/// Tilt of the scales on the surface of a hair, which shifts the lobes along the fibre.
+const SCALE_ANGLE: f64 = 2. * PI / 180.;
+
+/// Light scattered by a hair fibre, following "A Practical and Controllable Hair and Fur Model for
+/// Production Path Tracing" by Chiang et al.</br>
+/// Light is reflected (R), transmitted through the fibre (TT), or reflected once inside of it (TRT), each lobe
+/// spreads along the fibre by the longitudinal roughness and around it by the azimuthal roughness. Light
+/// that bounces more times inside of the fibre is gathered in a fourth lobe spread evenly around it.
+pub(super) struct HairScattering {
+    absorption: Colour,
+    refraction_index: f64,
+    /// Variance of the longitudinal spread of each lobe.
+    variances: [f64; 4],
+    /// Scale of the logistic distribution of the azimuthal spread.
+    logistic_scale: f64,
+    /// Sine and cosine of 2, 4, and 8 times the scale angle.
+    scale_tilts: [(f64, f64); 3],
+}
+
+/// Angles of a direction in the frame of the fibre, with X along the fibre and Z on the normal.
+struct FibreAngles {
+    sin_theta: f64,
+    cos_theta: f64,
+    phi: f64,
+}
+
+impl FibreAngles {
+    fn new(direction: Direction) -> Self {
+        let sin_theta = direction[0].clamp(-1., 1.);
+        Self {
+            sin_theta,
+            cos_theta: (1. - sin_theta * sin_theta).max(0.).sqrt(),
+            phi: direction[2].atan2(direction[1]),
+        }
+    }
+}
+
+/// Angles of the light inside of the fibre for light leaving towards `outgoing`.
+struct Refraction {
+    /// Angle between the normal of the fibre and the light entering, from the offset across the fibre.
+    gamma_outgoing: f64,
+    /// Angle of the light inside of the fibre.
+    gamma_transmitted: f64,
+    /// Light that crosses the fibre once.
+    transmittance: Colour,
+}
+
+impl HairScattering {
+    /// # Parameters
+    /// `absorption`: Light absorbed inside of the fibre per diameter crossed</br>
+    /// `longitudinal_roughness`: Spread of the lobes along the fibre, clamped between [0.01, 1.0]</br>
+    /// `azimuthal_roughness`: Spread of the lobes around the fibre, clamped between [0.01, 1.0]</br>
+    /// `refraction_index`: Refraction index of the fibre, 1.55 for human hair</br>
+    pub(super) fn new(
+        absorption: Colour,
+        longitudinal_roughness: f64,
+        azimuthal_roughness: f64,
+        refraction_index: f64,
+    ) -> Self {
+        let longitudinal = longitudinal_roughness.clamp(0.01, 1.);
+        let azimuthal = azimuthal_roughness.clamp(0.01, 1.);
+        let variance =
+            (0.726 * longitudinal + 0.812 * longitudinal.powi(2) + 3.7 * longitudinal.powi(20))
+                .powi(2);
+        let logistic_scale = (PI / 8.).sqrt()
+            * (0.265 * azimuthal + 1.194 * azimuthal.powi(2) + 5.372 * azimuthal.powi(22));
+
+        let mut scale_tilts = [(SCALE_ANGLE.sin(), SCALE_ANGLE.cos()); 3];
+        for index in 1..3 {
+            let (sin, cos) = scale_tilts[index - 1];
+            scale_tilts[index] = (2. * cos * sin, cos * cos - sin * sin);
+        }
+        Self {
+            absorption,
+            refraction_index,
+            variances: [variance, variance / 4., 4. * variance, 4. * variance],
+            logistic_scale,
+            scale_tilts,
+        }
+    }
+
+    /// Absorption that gives hair close to `colour`, for the `azimuthal_roughness` of the hair.
+    pub(super) fn absorption_from_colour(colour: Colour, azimuthal_roughness: f64) -> Colour {
+        let roughness = azimuthal_roughness.clamp(0.01, 1.);
+        let denominator = 5.969 - 0.215 * roughness + 2.532 * roughness.powi(2)
+            - 10.73 * roughness.powi(3)
+            + 5.574 * roughness.powi(4)
+            + 0.245 * roughness.powi(5);
+        map(colour, |channel| {
+            (channel.clamp(1e-4, 1.).ln() / denominator).powi(2)
+        })
+    }
+
+    /// Samples a lobe, and the direction of the light within the lobe.</br>
+    /// The fibre runs along the tangent of `hit_record`, or along any direction on the surface if it has no
+    /// tangent, and V across the fibre from 0 to 1 is where the Ray hit it.
+    pub(super) fn scatter(&self, hit_record: &HitRecord) -> RayScatter {
+        let normal = hit_record.normal;
+        let along = hit_record
+            .tangent
+            .map(|tangent| tangent - normal * tangent.dot(normal))
+            .filter(|tangent| !tangent.is_zero())
+            .map_or_else(|| orthonormal_basis(normal).0, Direction::unit_vector);
+        let across = normal.cross(along);
+        let outgoing = -hit_record.intersecting_ray.direction().unit_vector();
+        let outgoing = Direction::new(
+            outgoing.dot(along),
+            outgoing.dot(across),
+            outgoing.dot(normal),
+        );
+        let offset = (2. * hit_record.uv.1 - 1.).clamp(-1., 1.);
+
+        let incoming = self.sample(&outgoing, offset);
+        let (value, pdf) = self.evaluate(&outgoing, &incoming, offset);
+        RayScatter {
+            attenuation: if pdf > 0. && pdf.is_finite() {
+                value / pdf
+            } else {
+                Colour::default()
+            },
+            scattered: Ray::new(
+                hit_record.point_of_intersection,
+                along * incoming[0] + across * incoming[1] + normal * incoming[2],
+            ),
+        }
+    }
+
+    fn sample(&self, outgoing: &Direction, offset: f64) -> Direction {
+        let angles = FibreAngles::new(*outgoing);
+        let refraction = self.refraction(&angles, offset);
+        let lobe_pdfs = self.lobe_pdfs(&angles, offset, &refraction);
+        let mut choice = random::<f64>();
+        let lobe = (0..3)
+            .find(|lobe| {
+                let chosen = choice < lobe_pdfs[*lobe];
+                choice -= lobe_pdfs[*lobe];
+                chosen
+            })
+            .unwrap_or(3);
+
+        // Longitudinal angle around the tilted reflection
+        let (sin_theta_tilted, cos_theta_tilted) = self.tilt(lobe, &angles);
+        let variance = self.variances[lobe];
+        let u = random::<f64>().max(1e-5);
+        let cos_theta = 1. + variance * (u + (1. - u) * (-2. / variance).exp()).ln();
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let cos_phi = (2. * PI * random::<f64>()).cos();
+        let sin_theta_incoming =
+            -cos_theta * sin_theta_tilted + sin_theta * cos_phi * cos_theta_tilted;
+        let cos_theta_incoming = (1. - sin_theta_incoming * sin_theta_incoming)
+            .max(0.)
+            .sqrt();
+
+        // Azimuthal angle around the exit of the lobe
+        let phi_change = if lobe < 3 {
+            exit_azimuth(lobe, &refraction) + sample_trimmed_logistic(random(), self.logistic_scale)
+        } else {
+            2. * PI * random::<f64>()
+        };
+        let phi = angles.phi + phi_change;
+        Direction::new(
+            sin_theta_incoming,
+            cos_theta_incoming * phi.cos(),
+            cos_theta_incoming * phi.sin(),
+        )
+    }
+
+    /// Light scattered from `incoming` to `outgoing` times the cosine of `incoming`, and the probability
+    /// of `sample` choosing `incoming`.
+    fn evaluate(&self, outgoing: &Direction, incoming: &Direction, offset: f64) -> (Colour, f64) {
+        let angles = FibreAngles::new(*outgoing);
+        let incoming = FibreAngles::new(*incoming);
+        let refraction = self.refraction(&angles, offset);
+        let attenuations = self.attenuations(&angles, offset, &refraction);
+        let lobe_pdfs = self.lobe_pdfs(&angles, offset, &refraction);
+        let phi = incoming.phi - angles.phi;
+
+        let mut value = Colour::default();
+        let mut pdf = 0.;
+        for lobe in 0..4 {
+            let (sin_theta_tilted, cos_theta_tilted) = self.tilt(lobe, &angles);
+            let longitudinal = longitudinal_scattering(
+                (incoming.sin_theta, incoming.cos_theta),
+                (sin_theta_tilted, cos_theta_tilted),
+                self.variances[lobe],
+            );
+            let azimuthal = if lobe < 3 {
+                azimuthal_scattering(phi - exit_azimuth(lobe, &refraction), self.logistic_scale)
+            } else {
+                1. / (2. * PI)
+            };
+            value += attenuations[lobe] * (longitudinal * azimuthal);
+            pdf += lobe_pdfs[lobe] * longitudinal * azimuthal;
+        }
+        (value, pdf)
+    }
+
+    fn refraction(&self, angles: &FibreAngles, offset: f64) -> Refraction {
+        let sin_theta_transmitted = angles.sin_theta / self.refraction_index;
+        let cos_theta_transmitted = (1. - sin_theta_transmitted.powi(2)).max(0.).sqrt();
+        // Refraction index of the fibre seen across its cross-section
+        let projected_index = (self.refraction_index.powi(2) - angles.sin_theta.powi(2))
+            .max(0.)
+            .sqrt()
+            / angles.cos_theta.max(1e-8);
+        let sin_gamma_transmitted = (offset / projected_index).clamp(-1., 1.);
+        let cos_gamma_transmitted = (1. - sin_gamma_transmitted.powi(2)).max(0.).sqrt();
+        let path_length = 2. * cos_gamma_transmitted / cos_theta_transmitted.max(1e-8);
+        Refraction {
+            gamma_outgoing: offset.asin(),
+            gamma_transmitted: sin_gamma_transmitted.asin(),
+            transmittance: map(self.absorption, |absorption| {
+                (-absorption * path_length).exp()
+            }),
+        }
+    }
+
+    /// Light left in each lobe after the reflections and absorption along its path through the fibre.
+    fn attenuations(
+        &self,
+        angles: &FibreAngles,
+        offset: f64,
+        refraction: &Refraction,
+    ) -> [Colour; 4] {
+        let cos_gamma_outgoing = (1. - offset * offset).max(0.).sqrt();
+        let reflectance = fresnel(angles.cos_theta * cos_gamma_outgoing, self.refraction_index);
+        let transmittance = refraction.transmittance;
+        let reflected = Colour::new(reflectance, reflectance, reflectance);
+        let transmitted = transmittance * (1. - reflectance).powi(2);
+        let reflected_inside = transmitted * transmittance * reflectance;
+        let remaining = map(transmittance, |transmittance| {
+            let bounce = transmittance * reflectance;
+            if bounce < 1. {
+                bounce / (1. - bounce)
+            } else {
+                0.
+            }
+        }) * reflected_inside;
+        [reflected, transmitted, reflected_inside, remaining]
+    }
+
+    /// Chance of sampling each lobe, following the average light it carries.
+    fn lobe_pdfs(&self, angles: &FibreAngles, offset: f64, refraction: &Refraction) -> [f64; 4] {
+        let weights = self
+            .attenuations(angles, offset, refraction)
+            .map(|attenuation| (attenuation[0] + attenuation[1] + attenuation[2]) / 3.);
+        let total = weights.iter().sum::<f64>();
+        if total > 0. {
+            weights.map(|weight| weight / total)
+        } else {
+            [1., 0., 0., 0.]
+        }
+    }
+
+    /// Sine and cosine of the longitudinal angle of `angles`, tilted by the scales for `lobe`.
+    fn tilt(&self, lobe: usize, angles: &FibreAngles) -> (f64, f64) {
+        let (sin_theta, cos_theta) = (angles.sin_theta, angles.cos_theta);
+        let (sin, cos) = match lobe {
+            0 => {
+                let (sin_tilt, cos_tilt) = self.scale_tilts[1];
+                (
+                    sin_theta * cos_tilt - cos_theta * sin_tilt,
+                    cos_theta * cos_tilt + sin_theta * sin_tilt,
+                )
+            }
+            1 | 2 => {
+                let (sin_tilt, cos_tilt) = self.scale_tilts[if lobe == 1 { 0 } else { 2 }];
+                (
+                    sin_theta * cos_tilt + cos_theta * sin_tilt,
+                    cos_theta * cos_tilt - sin_theta * sin_tilt,
+                )
+            }
+            _ => (sin_theta, cos_theta),
+        };
+        (sin, cos.abs())
+    }
+}
+
+fn map(colour: Colour, function: impl Fn(f64) -> f64) -> Colour {
+    Colour::new(
+        function(colour[0]),
+        function(colour[1]),
+        function(colour[2]),
+    )
+}
+
+/// Fresnel reflectance of a dielectric with `refraction_index` for light arriving at `cos_theta`.
+fn fresnel(cos_theta: f64, refraction_index: f64) -> f64 {
+    let cos_incident = cos_theta.clamp(0., 1.);
+    let sin_transmitted = (1. - cos_incident.powi(2)).max(0.).sqrt() / refraction_index;
+    if sin_transmitted >= 1. {
+        return 1.;
+    }
+    let cos_transmitted = (1. - sin_transmitted.powi(2)).max(0.).sqrt();
+    let parallel = (refraction_index * cos_incident - cos_transmitted)
+        / (refraction_index * cos_incident + cos_transmitted);
+    let perpendicular = (cos_incident - refraction_index * cos_transmitted)
+        / (cos_incident + refraction_index * cos_transmitted);
+    (parallel.powi(2) + perpendicular.powi(2)) / 2.
+}
+
+/// Spread of light along the fibre, from `incoming` to the tilted `outgoing` sine and cosine.
+fn longitudinal_scattering(
+    (sin_incoming, cos_incoming): (f64, f64),
+    (sin_outgoing, cos_outgoing): (f64, f64),
+    variance: f64,
+) -> f64 {
+    let a = cos_incoming * cos_outgoing / variance;
+    let b = sin_incoming * sin_outgoing / variance;
+    if variance <= 0.1 {
+        // The Bessel function and the hyperbolic sine overflow for small variances
+        (log_bessel_i0(a) - b - 1. / variance
+            + std::f64::consts::LN_2
+            + (1. / (2. * variance)).ln())
+        .exp()
+    } else {
+        (-b).exp() * bessel_i0(a) / ((1. / variance).sinh() * 2. * variance)
+    }
+}
+
+/// Modified Bessel function of the first kind of order 0.
+fn bessel_i0(x: f64) -> f64 {
+    let mut value = 0.;
+    let mut power = 1.;
+    let mut factorial: f64 = 1.;
+    let mut four_power = 1.;
+    for index in 0..10 {
+        if index > 1 {
+            factorial *= f64::from(index);
+        }
+        value += power / (four_power * factorial * factorial);
+        power *= x * x;
+        four_power *= 4.;
+    }
+    value
+}
+
+fn log_bessel_i0(x: f64) -> f64 {
+    if x > 12. {
+        x + 0.5 * (-(2. * PI).ln() + (1. / x).ln() + 1. / (8. * x))
+    } else {
+        bessel_i0(x).ln()
+    }
+}
+
+/// Change of azimuth of light leaving the fibre through `lobe`.
+fn exit_azimuth(lobe: usize, refraction: &Refraction) -> f64 {
+    let lobe = lobe as f64;
+    2. * lobe * refraction.gamma_transmitted - 2. * refraction.gamma_outgoing + lobe * PI
+}
+
+/// Spread of light around the fibre, `phi` away from the exit of the lobe.
+fn azimuthal_scattering(phi: f64, scale: f64) -> f64 {
+    let phi = (phi + PI).rem_euclid(2. * PI) - PI;
+    let logistic = |x: f64| {
+        let exponential = (-x.abs() / scale).exp();
+        exponential / (scale * (1. + exponential).powi(2))
+    };
+    logistic(phi) / (logistic_cdf(PI, scale) - logistic_cdf(-PI, scale))
+}
+
+fn logistic_cdf(x: f64, scale: f64) -> f64 {
+    1. / (1. + (-x / scale).exp())
+}
+
+/// Angle between -π and π from a logistic distribution with `scale` limited to that range.
+fn sample_trimmed_logistic(u: f64, scale: f64) -> f64 {
+    let range = logistic_cdf(PI, scale) - logistic_cdf(-PI, scale);
+    let x = -scale * (1. / (u * range + logistic_cdf(-PI, scale)) - 1.).ln();
+    x.clamp(-PI, PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hair_scattering_test() {
+        // Without absorption a hair keeps all of the light, and both the scattering and the sampling
+        // integrate to 1 over the sphere
+        let hair = HairScattering::new(Colour::default(), 0.3, 0.3, 1.55);
+        let outgoing = Direction::new(0.3, 0., 1.).unit_vector();
+        let steps = 400;
+        let (mut scattered, mut probability) = (0., 0.);
+        for z_step in 0..steps {
+            let z = -1. + 2. * (f64::from(z_step) + 0.5) / f64::from(steps);
+            for phi_step in 0..steps {
+                let phi = 2. * PI * (f64::from(phi_step) + 0.5) / f64::from(steps);
+                let radius = (1. - z * z).sqrt();
+                let incoming = Direction::new(z, radius * phi.cos(), radius * phi.sin());
+                let (value, pdf) = hair.evaluate(&outgoing, &incoming, 0.3);
+                scattered += value[0];
+                probability += pdf;
+            }
+        }
+        let sphere = 4. * PI / f64::from(steps * steps);
+        assert!(
+            (scattered * sphere - 1.).abs() < 0.02,
+            "{}",
+            scattered * sphere
+        );
+        assert!(
+            (probability * sphere - 1.).abs() < 0.02,
+            "{}",
+            probability * sphere
+        );
+
+        let incoming = hair.sample(&outgoing, 0.3);
+        assert!((incoming.length() - 1.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,285 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use crate::{
+    ray::Ray,
+    vector::{Direction, Point},
+    Image,
+};
+
+use super::{mesh::hit_triangle, Aabb, GeometryHit};
+
+#[derive(Debug, Clone)]
+/// Grid of heights sampled at the corners of its cells, with `columns` along X and `rows` along Z.</br>
+/// The heights are shared between clones, so placing the same terrain many times is cheap.
+pub struct Heightfield {
+    columns: usize,
+    rows: usize,
+    heights: Arc<[f64]>,
+    /// Lowest and highest height of each cell, so a Ray can skip the cells it passes over or under.
+    cell_bounds: Arc<[(f64, f64)]>,
+    /// Lowest and highest height of the grid.
+    bounds: (f64, f64),
+}
+
+impl Heightfield {
+    /// Creates the Heightfield from `heights` given row by row, `None` if there are not `columns` times `rows`
+    /// heights or if the grid has less than 2 columns or rows.
+    pub fn new(columns: usize, rows: usize, heights: Vec<f64>) -> Option<Self> {
+        if columns < 2 || rows < 2 || columns.checked_mul(rows) != Some(heights.len()) {
+            return None;
+        }
+        let cell_bounds = (0..rows - 1)
+            .flat_map(|row| (0..columns - 1).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let corners = [
+                    heights[row * columns + column],
+                    heights[row * columns + column + 1],
+                    heights[(row + 1) * columns + column],
+                    heights[(row + 1) * columns + column + 1],
+                ];
+                corners
+                    .into_iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), height| {
+                        (low.min(height), high.max(height))
+                    })
+            })
+            .collect::<Arc<[_]>>();
+        let bounds = cell_bounds
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), cell| {
+                (low.min(cell.0), high.max(cell.1))
+            });
+        Some(Self {
+            columns,
+            rows,
+            heights: heights.into(),
+            cell_bounds,
+            bounds,
+        })
+    }
+
+    /// Creates the Heightfield with one height per pixel of `image`, the average of its channels.</br>
+    /// The top row of the image is at the lowest Z.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let (width, height) = image.get_dimensions();
+        Self::new(
+            width,
+            height,
+            image
+                .get_pixels()
+                .iter()
+                .map(|pixel| (pixel[0] + pixel[1] + pixel[2]) / 3.)
+                .collect(),
+        )
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Heights of the grid, row by row.
+    pub fn heights(&self) -> &[f64] {
+        &self.heights
+    }
+
+    pub fn height(&self, column: usize, row: usize) -> f64 {
+        self.heights[row * self.columns + column]
+    }
+
+    /// Box that contains the Heightfield stretched over `minimum` to `minimum + size`.
+    pub(super) fn bounding_box(&self, minimum: &Point, size: &Direction) -> Aabb {
+        let (low, high) = self.bounds;
+        Aabb::new(
+            *minimum + Direction::new(0., low * size[1], 0.),
+            *minimum + Direction::new(size[0], high * size[1], size[2]),
+        )
+    }
+
+    /// First hit of `ray` within `ray_length_min_max` with the Heightfield stretched over `minimum` to
+    /// `minimum + size`.</br>
+    /// The Ray walks the cells under it in order, and each cell is split into two triangles.
+    pub(super) fn hit(
+        &self,
+        minimum: &Point,
+        size: &Direction,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        // In grid space the cells are 1 wide and heights are not scaled, which keeps the distance along the Ray
+        let to_grid = Direction::new(
+            (self.columns - 1) as f64 / size[0],
+            1. / size[1],
+            (self.rows - 1) as f64 / size[2],
+        );
+        if (0..3).any(|axis| !to_grid[axis].is_finite()) {
+            return None;
+        }
+        let origin = minimum.point_towards(*ray.origin()) * to_grid;
+        let direction = *ray.direction() * to_grid;
+        let (low, high) = self.bounds;
+        let grid_box = Aabb::new(
+            Point::new(0., low, 0.),
+            Point::new((self.columns - 1) as f64, high, (self.rows - 1) as f64),
+        );
+        let grid_ray = Ray::new(Point::default() + origin, direction);
+        let range = grid_box.hit_range(&grid_ray, ray_length_min_max.clone())?;
+
+        // 2D DDA over the cells on X and Z
+        let start = origin + direction * *range.start();
+        let mut cell = [0, 2].map(|axis| {
+            let last_cell = if axis == 0 {
+                self.columns - 2
+            } else {
+                self.rows - 2
+            };
+            (start[axis].floor().max(0.) as usize).min(last_cell)
+        });
+        let step = [0, 2].map(|axis| direction[axis].signum());
+        let delta = [0, 2].map(|axis| direction[axis].abs().recip());
+        let mut next_crossing = [0, 2].map(|axis| {
+            let index = usize::from(axis == 2);
+            if direction[axis] == 0. {
+                f64::INFINITY
+            } else {
+                let boundary = cell[index] as f64 + f64::from(u8::from(direction[axis] > 0.));
+                (boundary - origin[axis]) / direction[axis]
+            }
+        });
+
+        let mut entry = *range.start();
+        loop {
+            let exit = next_crossing[0].min(next_crossing[1]).min(*range.end());
+            let (column, row) = (cell[0], cell[1]);
+            let (cell_low, cell_high) = self.cell_bounds[row * (self.columns - 1) + column];
+            let (entry_height, exit_height) = (
+                origin[1] + direction[1] * entry,
+                origin[1] + direction[1] * exit,
+            );
+            if entry_height.min(exit_height) <= cell_high
+                && entry_height.max(exit_height) >= cell_low
+            {
+                if let Some(hit) =
+                    self.hit_cell(column, row, origin, direction, &ray_length_min_max)
+                {
+                    let point = origin + direction * hit.distance_from_ray;
+                    return Some(GeometryHit {
+                        point_of_intersection: ray.at(hit.distance_from_ray),
+                        normal: (hit.normal * to_grid).unit_vector(),
+                        uv: (
+                            point[0] / (self.columns - 1) as f64,
+                            point[2] / (self.rows - 1) as f64,
+                        ),
+                        ..hit
+                    });
+                }
+            }
+            if exit >= *range.end() {
+                return None;
+            }
+
+            let axis = usize::from(next_crossing[1] < next_crossing[0]);
+            let last_cell = if axis == 0 {
+                self.columns - 2
+            } else {
+                self.rows - 2
+            };
+            cell[axis] = match (step[axis] > 0., cell[axis]) {
+                (true, index) if index < last_cell => index + 1,
+                (false, index) if index > 0 => index - 1,
+                _ => return None,
+            };
+            entry = next_crossing[axis];
+            next_crossing[axis] += delta[axis];
+        }
+    }
+
+    /// Nearest hit, in grid space, of the two triangles of the cell at `column` and `row`.</br>
+    /// The normal is interpolated from the normals at the corners and is not unit length.
+    fn hit_cell(
+        &self,
+        column: usize,
+        row: usize,
+        origin: Direction,
+        direction: Direction,
+        ray_length_min_max: &RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let corner = |column: usize, row: usize| {
+            (
+                Direction::new(column as f64, self.height(column, row), row as f64),
+                self.vertex_normal(column, row),
+            )
+        };
+        let near = corner(column, row);
+        let far = corner(column + 1, row + 1);
+        [
+            [near, corner(column + 1, row), far],
+            [near, far, corner(column, row + 1)],
+        ]
+        .into_iter()
+        .filter_map(|[(a, normal_a), (b, normal_b), (c, normal_c)]| {
+            let (root, weight_b, weight_c) =
+                hit_triangle([a, b, c], origin, direction, ray_length_min_max)?;
+            Some(GeometryHit {
+                distance_from_ray: root,
+                point_of_intersection: Point::default() + origin + direction * root,
+                normal: normal_a * (1. - weight_b - weight_c)
+                    + normal_b * weight_b
+                    + normal_c * weight_c,
+                uv: (weight_b, weight_c),
+                vertex_colour: None,
+                tangent: None,
+            })
+        })
+        .min_by(|lhs, rhs| lhs.distance_from_ray.total_cmp(&rhs.distance_from_ray))
+    }
+
+    /// Normal at a corner of the grid, in grid space, from the slope to the neighbouring corners.
+    fn vertex_normal(&self, column: usize, row: usize) -> Direction {
+        let slope = |before: (usize, usize), after: (usize, usize), span: usize| {
+            (self.height(after.0, after.1) - self.height(before.0, before.1)) / span as f64
+        };
+        let (left, right) = (column.saturating_sub(1), (column + 1).min(self.columns - 1));
+        let (back, front) = (row.saturating_sub(1), (row + 1).min(self.rows - 1));
+        Direction::new(
+            -slope((left, row), (right, row), right - left),
+            1.,
+            -slope((column, back), (column, front), front - back),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heightfield_hit_test() {
+        // Ramp rising along X from 0 to 1
+        let heights = Heightfield::new(3, 2, vec![0., 0.5, 1., 0., 0.5, 1.]).expect("Valid grid");
+        let (minimum, size) = (Point::new(0., 0., 0.), Direction::new(4., 2., 4.));
+        assert_eq!(
+            heights.bounding_box(&minimum, &size),
+            Aabb::new(minimum, Point::new(4., 2., 4.))
+        );
+
+        let ray = Ray::new(Point::new(3., 10., 1.), Direction::new(0., -1., 0.));
+        let hit = heights
+            .hit(&minimum, &size, &ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the ramp");
+        assert!((hit.distance_from_ray - 8.5).abs() < 1e-9);
+        assert!((hit.uv.0 - 0.75).abs() < 1e-9 && (hit.uv.1 - 0.25).abs() < 1e-9);
+        // Rising 2 over 4 units of X
+        let expected = Direction::new(-0.5, 1., 0.).unit_vector();
+        assert!((hit.normal.dot(expected) - 1.).abs() < 1e-9);
+
+        // Passing over the ramp
+        let ray = Ray::new(Point::new(-1., 2.5, 1.), Direction::new(1., 0., 0.));
+        assert!(heights
+            .hit(&minimum, &size, &ray, 0.001..=f64::INFINITY)
+            .is_none());
+    }
+}
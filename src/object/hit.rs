@@ -3,7 +3,7 @@ use crate::{
     vector::{Colour, Direction, Point},
 };
 
-use super::ObjectMaterial;
+use super::{GeometryHit, ObjectMaterial};
 
 pub struct RayScatter {
     pub attenuation: Colour,
@@ -15,24 +15,28 @@ pub struct HitRecord<'a> {
     pub distance_from_ray: f64,
     pub point_of_intersection: Point,
     pub normal: Direction,
-    pub material: ObjectMaterial,
+    /// Surface coordinates of the point of intersection.
+    pub uv: (f64, f64),
+    /// Colour of the vertices interpolated at the point of intersection, for meshes with vertex colours.
+    pub vertex_colour: Option<Colour>,
+    /// Direction along the surface where U grows, for curves.
+    pub tangent: Option<Direction>,
+    pub material: &'a ObjectMaterial,
     pub front_face: bool,
 }
 
 impl<'a> HitRecord<'a> {
-    pub fn new(
-        intersecting_ray: &'a Ray,
-        distance_from_ray: f64,
-        point_of_intersection: Point,
-        normal: Direction,
-        material: ObjectMaterial,
-    ) -> Self {
-        let front_face = intersecting_ray.direction().dot(normal) < 0.;
+    /// Creates the HitRecord of `hit`, with the normal turned to face against `intersecting_ray`.
+    pub fn new(intersecting_ray: &'a Ray, hit: GeometryHit, material: &'a ObjectMaterial) -> Self {
+        let front_face = intersecting_ray.direction().dot(hit.normal) < 0.;
         HitRecord {
             intersecting_ray,
-            distance_from_ray,
-            point_of_intersection,
-            normal: if front_face { normal } else { -normal },
+            distance_from_ray: hit.distance_from_ray,
+            point_of_intersection: hit.point_of_intersection,
+            normal: if front_face { hit.normal } else { -hit.normal },
+            uv: hit.uv,
+            vertex_colour: hit.vertex_colour,
+            tangent: hit.tangent,
             material,
             front_face,
         }
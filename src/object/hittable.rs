@@ -0,0 +1,241 @@
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use crate::{
+    random::random,
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+use super::{geometry::orthonormal_basis, Aabb, GeometryHit, ObjectGeometry};
+
+/// Surface that Rays can hit, so geometry defined outside of this crate can be placed in a scene with
+/// [`Object::new_custom`](super::Object::new_custom).</br>
+/// Every [`ObjectGeometry`] is also a Hittable.
+pub trait Hittable: std::fmt::Debug + Send + Sync {
+    /// First hit of `ray` with the surface within `ray_length_min_max`.</br>
+    /// The normal of the hit faces out of the surface, the [`HitRecord`](super::HitRecord) turns it against the
+    /// Ray.
+    fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Option<GeometryHit>;
+
+    /// Every hit of `ray` with the surface within `ray_length_min_max`, in order along the Ray.</br>
+    /// Only the first hit is given unless the surface says otherwise. Closed surfaces should give every hit, so
+    /// they alternate between entering and leaving when combined with CSG.
+    fn hits(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Vec<GeometryHit> {
+        self.hit(ray, ray_length_min_max).into_iter().collect()
+    }
+
+    /// Box that contains the surface, `None` if the surface is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Random point spread evenly over the surface, `None` if the surface cannot be sampled.</br>
+    /// Surfaces cannot be sampled unless they say otherwise.
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Point picked on a surface by [`Hittable::sample_surface`].
+pub struct SurfaceSample {
+    pub point: Point,
+    /// Normal facing out of the surface.
+    pub normal: Direction,
+    /// Probability density of picking the point, per unit of area.
+    pub pdf: f64,
+}
+
+impl Hittable for ObjectGeometry {
+    fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Option<GeometryHit> {
+        ObjectGeometry::hit(self, ray, ray_length_min_max)
+    }
+
+    fn hits(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Vec<GeometryHit> {
+        ObjectGeometry::hits(self, ray, ray_length_min_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        ObjectGeometry::bounding_box(self)
+    }
+
+    /// Spheres, disks, and quads can be sampled, along with custom geometry that can.
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        match self {
+            Self::Sphere { center, radius } => {
+                if *radius == 0. {
+                    return None;
+                }
+                let direction = Direction::new_random_in_unit_sphere();
+                if direction.is_zero() {
+                    return None;
+                }
+                let direction = direction.unit_vector();
+                Some(SurfaceSample {
+                    point: *center + direction * radius.abs(),
+                    // Negative radii turn the normal inwards, like their hits
+                    normal: direction * radius.signum(),
+                    pdf: 1. / (4. * PI * radius * radius),
+                })
+            }
+            Self::Disk {
+                center,
+                normal,
+                radius,
+            } => {
+                if normal.is_zero() || *radius == 0. {
+                    return None;
+                }
+                let normal = normal.unit_vector();
+                let (tangent, bitangent) = orthonormal_basis(normal);
+                let (distance, angle) = (
+                    radius.abs() * random::<f64>().sqrt(),
+                    2. * PI * random::<f64>(),
+                );
+                Some(SurfaceSample {
+                    point: *center
+                        + tangent * (distance * angle.cos())
+                        + bitangent * (distance * angle.sin()),
+                    normal,
+                    pdf: 1. / (PI * radius * radius),
+                })
+            }
+            Self::Quad { corner, u, v } => {
+                let normal = u.cross(*v);
+                if normal.is_zero() {
+                    return None;
+                }
+                Some(SurfaceSample {
+                    point: *corner + *u * random::<f64>() + *v * random::<f64>(),
+                    normal: normal.unit_vector(),
+                    pdf: 1. / normal.length(),
+                })
+            }
+            Self::Custom { geometry } => geometry.sample_surface(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        object::{Object, ObjectMaterial},
+        object_storage::ObjectStorage,
+        transform::Transform,
+        vector::Colour,
+    };
+
+    /// Floor that goes on forever at a height, defined as a downstream crate would.
+    #[derive(Debug)]
+    struct Floor {
+        height: f64,
+    }
+
+    impl Hittable for Floor {
+        fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Option<GeometryHit> {
+            let root = (self.height - ray.origin()[1]) / ray.direction()[1];
+            ray_length_min_max.contains(&root).then(|| {
+                let point_of_intersection = ray.at(root);
+                GeometryHit {
+                    distance_from_ray: root,
+                    point_of_intersection,
+                    normal: Direction::new(0., 1., 0.),
+                    uv: (point_of_intersection[0], point_of_intersection[2]),
+                    vertex_colour: None,
+                    tangent: None,
+                }
+            })
+        }
+
+        fn bounding_box(&self) -> Option<Aabb> {
+            None
+        }
+    }
+
+    /// Ball at the origin that gives every hit, so it can be combined with CSG.
+    #[derive(Debug)]
+    struct Ball {
+        radius: f64,
+    }
+
+    impl Ball {
+        fn sphere(&self) -> ObjectGeometry {
+            ObjectGeometry::Sphere {
+                center: Point::default(),
+                radius: self.radius,
+            }
+        }
+    }
+
+    impl Hittable for Ball {
+        fn hit(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Option<GeometryHit> {
+            self.sphere().hit(ray, ray_length_min_max)
+        }
+
+        fn hits(&self, ray: &Ray, ray_length_min_max: RangeInclusive<f64>) -> Vec<GeometryHit> {
+            self.sphere().hits(ray, ray_length_min_max)
+        }
+
+        fn bounding_box(&self) -> Option<Aabb> {
+            self.sphere().bounding_box()
+        }
+    }
+
+    #[test]
+    fn hittable_test() {
+        let material = ObjectMaterial::Lambert {
+            albedo: Colour::new(0.5, 0.5, 0.5).into(),
+        };
+        let mut objects = vec![Object::new_sphere(
+            Point::new(0., 0., 0.),
+            1.,
+            material.clone(),
+        )];
+        objects.add_object(
+            Object::new_custom(Arc::new(Floor { height: 2. }), material.clone())
+                .with_transform(Transform::translation(Direction::new(0., -1., 0.))),
+        );
+
+        // The floor, moved down to a height of 1, is in front of the sphere
+        let ray = Ray::new(Point::new(0., 5., 0.), Direction::new(0., -1., 0.));
+        let hit = objects
+            .find_intersection(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the floor");
+        assert_eq!(hit.distance_from_ray, 4.);
+        let ray = Ray::new(Point::new(0., 0.5, 5.), Direction::new(0., 0., -1.));
+        let hit = objects
+            .find_intersection(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the sphere");
+        assert!((hit.distance_from_ray - (5. - 0.75f64.sqrt())).abs() < 1e-12);
+
+        let quad = ObjectGeometry::Quad {
+            corner: Point::new(0., 0., 0.),
+            u: Direction::new(2., 0., 0.),
+            v: Direction::new(0., 0., -3.),
+        };
+        let sample = quad.sample_surface().expect("Quads can be sampled");
+        assert_eq!(sample.normal, Direction::new(0., 1., 0.));
+        assert_eq!(sample.pdf, 1. / 6.);
+        assert!(sample.point[1] == 0. && (0.0..=2.).contains(&sample.point[0]));
+        assert!((-3.0..=0.).contains(&sample.point[2]));
+        assert!(Floor { height: 0. }.sample_surface().is_none());
+
+        // The ball without the half in front of Z 0 is left, which needs the Ray to leave the ball
+        let half_ball = ObjectGeometry::Custom {
+            geometry: Arc::new(Ball { radius: 1. }),
+        }
+        .difference(ObjectGeometry::Box {
+            minimum: Point::new(-2., -2., 0.),
+            maximum: Point::new(2., 2., 2.),
+        });
+        let ray = Ray::new(Point::new(0., 0., 5.), Direction::new(0., 0., -1.));
+        let distances = half_ball
+            .hits(&ray, 0.001..=f64::INFINITY)
+            .iter()
+            .map(|hit| hit.distance_from_ray)
+            .collect::<Vec<_>>();
+        assert_eq!(distances, [5., 6.]);
+    }
+}
@@ -3,16 +3,50 @@ use crate::{
     vector::{Colour, Direction},
 };
 
-use super::{HitRecord, RayScatter};
+use super::{hair::HairScattering, HitRecord, RayScatter, Texture};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ObjectMaterial {
-    Lambert { albedo: Colour },
-    Metal { albedo: Colour, fuzzy_scatter: f64 },
-    Dialectric { refraction_index: f64 },
+    Lambert {
+        albedo: Texture,
+    },
+    Metal {
+        albedo: Colour,
+        fuzzy_scatter: f64,
+    },
+    Dialectric {
+        refraction_index: f64,
+    },
+    /// Fibres of hair or fur, best on [`CurveShape::Ribbon`](super::CurveShape::Ribbon) curves.</br>
+    /// `absorption` is the light absorbed inside of the fibre per diameter crossed, the roughnesses between
+    /// [0.01..1.0] spread the light along and around the fibre, and human hair has a `refraction_index` of 1.55.
+    Hair {
+        absorption: Colour,
+        longitudinal_roughness: f64,
+        azimuthal_roughness: f64,
+        refraction_index: f64,
+    },
 }
 
 impl ObjectMaterial {
+    /// Creates a Lambert material of a single `albedo` colour, like `Lambert { albedo: albedo.into() }`.
+    #[must_use]
+    pub fn new_lambert(albedo: Colour) -> Self {
+        Self::Lambert {
+            albedo: albedo.into(),
+        }
+    }
+
+    /// Creates a Hair material whose absorption gives fibres close to `colour`.
+    pub fn new_hair(colour: Colour, longitudinal_roughness: f64, azimuthal_roughness: f64) -> Self {
+        Self::Hair {
+            absorption: HairScattering::absorption_from_colour(colour, azimuthal_roughness),
+            longitudinal_roughness,
+            azimuthal_roughness,
+            refraction_index: 1.55,
+        }
+    }
+
     pub fn scatter(&self, hit_record: &HitRecord) -> RayScatter {
         match self {
             ObjectMaterial::Lambert { albedo } => Self::lambert_scatter(hit_record, albedo),
@@ -23,17 +57,29 @@ impl ObjectMaterial {
             ObjectMaterial::Dialectric { refraction_index } => {
                 Self::dialectric_scatter(hit_record, *refraction_index)
             }
+            ObjectMaterial::Hair {
+                absorption,
+                longitudinal_roughness,
+                azimuthal_roughness,
+                refraction_index,
+            } => HairScattering::new(
+                *absorption,
+                *longitudinal_roughness,
+                *azimuthal_roughness,
+                *refraction_index,
+            )
+            .scatter(hit_record),
         }
     }
 
-    fn lambert_scatter(hit_record: &HitRecord, albedo: &Colour) -> RayScatter {
+    fn lambert_scatter(hit_record: &HitRecord, albedo: &Texture) -> RayScatter {
         let scatter_direction = hit_record.normal
             + hit_record
                 .normal
                 .random_direction_in_hemisphere()
                 .unit_vector();
         RayScatter {
-            attenuation: *albedo,
+            attenuation: albedo.value(hit_record),
             scattered: Ray::new(
                 hit_record.point_of_intersection,
                 if scatter_direction.is_zero() {
@@ -74,7 +120,7 @@ impl ObjectMaterial {
             r0 + (1. - r0) * (1. - cos_theta).powi(5)
         };
 
-        let direction = if cannot_refract || reflectance > rand::random() {
+        let direction = if cannot_refract || reflectance > crate::random::random() {
             unit_direction.reflect(&hit_record.normal)
         } else {
             unit_direction.refract(&hit_record.normal, refraction_ratio)
@@ -0,0 +1,280 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use crate::{
+    ray::Ray,
+    vector::{Colour, Direction, Point},
+};
+
+use super::{bvh::Bvh, Aabb, GeometryHit};
+
+#[derive(Debug, Clone)]
+/// Triangles over shared vertices, with optional normals, UVs, and colours on the vertices.</br>
+/// The vertices and the Bvh over the triangles are shared between clones.
+pub struct TriangleMesh {
+    positions: Arc<[Point]>,
+    triangles: Arc<[[usize; 3]]>,
+    normals: Option<Arc<[Direction]>>,
+    uvs: Option<Arc<[(f64, f64)]>>,
+    colours: Option<Arc<[Colour]>>,
+    bvh: Arc<Bvh>,
+}
+
+impl TriangleMesh {
+    /// Creates the TriangleMesh from the corners of `triangles`, given as indices of `positions`, `None` if an
+    /// index is out of range.</br>
+    /// Triangles face the side where their corners go counter-clockwise.
+    pub fn new(positions: Vec<Point>, triangles: Vec<[usize; 3]>) -> Option<Self> {
+        if triangles
+            .iter()
+            .flatten()
+            .any(|index| *index >= positions.len())
+        {
+            return None;
+        }
+        let bounds = triangles
+            .iter()
+            .map(|triangle| {
+                Aabb::from_points(triangle.map(|index| positions[index]))
+                    .expect("Triangles have corners")
+            })
+            .collect::<Vec<_>>();
+        Some(Self {
+            bvh: Arc::new(Bvh::new(&bounds)),
+            positions: positions.into(),
+            triangles: triangles.into(),
+            normals: None,
+            uvs: None,
+            colours: None,
+        })
+    }
+
+    /// Adds a normal to each vertex, interpolated across the triangles for smooth shading, `None` if there is
+    /// not one normal per vertex.
+    pub fn with_normals(mut self, normals: Vec<Direction>) -> Option<Self> {
+        (normals.len() == self.positions.len()).then(|| {
+            self.normals = Some(normals.into());
+            self
+        })
+    }
+
+    /// Adds surface coordinates to each vertex, `None` if there are not one per vertex.
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Option<Self> {
+        (uvs.len() == self.positions.len()).then(|| {
+            self.uvs = Some(uvs.into());
+            self
+        })
+    }
+
+    /// Adds a linear colour to each vertex, which [`Texture::VertexColour`](super::Texture::VertexColour)
+    /// shows, `None` if there are not one per vertex.
+    pub fn with_colours(mut self, colours: Vec<Colour>) -> Option<Self> {
+        (colours.len() == self.positions.len()).then(|| {
+            self.colours = Some(colours.into());
+            self
+        })
+    }
+
+    /// Reads a PLY or STL mesh from `path`, choosing the format from the first bytes of the file.
+    /// # Errors
+    /// Fails if the file cannot be read, or if its contents are not a supported mesh.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, MeshError> {
+        let bytes = std::fs::read(path)?;
+        if bytes.starts_with(b"ply") {
+            crate::ply::ply_to_mesh(&bytes)
+        } else {
+            crate::stl::stl_to_mesh(&bytes)
+        }
+    }
+
+    pub fn positions(&self) -> &[Point] {
+        &self.positions
+    }
+
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    pub fn normals(&self) -> Option<&[Direction]> {
+        self.normals.as_deref()
+    }
+
+    pub fn uvs(&self) -> Option<&[(f64, f64)]> {
+        self.uvs.as_deref()
+    }
+
+    pub fn colours(&self) -> Option<&[Colour]> {
+        self.colours.as_deref()
+    }
+
+    /// Box that contains the mesh, `None` if the mesh has no triangles.
+    pub(super) fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.bounding_box()
+    }
+
+    pub(super) fn hit(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let mut closest: Option<GeometryHit> = None;
+        self.bvh
+            .traverse(ray, ray_length_min_max.clone(), |triangle| {
+                let end = closest
+                    .as_ref()
+                    .map_or(*ray_length_min_max.end(), |hit| hit.distance_from_ray);
+                let hit = self.hit_triangle(triangle, ray, *ray_length_min_max.start()..=end)?;
+                let distance = hit.distance_from_ray;
+                closest = Some(hit);
+                Some(distance)
+            });
+        closest
+    }
+
+    /// Every hit of `ray` with the mesh within `ray_length_min_max`, in order along the Ray.
+    pub(super) fn hits(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Vec<GeometryHit> {
+        let mut hits = Vec::new();
+        self.bvh
+            .traverse(ray, ray_length_min_max.clone(), |triangle| {
+                hits.extend(self.hit_triangle(triangle, ray, ray_length_min_max.clone()));
+                None
+            });
+        hits.sort_by(|lhs, rhs| lhs.distance_from_ray.total_cmp(&rhs.distance_from_ray));
+        hits
+    }
+
+    fn hit_triangle(
+        &self,
+        triangle: usize,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        let corners = self.triangles[triangle];
+        let [a, b, c] = corners.map(|index| Point::default().point_towards(self.positions[index]));
+        let origin = Point::default().point_towards(*ray.origin());
+        let (root, weight_b, weight_c) =
+            hit_triangle([a, b, c], origin, *ray.direction(), &ray_length_min_max)?;
+
+        let weights = [1. - weight_b - weight_c, weight_b, weight_c];
+        let geometric_normal = (b - a).cross(c - a).unit_vector();
+        let normal = self
+            .normals
+            .as_ref()
+            .map(|normals| interpolate(corners, weights, |index| normals[index]))
+            .filter(|normal| !normal.is_zero())
+            .map_or(geometric_normal, Direction::unit_vector);
+        let uv = self.uvs.as_ref().map_or((weight_b, weight_c), |uvs| {
+            let uv = interpolate(corners, weights, |index| {
+                Direction::new(uvs[index].0, uvs[index].1, 0.)
+            });
+            (uv[0], uv[1])
+        });
+        let vertex_colour = self
+            .colours
+            .as_ref()
+            .map(|colours| interpolate(corners, weights, |index| colours[index]));
+        Some(GeometryHit {
+            distance_from_ray: root,
+            point_of_intersection: ray.at(root),
+            normal,
+            uv,
+            vertex_colour,
+            tangent: None,
+        })
+    }
+}
+
+/// Sum of the values at the corners of a triangle, scaled by `weights`.
+fn interpolate<T>(corners: [usize; 3], weights: [f64; 3], value: impl Fn(usize) -> T) -> T
+where
+    T: std::ops::Mul<f64, Output = T> + std::ops::Add<Output = T>,
+{
+    value(corners[0]) * weights[0] + value(corners[1]) * weights[1] + value(corners[2]) * weights[2]
+}
+
+/// Unit normal of each of `positions`, averaged from the normals of the `triangles` around it weighted by
+/// their areas. Vertices outside of every triangle get a zero normal.
+pub(super) fn vertex_normals(positions: &[Point], triangles: &[[usize; 3]]) -> Vec<Direction> {
+    let mut normals = vec![Direction::default(); positions.len()];
+    for triangle in triangles {
+        let [a, b, c] = triangle.map(|index| positions[index]);
+        // Twice the area, facing out of the triangle
+        let normal = a.point_towards(b).cross(a.point_towards(c));
+        for index in triangle {
+            normals[*index] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|normal| {
+            if normal.is_zero() {
+                normal
+            } else {
+                normal.unit_vector()
+            }
+        })
+        .collect()
+}
+
+/// Distance along a Ray to a triangle with `corners`, and the weights of the second and third corners at the
+/// point of intersection.
+pub(super) fn hit_triangle(
+    [a, b, c]: [Direction; 3],
+    origin: Direction,
+    direction: Direction,
+    ray_length_min_max: &RangeInclusive<f64>,
+) -> Option<(f64, f64, f64)> {
+    let (edge_b, edge_c) = (b - a, c - a);
+    let perpendicular = direction.cross(edge_c);
+    let determinant = edge_b.dot(perpendicular);
+    if determinant == 0. {
+        // The Ray is parallel to the triangle
+        return None;
+    }
+    let inverse_determinant = determinant.recip();
+    let from_a = origin - a;
+    let weight_b = from_a.dot(perpendicular) * inverse_determinant;
+    if !(0.0..=1.).contains(&weight_b) {
+        return None;
+    }
+    let across = from_a.cross(edge_b);
+    let weight_c = direction.dot(across) * inverse_determinant;
+    if !(0.0..=1. - weight_b).contains(&weight_c) {
+        return None;
+    }
+    let root = edge_c.dot(across) * inverse_determinant;
+    ray_length_min_max
+        .contains(&root)
+        .then_some((root, weight_b, weight_c))
+}
+
+#[derive(Debug)]
+/// Errors when reading a TriangleMesh.
+pub enum MeshError {
+    Io(std::io::Error),
+    /// The contents do not follow the format.
+    Malformed(&'static str),
+    /// The contents use a feature of the format that is not supported.
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read mesh: {error}"),
+            Self::Malformed(message) => write!(f, "malformed mesh: {message}"),
+            Self::Unsupported(message) => write!(f, "unsupported mesh: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl From<std::io::Error> for MeshError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
@@ -1,40 +1,276 @@
+mod bounding_box;
+mod bvh;
+mod csg;
+mod curves;
+mod description;
+mod displacement;
 mod geometry;
+mod hair;
+mod heightfield;
 mod hit;
+mod hittable;
 mod material;
+mod mesh;
+mod patch;
+mod polynomial;
+mod quadric;
+mod sdf;
+mod subdivision;
+mod texture;
 
-use std::ops::RangeInclusive;
+use std::{ops::RangeInclusive, sync::Arc};
 
-pub use self::{geometry::*, hit::*, material::*};
-use crate::{ray::Ray, vector::Point};
+pub use self::{
+    bounding_box::*, csg::*, curves::*, displacement::*, geometry::*, heightfield::*, hit::*,
+    hittable::*, material::*, mesh::*, patch::*, sdf::*, subdivision::*, texture::*,
+};
+use crate::{
+    ray::Ray,
+    transform::Transform,
+    vector::{Direction, Point},
+};
 
+#[derive(Debug, Clone)]
 pub struct Object {
     geometry: ObjectGeometry,
     material: ObjectMaterial,
+    transform: Option<Transform>,
 }
 
 impl Object {
     pub fn new_sphere(center: Point, radius: f64, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Sphere { center, radius }, material)
+    }
+
+    /// Creates an infinite plane through `point`, facing `normal`.
+    pub fn new_plane(point: Point, normal: Direction, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Plane { point, normal }, material)
+    }
+
+    /// Creates a flat circle at `center`, facing `normal`.
+    pub fn new_disk(
+        center: Point,
+        normal: Direction,
+        radius: f64,
+        material: ObjectMaterial,
+    ) -> Self {
+        Self::new(
+            ObjectGeometry::Disk {
+                center,
+                normal,
+                radius,
+            },
+            material,
+        )
+    }
+
+    /// Creates a parallelogram with a corner at `corner` and sides `u` and `v`, facing `u` cross `v`.
+    pub fn new_quad(corner: Point, u: Direction, v: Direction, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Quad { corner, u, v }, material)
+    }
+
+    /// Creates a bilinear patch through `corners`, at UV (0, 0), (1, 0), (0, 1), and (1, 1).
+    pub fn new_bilinear_patch(corners: [Point; 4], material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::BilinearPatch { corners }, material)
+    }
+
+    /// Creates an Object from a bicubic Bézier `patch`.
+    pub fn new_bezier_patch(patch: BezierPatch, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::BezierPatch { patch }, material)
+    }
+
+    /// Creates an axis-aligned box with opposite corners at `a` and `b`.
+    pub fn new_box(a: Point, b: Point, material: ObjectMaterial) -> Self {
+        Self::new(
+            ObjectGeometry::Box {
+                minimum: Point::new(a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])),
+                maximum: Point::new(a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])),
+            },
+            material,
+        )
+    }
+
+    /// Creates a cylinder from the center of the `base` to the center of the `top`, closed at both ends
+    /// if `capped`.
+    pub fn new_cylinder(
+        base: Point,
+        top: Point,
+        radius: f64,
+        capped: bool,
+        material: ObjectMaterial,
+    ) -> Self {
+        Self::new(
+            ObjectGeometry::Cylinder {
+                base,
+                axis: base.point_towards(top),
+                radius,
+                capped,
+            },
+            material,
+        )
+    }
+
+    /// Creates a cone frustum from the center of the `base` to the center of the `top`, closed at both ends
+    /// if `capped`. A `top_radius` of zero makes a full cone.
+    pub fn new_cone(
+        base: Point,
+        top: Point,
+        base_radius: f64,
+        top_radius: f64,
+        capped: bool,
+        material: ObjectMaterial,
+    ) -> Self {
+        Self::new(
+            ObjectGeometry::Cone {
+                base,
+                axis: base.point_towards(top),
+                base_radius,
+                top_radius,
+                capped,
+            },
+            material,
+        )
+    }
+
+    /// Creates a capsule, a cylinder from `start` to `end` closed by half spheres.
+    pub fn new_capsule(start: Point, end: Point, radius: f64, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Capsule { start, end, radius }, material)
+    }
+
+    /// Creates a torus at `center`, around `axis`.
+    pub fn new_torus(
+        center: Point,
+        axis: Direction,
+        major_radius: f64,
+        minor_radius: f64,
+        material: ObjectMaterial,
+    ) -> Self {
+        Self::new(
+            ObjectGeometry::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            },
+            material,
+        )
+    }
+
+    /// Creates the surface where `function` is zero, found by sphere tracing with the settings of `tracing`.
+    pub fn new_sdf(function: Sdf, tracing: SdfTracing, material: ObjectMaterial) -> Self {
+        Self::new(
+            ObjectGeometry::SignedDistance { function, tracing },
+            material,
+        )
+    }
+
+    /// Creates a terrain from `heights`, stretched over `minimum` to `minimum + size` on X and Z, where a height
+    /// of 1 rises `size` on Y.
+    pub fn new_heightfield(
+        heights: Heightfield,
+        minimum: Point,
+        size: Direction,
+        material: ObjectMaterial,
+    ) -> Self {
+        Self::new(
+            ObjectGeometry::Heightfield {
+                heights,
+                minimum,
+                size,
+            },
+            material,
+        )
+    }
+
+    /// Creates an Object from the triangles of `mesh`.
+    pub fn new_mesh(mesh: TriangleMesh, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Mesh { mesh }, material)
+    }
+
+    /// Creates an Object from the triangles of a displaced `mesh`.
+    pub fn new_displaced_mesh(mesh: DisplacedMesh, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::DisplacedMesh { mesh }, material)
+    }
+
+    /// Creates an Object from the smooth surface of a subdivision cage.
+    pub fn new_subdivision_surface(surface: SubdivisionSurface, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Subdivision { surface }, material)
+    }
+
+    /// Creates an Object from the strands of `curves`.
+    pub fn new_curves(curves: Curves, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Curves { curves }, material)
+    }
+
+    /// Creates an Object from `geometry` defined outside of this crate.
+    pub fn new_custom(geometry: Arc<dyn Hittable>, material: ObjectMaterial) -> Self {
+        Self::new(ObjectGeometry::Custom { geometry }, material)
+    }
+
+    /// Creates an Object from any `geometry`.
+    pub fn new(geometry: ObjectGeometry, material: ObjectMaterial) -> Self {
         Self {
-            geometry: ObjectGeometry::Sphere { center, radius },
+            geometry,
             material,
+            transform: None,
         }
     }
 
+    /// Places the Object with `transform`, applied after any Transform the Object already had.
+    #[must_use]
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(match self.transform {
+            Some(current) => current.then(&transform),
+            None => transform,
+        });
+        self
+    }
+
+    /// Box that contains the Object, `None` if the Object is unbounded.
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        let bounding_box = self.geometry.bounding_box()?;
+        Some(match &self.transform {
+            Some(transform) => bounding_box.transformed(transform),
+            None => bounding_box,
+        })
+    }
+
+    /// Checks if the Object has custom geometry, which cannot be written to a scene description.
+    pub(crate) fn has_custom_geometry(&self) -> bool {
+        self.geometry.has_custom()
+    }
+
+    pub fn get_material(&self) -> &ObjectMaterial {
+        &self.material
+    }
+
+    /// Replaces the material of the Object.
+    #[must_use]
+    pub fn with_material(mut self, material: ObjectMaterial) -> Self {
+        self.material = material;
+        self
+    }
+
     pub fn hit<'a>(
-        &self,
+        &'a self,
         ray: &'a Ray,
         ray_length_min_max: RangeInclusive<f64>,
     ) -> Option<HitRecord<'a>> {
-        self.geometry.hit(ray, ray_length_min_max).map(
-            |(distance_from_ray, point_of_intersection, normal)| {
-                HitRecord::new(
-                    ray,
-                    distance_from_ray,
-                    point_of_intersection,
-                    normal,
-                    self.material,
-                )
-            },
-        )
+        let hit = match &self.transform {
+            // The Ray is moved into the space of the geometry, and the hit is moved back to the world
+            Some(transform) => self
+                .geometry
+                .hit(&transform.inverse().apply_ray(ray), ray_length_min_max)
+                .map(|hit| GeometryHit {
+                    point_of_intersection: transform.apply_point(hit.point_of_intersection),
+                    normal: transform.apply_normal(hit.normal).unit_vector(),
+                    tangent: hit
+                        .tangent
+                        .map(|tangent| transform.apply_direction(tangent).unit_vector()),
+                    ..hit
+                }),
+            None => self.geometry.hit(ray, ray_length_min_max),
+        };
+        hit.map(|hit| HitRecord::new(ray, hit, &self.material))
     }
 }
@@ -0,0 +1,270 @@
+use std::{ops::RangeInclusive, sync::Arc};
+
+use crate::{
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+use super::{polynomial::solve_quadratic, Aabb, GeometryHit, TriangleMesh};
+
+/// Most rows or columns of quads a BezierPatch is tessellated into.
+const MAX_SEGMENTS: usize = 64;
+/// Farthest the tessellation may be from the patch, relative to the size of the patch.
+const TOLERANCE: f64 = 1e-3;
+
+#[derive(Debug, Clone)]
+/// Bicubic Bézier patch, tessellated into triangles with more rows or columns where the patch bends more.</br>
+/// The triangles carry the normals and UVs of the patch, and they and the control points are shared between
+/// clones.
+pub struct BezierPatch {
+    control_points: Arc<[[Point; 4]; 4]>,
+    mesh: TriangleMesh,
+}
+
+impl BezierPatch {
+    /// Creates the patch from a grid of `control_points`, where `control_points[j][i]` is the `i`th point along
+    /// U of the `j`th row along V.</br>
+    /// The patch goes through the four corners of the grid, and faces the side of the derivative along U
+    /// cross the derivative along V.
+    pub fn new(control_points: [[Point; 4]; 4]) -> Self {
+        let points =
+            control_points.map(|row| row.map(|point| Point::default().point_towards(point)));
+        let size = Aabb::from_points(control_points.into_iter().flatten())
+            .map(|bounds| bounds.minimum.point_towards(bounds.maximum).length())
+            .unwrap_or_default();
+        let tolerance = (size * TOLERANCE).max(f64::EPSILON);
+
+        // A cubic strays from its segments by at most 3/4 of its largest second difference over the square of
+        // the segments, and the twist of the grid bends the pair of triangles of each quad
+        let largest =
+            |difference: &dyn Fn(usize, usize) -> Direction, rows: usize, columns: usize| {
+                (0..rows)
+                    .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                    .map(|(row, column)| difference(row, column).length())
+                    .fold(0., f64::max)
+            };
+        let along_u = largest(
+            &|row, column| {
+                points[row][column] - points[row][column + 1] * 2. + points[row][column + 2]
+            },
+            4,
+            2,
+        );
+        let along_v = largest(
+            &|row, column| {
+                points[row][column] - points[row + 1][column] * 2. + points[row + 2][column]
+            },
+            2,
+            4,
+        );
+        let twist = largest(
+            &|row, column| {
+                points[row][column] - points[row][column + 1] - points[row + 1][column]
+                    + points[row + 1][column + 1]
+            },
+            3,
+            3,
+        );
+        let segments = |bend: f64| {
+            ((0.75 * bend + 2.25 * twist) / tolerance)
+                .sqrt()
+                .ceil()
+                .clamp(1., MAX_SEGMENTS as f64) as usize
+        };
+        let (columns, rows) = (segments(along_u), segments(along_v));
+
+        let mut positions = Vec::with_capacity((columns + 1) * (rows + 1));
+        let mut normals = Vec::with_capacity(positions.capacity());
+        let mut uvs = Vec::with_capacity(positions.capacity());
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let uv = (column as f64 / columns as f64, row as f64 / rows as f64);
+                let (position, normal) = evaluate(&points, uv);
+                positions.push(Point::default() + position);
+                normals.push(normal);
+                uvs.push(uv);
+            }
+        }
+        let triangles = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| row * (columns + 1) + column))
+            .flat_map(|corner| {
+                let above = corner + columns + 1;
+                [[corner, corner + 1, above + 1], [corner, above + 1, above]]
+            })
+            .collect();
+
+        let mesh = TriangleMesh::new(positions, triangles)
+            .and_then(|mesh| mesh.with_normals(normals))
+            .and_then(|mesh| mesh.with_uvs(uvs))
+            .expect("Tessellation has one normal and UV per vertex");
+        Self {
+            control_points: Arc::new(control_points),
+            mesh,
+        }
+    }
+
+    pub fn control_points(&self) -> &[[Point; 4]; 4] {
+        &self.control_points
+    }
+
+    /// Triangles the patch was tessellated into.
+    pub fn mesh(&self) -> &TriangleMesh {
+        &self.mesh
+    }
+
+    /// Box that contains the tessellated patch.
+    pub(super) fn bounding_box(&self) -> Option<Aabb> {
+        self.mesh.bounding_box()
+    }
+
+    pub(super) fn hit(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        self.mesh.hit(ray, ray_length_min_max)
+    }
+}
+
+/// Point and unit normal of a Bézier patch at `uv`, the normal is zero where the patch collapses to a point
+/// both there and right next to it.
+fn evaluate(points: &[[Direction; 4]; 4], (u, v): (f64, f64)) -> (Direction, Direction) {
+    let ((basis_u, derivative_u), (basis_v, derivative_v)) = (bernstein(u), bernstein(v));
+    let sum = |weights_u: [f64; 4], weights_v: [f64; 4]| {
+        (0..4)
+            .flat_map(|row| (0..4).map(move |column| (row, column)))
+            .fold(Direction::default(), |sum, (row, column)| {
+                sum + points[row][column] * (weights_u[column] * weights_v[row])
+            })
+    };
+    let normal = sum(derivative_u, basis_v).cross(sum(basis_u, derivative_v));
+    let normal = if normal.is_zero() && (u, v) != nudged((u, v)) {
+        // Collapsed edges, like the poles of a sphere, have no derivative, so the normal comes from inside
+        evaluate(points, nudged((u, v))).1
+    } else if normal.is_zero() {
+        normal
+    } else {
+        normal.unit_vector()
+    };
+    (sum(basis_u, basis_v), normal)
+}
+
+/// `uv` moved a little away from the edges of the patch.
+fn nudged((u, v): (f64, f64)) -> (f64, f64) {
+    (u.clamp(1e-4, 1. - 1e-4), v.clamp(1e-4, 1. - 1e-4))
+}
+
+/// Cubic Bernstein polynomials at `t` and their derivatives.
+fn bernstein(t: f64) -> ([f64; 4], [f64; 4]) {
+    let s = 1. - t;
+    (
+        [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t],
+        [
+            -3. * s * s,
+            3. * s * s - 6. * s * t,
+            6. * s * t - 3. * t * t,
+            3. * t * t,
+        ],
+    )
+}
+
+/// First hit of `ray` with the bilinear patch through `corners` within `ray_length_min_max`.</br>
+/// The corners are at UV (0, 0), (1, 0), (0, 1), and (1, 1), and the patch faces the side of the derivative
+/// along U cross the derivative along V.
+pub(super) fn hit_bilinear_patch(
+    corners: &[Point; 4],
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<GeometryHit> {
+    let [p00, p10, p01, p11] = *corners;
+    let direction = *ray.direction();
+    let (q00, q10) = (
+        ray.origin().point_towards(p00),
+        ray.origin().point_towards(p10),
+    );
+    let (bottom, right, left) = (
+        p00.point_towards(p10),
+        p10.point_towards(p11),
+        p00.point_towards(p01),
+    );
+
+    // U where the Ray meets the line from the bottom edge to the top edge, Reshetov's "Cool Patches"
+    let a = q00.cross(direction).dot(left);
+    let c = bottom.cross(p11.point_towards(p01)).dot(direction);
+    let b = q10.cross(direction).dot(right) - a - c;
+    let (u, v, root) = solve_quadratic(c, b, a)
+        .into_iter()
+        .filter(|u| (0.0..=1.).contains(u))
+        .filter_map(|u| {
+            let start = q00 * (1. - u) + q10 * u;
+            let along = left * (1. - u) + right * u;
+            let perpendicular = direction.cross(along);
+            let determinant = perpendicular.length_squared();
+            if determinant == 0. {
+                return None;
+            }
+            let across = perpendicular.cross(start);
+            let root = across.dot(along) / determinant;
+            let v = across.dot(direction) / determinant;
+            ((0.0..=1.).contains(&v) && ray_length_min_max.contains(&root)).then_some((u, v, root))
+        })
+        .min_by(|lhs, rhs| lhs.2.total_cmp(&rhs.2))?;
+
+    let derivative_u = bottom * (1. - v) + p01.point_towards(p11) * v;
+    let derivative_v = left * (1. - u) + right * u;
+    let normal = derivative_u.cross(derivative_v);
+    let normal = if normal.is_zero() {
+        p00.point_towards(p11).cross(p10.point_towards(p01))
+    } else {
+        normal
+    };
+    Some(GeometryHit {
+        distance_from_ray: root,
+        point_of_intersection: ray.at(root),
+        normal: if normal.is_zero() {
+            normal
+        } else {
+            normal.unit_vector()
+        },
+        uv: (u, v),
+        vertex_colour: None,
+        tangent: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_hit_test() {
+        // Saddle where Z is U times V
+        let saddle = |u: f64, v: f64| Point::new(u, v, u * v);
+        let corners = [
+            saddle(0., 0.),
+            saddle(1., 0.),
+            saddle(0., 1.),
+            saddle(1., 1.),
+        ];
+        let ray = Ray::new(Point::new(0.5, 0.25, 5.), Direction::new(0., 0., -1.));
+        let hit = hit_bilinear_patch(&corners, &ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the bilinear patch");
+        assert!((hit.distance_from_ray - 4.875).abs() < 1e-9);
+        assert!((hit.uv.0 - 0.5).abs() < 1e-9 && (hit.uv.1 - 0.25).abs() < 1e-9);
+        let expected = Direction::new(-0.25, -0.5, 1.).unit_vector();
+        assert!((hit.normal - expected).length() < 1e-9);
+
+        // The same saddle as a Bézier patch, since a bilinear patch is a Bézier patch with evenly spaced
+        // control points
+        let patch = BezierPatch::new(std::array::from_fn(|row| {
+            std::array::from_fn(|column| saddle(column as f64 / 3., row as f64 / 3.))
+        }));
+        let hit = patch
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the Bézier patch");
+        assert!((hit.distance_from_ray - 4.875).abs() < 1e-2);
+        assert!((hit.uv.0 - 0.5).abs() < 1e-9 && (hit.uv.1 - 0.25).abs() < 1e-9);
+        assert!((hit.normal - expected).length() < 1e-2);
+        assert!(patch.mesh().triangles().len() > 2);
+    }
+}
@@ -0,0 +1,126 @@
+/// Real roots of `a x² + b x + c`, in ascending order.
+pub(crate) fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return if b.abs() < 1e-12 {
+            Vec::new()
+        } else {
+            vec![-c / b]
+        };
+    }
+
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return Vec::new();
+    }
+    // Avoids the cancellation of `-b + sqrt(discriminant)` when `b` is large
+    let q = -0.5 * (b + discriminant.sqrt().copysign(b));
+    let mut roots = if q == 0. {
+        vec![0., 0.]
+    } else {
+        vec![q / a, c / q]
+    };
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Largest real root of `x³ + a x² + b x + c`.
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    // Depressed cubic t³ + p t + q with x = t - a / 3
+    let p = b - a * a / 3.;
+    let q = 2. * a.powi(3) / 27. - a * b / 3. + c;
+    let discriminant = (q / 2.).powi(2) + (p / 3.).powi(3);
+
+    let t = if discriminant > 0. {
+        // One real root
+        let sqrt_discriminant = discriminant.sqrt();
+        (-q / 2. + sqrt_discriminant).cbrt() + (-q / 2. - sqrt_discriminant).cbrt()
+    } else {
+        // Three real roots, the largest is at angle 0
+        let radius = (-p / 3.).max(0.).sqrt();
+        let cos_angle = if radius == 0. {
+            0.
+        } else {
+            (-q / (2. * radius.powi(3))).clamp(-1., 1.)
+        };
+        2. * radius * (cos_angle.acos() / 3.).cos()
+    };
+    t - a / 3.
+}
+
+/// Real roots of `a x⁴ + b x³ + c x² + d x + e`, in ascending order, found with Ferrari's method and refined
+/// with Newton's method.
+pub(crate) fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return solve_cubic(b, c, d, e);
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depressed quartic y⁴ + p y² + q y + r with x = y - b / 4
+    let p = c - 3. * b * b / 8.;
+    let q = d - b * c / 2. + b.powi(3) / 8.;
+    let r = e - b * d / 4. + b * b * c / 16. - 3. * b.powi(4) / 256.;
+
+    let mut roots = if q.abs() < 1e-12 {
+        // Biquadratic, quadratic on y²
+        solve_quadratic(1., p, r)
+            .into_iter()
+            .filter(|z| *z >= 0.)
+            .flat_map(|z| [z.sqrt(), -z.sqrt()])
+            .collect::<Vec<_>>()
+    } else {
+        // `m` makes both sides of (y² + p/2 + m)² = 2m y² - q y + m² + m p + p²/4 - r perfect squares
+        let m = largest_cubic_root(p, p * p / 4. - r, -q * q / 8.);
+        if m <= 0. {
+            return Vec::new();
+        }
+        let s = (2. * m).sqrt();
+        let mut roots = solve_quadratic(1., -s, p / 2. + m + q / (2. * s));
+        roots.extend(solve_quadratic(1., s, p / 2. + m - q / (2. * s)));
+        roots
+    };
+
+    for root in &mut roots {
+        *root -= b / 4.;
+        for _ in 0..3 {
+            let value = (((*root + b) * *root + c) * *root + d) * *root + e;
+            let derivative = ((4. * *root + 3. * b) * *root + 2. * c) * *root + d;
+            if derivative.abs() < 1e-12 {
+                break;
+            }
+            *root -= value / derivative;
+        }
+    }
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Real roots of `a x³ + b x² + c x + d`, in ascending order.
+fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() < 1e-12 {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+    let largest = largest_cubic_root(b, c, d);
+    // Divide out the known root, leaving x² + (b + largest) x + (c + largest (b + largest))
+    let mut roots = solve_quadratic(1., b + largest, c + largest * (b + largest));
+    roots.push(largest);
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_quartic_test() {
+        // (x - 1)(x - 2)(x + 3)(x - 0.5)
+        let roots = solve_quartic(1., -0.5, -7., 9.5, -3.);
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip([-3., 0.5, 1., 2.]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+        // x⁴ + 1 has no real roots
+        assert!(solve_quartic(1., 0., 0., 0., 1.).is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use crate::{
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+use super::{
+    geometry::orthonormal_basis,
+    polynomial::{solve_quadratic, solve_quartic},
+    GeometryHit,
+};
+
+/// Orthonormal frame where the axis of a shape is Y, so the shape can be intersected in its simplest form.
+pub(super) struct LocalFrame {
+    origin: Point,
+    tangent: Direction,
+    axis: Direction,
+    bitangent: Direction,
+}
+
+impl LocalFrame {
+    /// Creates the LocalFrame at `origin` with Y along `axis`, `None` if `axis` is zero.
+    pub(super) fn new(origin: Point, axis: Direction) -> Option<Self> {
+        if axis.length_squared() < 1e-24 {
+            return None;
+        }
+        let axis = axis.unit_vector();
+        let (bitangent, tangent) = orthonormal_basis(axis);
+        Some(Self {
+            origin,
+            tangent,
+            axis,
+            bitangent,
+        })
+    }
+
+    pub(super) fn to_local_point(&self, point: Point) -> Direction {
+        self.to_local_direction(self.origin.point_towards(point))
+    }
+
+    pub(super) fn to_local_direction(&self, direction: Direction) -> Direction {
+        Direction::new(
+            direction.dot(self.tangent),
+            direction.dot(self.axis),
+            direction.dot(self.bitangent),
+        )
+    }
+
+    pub(super) fn to_world_direction(&self, direction: Direction) -> Direction {
+        self.tangent * direction[0] + self.axis * direction[1] + self.bitangent * direction[2]
+    }
+}
+
+/// Angle around the Y axis of a local point, between [0.0..1.0).
+fn angle_around_axis(local: Direction) -> f64 {
+    (f64::atan2(local[2], local[0]) + PI) / (2. * PI)
+}
+
+struct LocalHit {
+    distance_from_ray: f64,
+    normal: Direction,
+    uv: (f64, f64),
+}
+
+/// Moves the nearest of `hits` back to the world.
+fn to_nearest_geometry_hit(
+    frame: &LocalFrame,
+    ray: &Ray,
+    hits: impl IntoIterator<Item = LocalHit>,
+) -> Option<GeometryHit> {
+    hits.into_iter()
+        .min_by(|lhs, rhs| lhs.distance_from_ray.total_cmp(&rhs.distance_from_ray))
+        .map(|hit| to_geometry_hit(frame, ray, hit))
+}
+
+fn to_geometry_hit(frame: &LocalFrame, ray: &Ray, hit: LocalHit) -> GeometryHit {
+    GeometryHit {
+        distance_from_ray: hit.distance_from_ray,
+        point_of_intersection: ray.at(hit.distance_from_ray),
+        normal: frame.to_world_direction(hit.normal).unit_vector(),
+        uv: hit.uv,
+        vertex_colour: None,
+        tangent: None,
+    }
+}
+
+/// Moves `hits` back to the world, in order along the Ray.
+fn to_geometry_hits(
+    frame: &LocalFrame,
+    ray: &Ray,
+    hits: impl IntoIterator<Item = LocalHit>,
+) -> Vec<GeometryHit> {
+    let mut hits = hits
+        .into_iter()
+        .map(|hit| to_geometry_hit(frame, ray, hit))
+        .collect::<Vec<_>>();
+    hits.sort_by(|lhs, rhs| lhs.distance_from_ray.total_cmp(&rhs.distance_from_ray));
+    hits
+}
+
+/// Hits of a Ray, given in local coordinates, with the side of a cone frustum between heights 0 and `height`.
+fn hit_cone_side(
+    origin: Direction,
+    direction: Direction,
+    height: f64,
+    base_radius: f64,
+    top_radius: f64,
+    ray_length_min_max: &RangeInclusive<f64>,
+) -> impl Iterator<Item = LocalHit> {
+    // x² + z² = (base_radius + slope y)²
+    let slope = (top_radius - base_radius) / height;
+    let radius_at_origin = base_radius + slope * origin[1];
+    let a = direction[0].powi(2) + direction[2].powi(2) - (slope * direction[1]).powi(2);
+    let b = 2.
+        * (origin[0] * direction[0] + origin[2] * direction[2]
+            - slope * direction[1] * radius_at_origin);
+    let c = origin[0].powi(2) + origin[2].powi(2) - radius_at_origin.powi(2);
+
+    let ray_length_min_max = ray_length_min_max.clone();
+    solve_quadratic(a, b, c)
+        .into_iter()
+        .filter_map(move |root| {
+            let local = origin + direction * root;
+            let radius = base_radius + slope * local[1];
+            (ray_length_min_max.contains(&root)
+                && (0.0..=height).contains(&local[1])
+                && radius >= 0.)
+                .then(|| LocalHit {
+                    distance_from_ray: root,
+                    normal: Direction::new(local[0], -slope * radius, local[2]),
+                    uv: (angle_around_axis(local), local[1] / height),
+                })
+        })
+}
+
+/// Hit of a Ray, given in local coordinates, with the cap at `cap_height` facing `facing` along Y.
+fn hit_cap(
+    origin: Direction,
+    direction: Direction,
+    cap_height: f64,
+    radius: f64,
+    facing: f64,
+    ray_length_min_max: &RangeInclusive<f64>,
+) -> Option<LocalHit> {
+    if direction[1].abs() < 1e-12 {
+        return None;
+    }
+    let root = (cap_height - origin[1]) / direction[1];
+    let local = origin + direction * root;
+    let distance_from_axis = local[0].hypot(local[2]);
+    (ray_length_min_max.contains(&root) && distance_from_axis <= radius).then(|| LocalHit {
+        distance_from_ray: root,
+        normal: Direction::new(0., facing, 0.),
+        uv: (
+            angle_around_axis(local),
+            distance_from_axis / radius.max(f64::EPSILON),
+        ),
+    })
+}
+
+/// Hits a cone frustum from `base` to `base + axis`, with radii `base_radius` and `top_radius` at each end.
+pub(super) fn hit_cone(
+    base: &Point,
+    axis: &Direction,
+    base_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<GeometryHit> {
+    let (frame, hits) = cone_local_hits(
+        base,
+        axis,
+        base_radius,
+        top_radius,
+        capped,
+        ray,
+        ray_length_min_max,
+    )?;
+    to_nearest_geometry_hit(&frame, ray, hits)
+}
+
+/// Hits within `ray_length_min_max` of a cone frustum from `base` to `base + axis`, with radii `base_radius`
+/// and `top_radius` at each end, in order along the Ray.
+pub(super) fn cone_hits(
+    base: &Point,
+    axis: &Direction,
+    base_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Vec<GeometryHit> {
+    cone_local_hits(
+        base,
+        axis,
+        base_radius,
+        top_radius,
+        capped,
+        ray,
+        ray_length_min_max,
+    )
+    .map_or_else(Vec::new, |(frame, hits)| {
+        to_geometry_hits(&frame, ray, hits)
+    })
+}
+
+/// Frame of a cone frustum and the hits of a Ray with it, in no order, `None` if `axis` is zero.
+fn cone_local_hits(
+    base: &Point,
+    axis: &Direction,
+    base_radius: f64,
+    top_radius: f64,
+    capped: bool,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<(LocalFrame, impl Iterator<Item = LocalHit>)> {
+    let frame = LocalFrame::new(*base, *axis)?;
+    let height = axis.length();
+    let (base_radius, top_radius) = (base_radius.abs(), top_radius.abs());
+    let origin = frame.to_local_point(*ray.origin());
+    let direction = frame.to_local_direction(*ray.direction());
+
+    let side = hit_cone_side(
+        origin,
+        direction,
+        height,
+        base_radius,
+        top_radius,
+        &ray_length_min_max,
+    );
+    let caps = capped.then(|| {
+        [
+            hit_cap(origin, direction, 0., base_radius, -1., &ray_length_min_max),
+            hit_cap(
+                origin,
+                direction,
+                height,
+                top_radius,
+                1.,
+                &ray_length_min_max,
+            ),
+        ]
+    });
+    Some((frame, side.chain(caps.into_iter().flatten().flatten())))
+}
+
+/// Hits a capsule, a cylinder from `start` to `end` closed by half spheres.
+pub(super) fn hit_capsule(
+    start: &Point,
+    end: &Point,
+    radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<GeometryHit> {
+    let (frame, hits) = capsule_local_hits(start, end, radius, ray, ray_length_min_max)?;
+    to_nearest_geometry_hit(&frame, ray, hits)
+}
+
+/// Hits within `ray_length_min_max` of a capsule, a cylinder from `start` to `end` closed by half spheres,
+/// in order along the Ray.
+pub(super) fn capsule_hits(
+    start: &Point,
+    end: &Point,
+    radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Vec<GeometryHit> {
+    capsule_local_hits(start, end, radius, ray, ray_length_min_max)
+        .map_or_else(Vec::new, |(frame, hits)| {
+            to_geometry_hits(&frame, ray, hits)
+        })
+}
+
+/// Frame of a capsule and the hits of a Ray with it, in no order.
+fn capsule_local_hits(
+    start: &Point,
+    end: &Point,
+    radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<(LocalFrame, impl Iterator<Item = LocalHit>)> {
+    let radius = radius.abs();
+    // A capsule with no length is a sphere, its frame can point anywhere
+    let axis = start.point_towards(*end);
+    let frame = LocalFrame::new(*start, axis)
+        .or_else(|| LocalFrame::new(*start, Direction::new(0., 1., 0.)))?;
+    let length = axis.length();
+    let origin = frame.to_local_point(*ray.origin());
+    let direction = frame.to_local_direction(*ray.direction());
+    let total_length = length + 2. * radius;
+
+    let side = (length > 1e-12)
+        .then(|| {
+            hit_cone_side(
+                origin,
+                direction,
+                length,
+                radius,
+                radius,
+                &ray_length_min_max,
+            )
+        })
+        .into_iter()
+        .flatten();
+    // Only the half of each sphere that is outside of the cylinder is part of the capsule
+    let half_sphere = |center_height: f64, outside: fn(f64) -> bool| {
+        let center = Direction::new(0., center_height, 0.);
+        let from_center = origin - center;
+        let ray_length_min_max = ray_length_min_max.clone();
+        solve_quadratic(
+            direction.length_squared(),
+            2. * from_center.dot(direction),
+            from_center.length_squared() - radius.powi(2),
+        )
+        .into_iter()
+        .filter_map(move |root| {
+            let local = origin + direction * root;
+            (ray_length_min_max.contains(&root) && outside(local[1] - center_height)).then(|| {
+                LocalHit {
+                    distance_from_ray: root,
+                    normal: local - center,
+                    uv: (angle_around_axis(local), (local[1] + radius) / total_length),
+                }
+            })
+        })
+    };
+
+    let hits = side
+        .map(move |hit| LocalHit {
+            uv: (hit.uv.0, (hit.uv.1 * length + radius) / total_length),
+            ..hit
+        })
+        .chain(half_sphere(0., |offset| offset <= 0.))
+        .chain(half_sphere(length, |offset| offset >= 0.));
+    Some((frame, hits))
+}
+
+/// Hits a torus at `center` around `axis`, with the tube of `minor_radius` at `major_radius` from the center.
+pub(super) fn hit_torus(
+    center: &Point,
+    axis: &Direction,
+    major_radius: f64,
+    minor_radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<GeometryHit> {
+    let (frame, mut hits) = torus_local_hits(
+        center,
+        axis,
+        major_radius,
+        minor_radius,
+        ray,
+        ray_length_min_max,
+    )?;
+    // The hits are already in order, so only the first one is worked out
+    hits.next().map(|hit| to_geometry_hit(&frame, ray, hit))
+}
+
+/// Hits within `ray_length_min_max` of a torus at `center` around `axis`, with the tube of `minor_radius` at
+/// `major_radius` from the center, in order along the Ray.
+pub(super) fn torus_hits(
+    center: &Point,
+    axis: &Direction,
+    major_radius: f64,
+    minor_radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Vec<GeometryHit> {
+    torus_local_hits(
+        center,
+        axis,
+        major_radius,
+        minor_radius,
+        ray,
+        ray_length_min_max,
+    )
+    .map_or_else(Vec::new, |(frame, hits)| {
+        to_geometry_hits(&frame, ray, hits)
+    })
+}
+
+/// Frame of a torus and the hits of a Ray with it, in order along the Ray.
+fn torus_local_hits(
+    center: &Point,
+    axis: &Direction,
+    major_radius: f64,
+    minor_radius: f64,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<(LocalFrame, impl Iterator<Item = LocalHit>)> {
+    let frame = LocalFrame::new(*center, *axis)?;
+    let (major_radius, minor_radius) = (major_radius.abs(), minor_radius.abs());
+    let direction = frame.to_local_direction(*ray.direction());
+    let speed = direction.length();
+    if speed < 1e-12 {
+        return None;
+    }
+    let direction = direction / speed;
+
+    // Solving from the point of the Ray closest to the center keeps the coefficients small
+    let origin = frame.to_local_point(*ray.origin());
+    let shift = -origin.dot(direction);
+    let origin = origin + direction * shift;
+
+    // (x² + y² + z² + R² - r²)² = 4 R² (x² + z²)
+    let major_squared = major_radius.powi(2);
+    let k = origin.length_squared() + major_squared - minor_radius.powi(2);
+    let origin_dot_direction = origin.dot(direction);
+    let roots = solve_quartic(
+        1.,
+        4. * origin_dot_direction,
+        2. * k + 4. * origin_dot_direction.powi(2)
+            - 4. * major_squared * (direction[0].powi(2) + direction[2].powi(2)),
+        4. * k * origin_dot_direction
+            - 8. * major_squared * (origin[0] * direction[0] + origin[2] * direction[2]),
+        k.powi(2) - 4. * major_squared * (origin[0].powi(2) + origin[2].powi(2)),
+    );
+
+    // Roots are in ascending order, and dividing by the positive speed keeps it
+    let hits = roots.into_iter().filter_map(move |root| {
+        let distance_from_ray = (root + shift) / speed;
+        ray_length_min_max.contains(&distance_from_ray).then(|| {
+            let local = origin + direction * root;
+            let distance_from_axis = local[0].hypot(local[2]);
+            // Direction from the center of the tube, which is the normal of the torus
+            let ring = if distance_from_axis < 1e-12 {
+                Direction::default()
+            } else {
+                Direction::new(local[0], 0., local[2]) * (major_radius / distance_from_axis)
+            };
+            LocalHit {
+                distance_from_ray,
+                normal: local - ring,
+                uv: (
+                    angle_around_axis(local),
+                    (f64::atan2(local[1], distance_from_axis - major_radius) + PI) / (2. * PI),
+                ),
+            }
+        })
+    });
+    Some((frame, hits))
+}
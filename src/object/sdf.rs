@@ -0,0 +1,527 @@
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use crate::{
+    ray::Ray,
+    transform::Transform,
+    vector::{Direction, Point},
+};
+
+use super::{Aabb, GeometryHit};
+
+/// Iterations of a Mandelbulb stop once the point is this far from the origin.
+const MANDELBULB_ESCAPE_RADIUS: f64 = 2.;
+
+#[derive(Debug, Clone)]
+/// Signed distance function, the distance from a point to the closest surface, negative inside of the shape.</br>
+/// Shapes are centered at the origin and placed with the operators.
+pub enum Sdf {
+    Sphere {
+        radius: f64,
+    },
+    /// Box from `-half_extents` to `half_extents`, with edges rounded by `rounding`.
+    Box {
+        half_extents: Direction,
+        rounding: f64,
+    },
+    /// Torus around the Y axis.
+    Torus {
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    /// Mandelbulb fractal of `power`, inside a sphere of radius 1.2.
+    Mandelbulb {
+        power: f64,
+        iterations: usize,
+    },
+    /// Menger sponge fractal, in a box from (-1, -1, -1) to (1, 1, 1).
+    MengerSponge {
+        iterations: usize,
+    },
+    /// Places `child` with `transform`, which should only rotate and translate to keep the distances exact.
+    Transform {
+        transform: Transform,
+        child: Box<Sdf>,
+    },
+    /// Scales `child` by `factor` from the origin.
+    Scale {
+        factor: f64,
+        child: Box<Sdf>,
+    },
+    Union {
+        left: Box<Sdf>,
+        right: Box<Sdf>,
+    },
+    Intersection {
+        left: Box<Sdf>,
+        right: Box<Sdf>,
+    },
+    /// Inside of `left` but not of `right`.
+    Difference {
+        left: Box<Sdf>,
+        right: Box<Sdf>,
+    },
+    /// Union that blends the surfaces where they are closer than `smoothness`.
+    SmoothUnion {
+        left: Box<Sdf>,
+        right: Box<Sdf>,
+        smoothness: f64,
+    },
+    /// Repeats `child` forever, every `period` along each axis. An axis with a period of zero does not repeat.
+    Repeat {
+        period: Direction,
+        child: Box<Sdf>,
+    },
+    /// Rotates `child` around the Y axis by `rate` degrees for each unit of height.
+    Twist {
+        rate: f64,
+        child: Box<Sdf>,
+    },
+    /// Moves the surface of `child` out by a wave of `amplitude` with `frequency` waves per unit.
+    Displace {
+        amplitude: f64,
+        frequency: f64,
+        child: Box<Sdf>,
+    },
+}
+
+impl Sdf {
+    pub fn sphere(radius: f64) -> Self {
+        Self::Sphere { radius }
+    }
+
+    pub fn cuboid(half_extents: Direction, rounding: f64) -> Self {
+        Self::Box {
+            half_extents,
+            rounding,
+        }
+    }
+
+    pub fn torus(major_radius: f64, minor_radius: f64) -> Self {
+        Self::Torus {
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    pub fn mandelbulb(power: f64, iterations: usize) -> Self {
+        Self::Mandelbulb { power, iterations }
+    }
+
+    pub fn menger_sponge(iterations: usize) -> Self {
+        Self::MengerSponge { iterations }
+    }
+
+    #[must_use]
+    pub fn translate(self, offset: Direction) -> Self {
+        self.transform(Transform::translation(offset))
+    }
+
+    /// Rotates by the components of `angles`, in degrees, around the X, Y, then Z axes.
+    #[must_use]
+    pub fn rotate(self, angles: Direction) -> Self {
+        self.transform(Transform::euler_rotation(angles))
+    }
+
+    #[must_use]
+    pub fn transform(self, transform: Transform) -> Self {
+        Self::Transform {
+            transform,
+            child: Box::new(self),
+        }
+    }
+
+    #[must_use]
+    pub fn scale(self, factor: f64) -> Self {
+        Self::Scale {
+            factor,
+            child: Box::new(self),
+        }
+    }
+
+    #[must_use]
+    pub fn union(self, other: Self) -> Self {
+        Self::Union {
+            left: Box::new(self),
+            right: Box::new(other),
+        }
+    }
+
+    #[must_use]
+    pub fn intersection(self, other: Self) -> Self {
+        Self::Intersection {
+            left: Box::new(self),
+            right: Box::new(other),
+        }
+    }
+
+    #[must_use]
+    pub fn difference(self, other: Self) -> Self {
+        Self::Difference {
+            left: Box::new(self),
+            right: Box::new(other),
+        }
+    }
+
+    #[must_use]
+    pub fn smooth_union(self, other: Self, smoothness: f64) -> Self {
+        Self::SmoothUnion {
+            left: Box::new(self),
+            right: Box::new(other),
+            smoothness,
+        }
+    }
+
+    #[must_use]
+    pub fn repeat(self, period: Direction) -> Self {
+        Self::Repeat {
+            period,
+            child: Box::new(self),
+        }
+    }
+
+    #[must_use]
+    pub fn twist(self, rate: f64) -> Self {
+        Self::Twist {
+            rate,
+            child: Box::new(self),
+        }
+    }
+
+    #[must_use]
+    pub fn displace(self, amplitude: f64, frequency: f64) -> Self {
+        Self::Displace {
+            amplitude,
+            frequency,
+            child: Box::new(self),
+        }
+    }
+
+    /// Signed distance from `point` to the surface.
+    pub fn distance(&self, point: Direction) -> f64 {
+        match self {
+            Self::Sphere { radius } => point.length() - radius.abs(),
+            Self::Box {
+                half_extents,
+                rounding,
+            } => {
+                let rounding = rounding.max(0.);
+                let q = Direction::new(
+                    point[0].abs() - half_extents[0].abs() + rounding,
+                    point[1].abs() - half_extents[1].abs() + rounding,
+                    point[2].abs() - half_extents[2].abs() + rounding,
+                );
+                let outside = Direction::new(q[0].max(0.), q[1].max(0.), q[2].max(0.)).length();
+                let inside = q[0].max(q[1]).max(q[2]).min(0.);
+                outside + inside - rounding
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let distance_from_ring = point[0].hypot(point[2]) - major_radius.abs();
+                distance_from_ring.hypot(point[1]) - minor_radius.abs()
+            }
+            Self::Mandelbulb { power, iterations } => mandelbulb(point, *power, *iterations),
+            Self::MengerSponge { iterations } => menger_sponge(point, *iterations),
+            Self::Transform { transform, child } => {
+                let local = transform
+                    .inverse()
+                    .apply_point(Point::new(point[0], point[1], point[2]));
+                child.distance(Direction::new(local[0], local[1], local[2]))
+            }
+            Self::Scale { factor, child } => {
+                let factor = factor.abs().max(f64::EPSILON);
+                child.distance(point / factor) * factor
+            }
+            Self::Union { left, right } => left.distance(point).min(right.distance(point)),
+            Self::Intersection { left, right } => left.distance(point).max(right.distance(point)),
+            Self::Difference { left, right } => left.distance(point).max(-right.distance(point)),
+            Self::SmoothUnion {
+                left,
+                right,
+                smoothness,
+            } => {
+                let (left, right) = (left.distance(point), right.distance(point));
+                let smoothness = smoothness.max(f64::EPSILON);
+                let h = (0.5 + 0.5 * (right - left) / smoothness).clamp(0., 1.);
+                right + (left - right) * h - smoothness * h * (1. - h)
+            }
+            Self::Repeat { period, child } => {
+                let mut local = point;
+                for axis in 0..3 {
+                    if period[axis] > 0. {
+                        local[axis] -= period[axis] * (point[axis] / period[axis]).round();
+                    }
+                }
+                child.distance(local)
+            }
+            Self::Twist { rate, child } => {
+                let (sin, cos) = (-rate.to_radians() * point[1]).sin_cos();
+                child.distance(Direction::new(
+                    cos * point[0] - sin * point[2],
+                    point[1],
+                    sin * point[0] + cos * point[2],
+                ))
+            }
+            Self::Displace {
+                amplitude,
+                frequency,
+                child,
+            } => {
+                let wave = |component: f64| (2. * PI * frequency * component).sin();
+                child.distance(point) - amplitude * wave(point[0]) * wave(point[1]) * wave(point[2])
+            }
+        }
+    }
+
+    /// Box that contains the shape, `None` if the shape repeats forever.
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        let cube = |half: f64| Aabb::around(Point::default(), Direction::new(half, half, half));
+        match self {
+            Self::Sphere { radius } => Some(cube(radius.abs())),
+            Self::Box { half_extents, .. } => Some(Aabb::around(
+                Point::default(),
+                Direction::new(
+                    half_extents[0].abs(),
+                    half_extents[1].abs(),
+                    half_extents[2].abs(),
+                ),
+            )),
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius.abs() + minor_radius.abs();
+                Some(Aabb::around(
+                    Point::default(),
+                    Direction::new(outer, minor_radius.abs(), outer),
+                ))
+            }
+            Self::Mandelbulb { .. } => Some(cube(1.2)),
+            Self::MengerSponge { .. } => Some(cube(1.)),
+            Self::Transform { transform, child } => {
+                Some(child.bounding_box()?.transformed(transform))
+            }
+            Self::Scale { factor, child } => {
+                let bounding_box = child.bounding_box()?;
+                Some(Aabb::new(
+                    bounding_box.minimum * factor.abs(),
+                    bounding_box.maximum * factor.abs(),
+                ))
+            }
+            Self::Union { left, right } => Some(left.bounding_box()?.union(&right.bounding_box()?)),
+            Self::Intersection { left, right } => {
+                match (left.bounding_box(), right.bounding_box()) {
+                    (Some(left), Some(right)) => Some(left.intersection(&right)),
+                    (left, right) => left.or(right),
+                }
+            }
+            Self::Difference { left, .. } => left.bounding_box(),
+            Self::SmoothUnion {
+                left,
+                right,
+                smoothness,
+            } => {
+                // The blend pulls the surface out by up to a quarter of the smoothness
+                let grow = smoothness.abs() / 4.;
+                let bounding_box = left.bounding_box()?.union(&right.bounding_box()?);
+                Some(Aabb::new(
+                    bounding_box.minimum + -Direction::new(grow, grow, grow),
+                    bounding_box.maximum + Direction::new(grow, grow, grow),
+                ))
+            }
+            Self::Repeat { period, child } => {
+                if period[0] > 0. || period[1] > 0. || period[2] > 0. {
+                    None
+                } else {
+                    child.bounding_box()
+                }
+            }
+            Self::Twist { child, .. } => {
+                // Twisting turns the shape around Y, so it stays in the cylinder around its box
+                let bounding_box = child.bounding_box()?;
+                let radius = bounding_box
+                    .corners()
+                    .iter()
+                    .map(|corner| corner[0].hypot(corner[2]))
+                    .fold(0., f64::max);
+                Some(Aabb::new(
+                    Point::new(-radius, bounding_box.minimum[1], -radius),
+                    Point::new(radius, bounding_box.maximum[1], radius),
+                ))
+            }
+            Self::Displace {
+                amplitude, child, ..
+            } => {
+                let grow = amplitude.abs();
+                let bounding_box = child.bounding_box()?;
+                Some(Aabb::new(
+                    bounding_box.minimum + -Direction::new(grow, grow, grow),
+                    bounding_box.maximum + Direction::new(grow, grow, grow),
+                ))
+            }
+        }
+    }
+
+    /// Direction of fastest increase of the distance, which is the normal on the surface.
+    fn gradient(&self, point: Direction, step: f64) -> Direction {
+        // Tetrahedral central differences, four evaluations instead of six
+        [
+            Direction::new(1., -1., -1.),
+            Direction::new(-1., -1., 1.),
+            Direction::new(-1., 1., -1.),
+            Direction::new(1., 1., 1.),
+        ]
+        .into_iter()
+        .fold(Direction::default(), |gradient, offset| {
+            gradient + offset * self.distance(point + offset * step)
+        })
+    }
+}
+
+fn mandelbulb(point: Direction, power: f64, iterations: usize) -> f64 {
+    let mut w = point;
+    let mut radius = w.length();
+    let mut derivative = 1.;
+    for _ in 0..iterations {
+        if !(1e-12..=MANDELBULB_ESCAPE_RADIUS).contains(&radius) {
+            break;
+        }
+        derivative = power * radius.powf(power - 1.) * derivative + 1.;
+        let theta = (w[1] / radius).clamp(-1., 1.).acos() * power;
+        let phi = f64::atan2(w[0], w[2]) * power;
+        w = point
+            + Direction::new(
+                theta.sin() * phi.sin(),
+                theta.cos(),
+                theta.sin() * phi.cos(),
+            ) * radius.powf(power);
+        radius = w.length();
+    }
+    if radius < 1e-12 {
+        return 0.;
+    }
+    0.5 * radius.ln() * radius / derivative
+}
+
+fn menger_sponge(point: Direction, iterations: usize) -> f64 {
+    let mut distance = Sdf::cuboid(Direction::new(1., 1., 1.), 0.).distance(point);
+    let mut scale = 1.;
+    for _ in 0..iterations {
+        let cross = |component: f64| {
+            let wrapped = (component * scale).rem_euclid(2.) - 1.;
+            (1. - 3. * wrapped.abs()).abs()
+        };
+        let (x, y, z) = (cross(point[0]), cross(point[1]), cross(point[2]));
+        scale *= 3.;
+        let hole = (x.max(y).min(y.max(z)).min(z.max(x)) - 1.) / scale;
+        distance = distance.max(hole);
+    }
+    distance
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Settings of the sphere tracing of an Sdf.
+pub struct SdfTracing {
+    /// Distance to the surface where a point counts as a hit.
+    pub epsilon: f64,
+    /// Steps along the Ray before giving up.
+    pub max_steps: usize,
+    /// Distance along the Ray before giving up, for shapes without a bounding box.
+    pub max_distance: f64,
+    /// Fraction of the distance to the surface taken on each step, below 1 for operators that stretch space,
+    /// like twist and displace, which otherwise overshoot the surface.
+    pub step_scale: f64,
+}
+
+impl Default for SdfTracing {
+    fn default() -> Self {
+        Self {
+            epsilon: 1e-4,
+            max_steps: 256,
+            max_distance: 1e3,
+            step_scale: 1.,
+        }
+    }
+}
+
+/// Sphere traces `ray` against `function`, returning the first hit within `ray_length_min_max`.
+pub(super) fn trace(
+    function: &Sdf,
+    tracing: &SdfTracing,
+    ray: &Ray,
+    ray_length_min_max: RangeInclusive<f64>,
+) -> Option<GeometryHit> {
+    let speed = ray.direction().length();
+    if speed < 1e-12 {
+        return None;
+    }
+    let ray_length_min_max = *ray_length_min_max.start()
+        ..=ray_length_min_max
+            .end()
+            .min(ray_length_min_max.start() + tracing.max_distance / speed);
+    let ray_length_min_max = match function.bounding_box() {
+        Some(bounding_box) => bounding_box.hit_range(ray, ray_length_min_max)?,
+        None => ray_length_min_max,
+    };
+
+    let origin = Point::default().point_towards(*ray.origin());
+    let mut root = *ray_length_min_max.start();
+    for _ in 0..tracing.max_steps {
+        let point = origin + *ray.direction() * root;
+        let distance = function.distance(point);
+        if distance.abs() < tracing.epsilon {
+            let normal = function.gradient(point, tracing.epsilon).unit_vector();
+            return Some(GeometryHit {
+                distance_from_ray: root,
+                point_of_intersection: ray.at(root),
+                normal,
+                // Spherical coordinates of the normal, SDFs have no parameterization of their surface
+                uv: (
+                    (f64::atan2(-normal[2], normal[0]) + PI) / (2. * PI),
+                    (-normal[1]).clamp(-1., 1.).acos() / PI,
+                ),
+                vertex_colour: None,
+                tangent: None,
+            });
+        }
+        // Rays that start inside of the shape step towards the surface the same way
+        root += distance.abs() * tracing.step_scale / speed;
+        if root > *ray_length_min_max.end() {
+            return None;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdf_trace_test() {
+        let spheres = Sdf::sphere(1.).union(Sdf::sphere(1.).translate(Direction::new(3., 0., 0.)));
+        assert!((spheres.distance(Direction::new(1.5, 0., 0.)) - 0.5).abs() < 1e-12);
+        assert!((spheres.distance(Direction::new(0., 0., 0.)) + 1.).abs() < 1e-12);
+
+        let ray = Ray::new(Point::new(3., 0., -5.), Direction::new(0., 0., 1.));
+        let hit = trace(
+            &spheres,
+            &SdfTracing::default(),
+            &ray,
+            0.001..=f64::INFINITY,
+        )
+        .expect("Ray should hit the second sphere");
+        assert!((hit.distance_from_ray - 4.).abs() < 1e-3);
+        assert!((hit.normal.dot(Direction::new(0., 0., -1.)) - 1.).abs() < 1e-3);
+
+        let miss = Ray::new(Point::new(1.5, 0., -5.), Direction::new(0., 0., 1.));
+        assert!(trace(
+            &spheres,
+            &SdfTracing::default(),
+            &miss,
+            0.001..=f64::INFINITY
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+use super::{mesh::vertex_normals, Aabb, GeometryHit, TriangleMesh};
+
+/// Most times the cage is refined, each level turns every quad into four.
+const MAX_LEVEL: u32 = 6;
+
+#[derive(Debug, Clone)]
+/// Smooth surface of a cage of polygons, refined with the Catmull-Clark rules into a TriangleMesh.</br>
+/// Edges with one polygon are boundaries that stay sharp, and corners with one polygon stay in place. The mesh
+/// is refined the first time a Ray comes near it, and it and the cage are shared between clones.
+pub struct SubdivisionSurface {
+    positions: Arc<[Point]>,
+    faces: Arc<[Vec<usize>]>,
+    creases: Arc<[([usize; 2], f64)]>,
+    level: u32,
+    mesh: Arc<OnceLock<TriangleMesh>>,
+}
+
+/// Cage during one level of refinement.
+struct Cage {
+    positions: Vec<Direction>,
+    faces: Vec<Vec<usize>>,
+    /// Sharpness of the creased edges, by their vertices in ascending order.
+    sharpness: HashMap<(usize, usize), f64>,
+}
+
+impl SubdivisionSurface {
+    /// Creates the surface of the cage with `faces`, polygons given as indices of `positions` going
+    /// counter-clockwise, refined `level` times, up to 6 times. `None` if a face has less than 3 vertices or an
+    /// index is out of range.
+    pub fn new(positions: Vec<Point>, faces: Vec<Vec<usize>>, level: u32) -> Option<Self> {
+        if faces
+            .iter()
+            .any(|face| face.len() < 3 || face.iter().any(|index| *index >= positions.len()))
+        {
+            return None;
+        }
+        Some(Self {
+            positions: positions.into(),
+            faces: faces.into(),
+            creases: Arc::new([]),
+            level: level.min(MAX_LEVEL),
+            mesh: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// Makes the edges between each pair of vertices of `creases` sharp, `None` if an index is out of range.
+    /// </br>
+    /// An edge with a sharpness of at least the level stays a sharp crease, and lower sharpnesses round the
+    /// crease off after that many levels, blending fractions with the smooth rules.
+    pub fn with_creases(mut self, creases: Vec<([usize; 2], f64)>) -> Option<Self> {
+        if creases
+            .iter()
+            .flat_map(|(edge, _)| edge)
+            .any(|index| *index >= self.positions.len())
+        {
+            return None;
+        }
+        self.creases = creases.into();
+        self.mesh = Arc::new(OnceLock::new());
+        Some(self)
+    }
+
+    pub fn positions(&self) -> &[Point] {
+        &self.positions
+    }
+
+    pub fn faces(&self) -> &[Vec<usize>] {
+        &self.faces
+    }
+
+    pub fn creases(&self) -> &[([usize; 2], f64)] {
+        &self.creases
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Triangles of the refined surface with smooth normals, which are built by the first call.
+    pub fn mesh(&self) -> &TriangleMesh {
+        self.mesh.get_or_init(|| self.refine())
+    }
+
+    /// Box that contains the cage, the refined surface never leaves it.
+    pub(super) fn bounding_box(&self) -> Option<Aabb> {
+        Aabb::from_points(
+            self.faces
+                .iter()
+                .flatten()
+                .map(|index| self.positions[*index]),
+        )
+    }
+
+    pub(super) fn hit(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Option<GeometryHit> {
+        self.mesh().hit(ray, ray_length_min_max)
+    }
+
+    /// Every hit of `ray` with the refined surface within `ray_length_min_max`, in order along the Ray.
+    pub(super) fn hits(
+        &self,
+        ray: &Ray,
+        ray_length_min_max: RangeInclusive<f64>,
+    ) -> Vec<GeometryHit> {
+        self.mesh().hits(ray, ray_length_min_max)
+    }
+
+    fn refine(&self) -> TriangleMesh {
+        let mut sharpness = HashMap::new();
+        for ([a, b], edge_sharpness) in self.creases.iter() {
+            if a != b && *edge_sharpness > 0. {
+                sharpness.insert(edge(*a, *b), *edge_sharpness);
+            }
+        }
+        let mut cage = Cage {
+            positions: self
+                .positions
+                .iter()
+                .map(|position| Point::default().point_towards(*position))
+                .collect(),
+            faces: self.faces.to_vec(),
+            sharpness,
+        };
+        for _ in 0..self.level {
+            cage = cage.refine();
+        }
+
+        let positions = cage
+            .positions
+            .into_iter()
+            .map(|position| Point::default() + position)
+            .collect::<Vec<_>>();
+        let triangles = cage
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len() - 1).map(|index| [face[0], face[index], face[index + 1]])
+            })
+            .collect::<Vec<_>>();
+        let normals = vertex_normals(&positions, &triangles);
+        TriangleMesh::new(positions, triangles)
+            .and_then(|mesh| mesh.with_normals(normals))
+            .expect("Refinement keeps the faces within the vertices")
+    }
+}
+
+impl Cage {
+    /// Splits each face into quads, one for each of its corners, and moves the vertices with the Catmull-Clark
+    /// rules.</br>
+    /// The new vertices are the old vertices, followed by one for each face, then one for each edge.
+    fn refine(&self) -> Self {
+        let face_points = self
+            .faces
+            .iter()
+            .map(|face| average(face.iter().map(|index| self.positions[*index])))
+            .collect::<Vec<_>>();
+
+        // Edges in the order they are first met, with the faces on each side
+        let mut edge_indices = HashMap::new();
+        let mut edges: Vec<((usize, usize), Vec<usize>)> = Vec::new();
+        for (face_index, face) in self.faces.iter().enumerate() {
+            for (corner, a) in face.iter().enumerate() {
+                let key = edge(*a, face[(corner + 1) % face.len()]);
+                let index = *edge_indices.entry(key).or_insert_with(|| {
+                    edges.push((key, Vec::new()));
+                    edges.len() - 1
+                });
+                edges[index].1.push(face_index);
+            }
+        }
+        // Boundaries, and edges of more than two faces, are as sharp as a crease can be
+        let sharpness = |(key, faces): &((usize, usize), Vec<usize>)| {
+            if faces.len() == 2 {
+                self.sharpness.get(key).copied().unwrap_or(0.)
+            } else {
+                f64::INFINITY
+            }
+        };
+
+        let edge_points = edges
+            .iter()
+            .map(|edge| {
+                let ((a, b), faces) = edge;
+                let middle = (self.positions[*a] + self.positions[*b]) / 2.;
+                let sharpness = sharpness(edge);
+                if sharpness >= 1. {
+                    middle
+                } else {
+                    let smooth = (self.positions[*a]
+                        + self.positions[*b]
+                        + face_points[faces[0]]
+                        + face_points[faces[1]])
+                        / 4.;
+                    smooth * (1. - sharpness) + middle * sharpness
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut vertex_edges = vec![Vec::new(); self.positions.len()];
+        for (index, ((a, b), _)) in edges.iter().enumerate() {
+            vertex_edges[*a].push(index);
+            vertex_edges[*b].push(index);
+        }
+        let mut vertex_faces = vec![Vec::new(); self.positions.len()];
+        for (index, face) in self.faces.iter().enumerate() {
+            for vertex in face {
+                vertex_faces[*vertex].push(index);
+            }
+        }
+        let vertex_points = self.positions.iter().enumerate().map(|(vertex, position)| {
+            let (incident_edges, incident_faces) = (&vertex_edges[vertex], &vertex_faces[vertex]);
+            if incident_faces.is_empty() {
+                return *position;
+            }
+            let other = |edge: usize| {
+                let (a, b) = edges[edge].0;
+                self.positions[if a == vertex { b } else { a }]
+            };
+            let sharp = incident_edges
+                .iter()
+                .map(|edge| (*edge, sharpness(&edges[*edge])))
+                .filter(|(_, sharpness)| *sharpness > 0.)
+                .collect::<Vec<_>>();
+
+            let valence = incident_edges.len() as f64;
+            let smooth = (average(incident_faces.iter().map(|face| face_points[*face]))
+                + average(
+                    incident_edges
+                        .iter()
+                        .map(|edge| (*position + other(*edge)) / 2.),
+                ) * 2.
+                + *position * (valence - 3.))
+                / valence;
+            let (sharp_point, vertex_sharpness) = match sharp.as_slice() {
+                // Interior vertices and the ends of creases are smooth
+                [] | [_] => return smooth,
+                [(first, first_sharpness), (second, second_sharpness)]
+                    if incident_faces.len() > 1 =>
+                {
+                    (
+                        (other(*first) + *position * 6. + other(*second)) / 8.,
+                        (first_sharpness + second_sharpness) / 2.,
+                    )
+                }
+                // Corners, where three or more creases meet or the vertex has a single face
+                _ => (
+                    *position,
+                    sharp.iter().map(|(_, sharpness)| sharpness).sum::<f64>() / sharp.len() as f64,
+                ),
+            };
+            if vertex_sharpness >= 1. {
+                sharp_point
+            } else {
+                smooth * (1. - vertex_sharpness) + sharp_point * vertex_sharpness
+            }
+        });
+
+        let (face_start, edge_start) = (
+            self.positions.len(),
+            self.positions.len() + self.faces.len(),
+        );
+        let faces = self
+            .faces
+            .iter()
+            .enumerate()
+            .flat_map(|(face_index, face)| {
+                let edge_point = |a: usize, b: usize| edge_start + edge_indices[&edge(a, b)];
+                (0..face.len())
+                    .map(|corner| {
+                        let (previous, vertex, next) = (
+                            face[(corner + face.len() - 1) % face.len()],
+                            face[corner],
+                            face[(corner + 1) % face.len()],
+                        );
+                        vec![
+                            vertex,
+                            edge_point(vertex, next),
+                            face_start + face_index,
+                            edge_point(previous, vertex),
+                        ]
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // Each half of a crease is one level less sharp
+        let mut sharpness = HashMap::new();
+        for (key, edge_sharpness) in &self.sharpness {
+            if *edge_sharpness > 1. {
+                if let Some(index) = edge_indices.get(key) {
+                    let middle = edge_start + index;
+                    sharpness.insert(edge(key.0, middle), edge_sharpness - 1.);
+                    sharpness.insert(edge(middle, key.1), edge_sharpness - 1.);
+                }
+            }
+        }
+
+        Self {
+            positions: vertex_points
+                .chain(face_points.iter().copied())
+                .chain(edge_points)
+                .collect(),
+            faces,
+            sharpness,
+        }
+    }
+}
+
+/// Key of the edge between `a` and `b`, the same in both directions.
+fn edge(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+fn average(directions: impl ExactSizeIterator<Item = Direction>) -> Direction {
+    let count = directions.len() as f64;
+    directions.fold(Direction::default(), |sum, direction| sum + direction) / count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subdivision_surface_test() {
+        // Cube from -1 to 1, the corner at index 7 is at (1, 1, 1)
+        let positions = (0..8)
+            .map(|index| {
+                let coordinate = |bit: usize| if index & bit == 0 { -1. } else { 1. };
+                Point::new(coordinate(1), coordinate(2), coordinate(4))
+            })
+            .collect::<Vec<_>>();
+        let faces = vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ];
+        let cube = SubdivisionSurface::new(positions.clone(), faces.clone(), 1)
+            .expect("Cube should be a valid cage");
+        assert_eq!(cube.mesh().triangles().len(), 48);
+        // Smooth rule for a corner with three faces
+        let corner = Point::new(5. / 9., 5. / 9., 5. / 9.);
+        assert!(cube.mesh().positions()[7].point_towards(corner).length() < 1e-12);
+        let ray = Ray::new(Point::new(0.1, 0.2, 5.), Direction::new(0., 0., -1.));
+        let hit = cube
+            .hit(&ray, 0.001..=f64::INFINITY)
+            .expect("Ray should hit the refined cube");
+        assert!(hit.distance_from_ray > 4. && hit.distance_from_ray < 5.);
+
+        // The three creases meeting at the corner keep it in place, and the creases between keep their middles
+        let creased = SubdivisionSurface::new(positions, faces, 1)
+            .and_then(|cube| cube.with_creases(vec![([7, 6], 2.), ([7, 5], 2.), ([7, 3], 2.)]))
+            .expect("Creases should be on the cube");
+        assert_eq!(creased.mesh().positions()[7], Point::new(1., 1., 1.));
+        assert!(creased.mesh().positions().contains(&Point::new(0., 1., 1.)));
+
+        // A single quad keeps its corners and boundaries
+        let quad = SubdivisionSurface::new(
+            vec![
+                Point::new(0., 0., 0.),
+                Point::new(1., 0., 0.),
+                Point::new(1., 1., 0.),
+                Point::new(0., 1., 0.),
+            ],
+            vec![vec![0, 1, 2, 3]],
+            2,
+        )
+        .expect("Quad should be a valid cage");
+        assert_eq!(quad.mesh().positions()[2], Point::new(1., 1., 0.));
+        assert_eq!(quad.bounding_box(), quad.mesh().bounding_box());
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use crate::{vector::Colour, Image};
+
+use super::HitRecord;
+
+#[derive(Debug, Clone)]
+/// Colour that changes over a surface.
+pub enum Texture {
+    /// Same colour everywhere.
+    Solid(Colour),
+    /// Colours of the vertices of a mesh, interpolated across its triangles.</br>
+    /// Surfaces without vertex colours are white.
+    VertexColour,
+    /// Linear colours of an Image, placed over the surface by its UVs and repeated outside of 0 to 1.</br>
+    /// V grows from the bottom row of the Image to the top, and the pixels are blended bilinearly.
+    Image(Arc<Image>),
+}
+
+impl Texture {
+    /// Colour of the texture at the point of `hit_record`.
+    pub fn value(&self, hit_record: &HitRecord) -> Colour {
+        self.value_at(hit_record.uv, hit_record.vertex_colour)
+    }
+
+    /// Colour of the texture at `uv`, on a surface with `vertex_colour` there.
+    pub(super) fn value_at(&self, uv: (f64, f64), vertex_colour: Option<Colour>) -> Colour {
+        match self {
+            Self::Solid(colour) => *colour,
+            Self::VertexColour => vertex_colour.unwrap_or(Colour::new(1., 1., 1.)),
+            Self::Image(image) => Self::image_value(image, uv),
+        }
+    }
+
+    fn image_value(image: &Image, (u, v): (f64, f64)) -> Colour {
+        let (width, height) = image.get_dimensions();
+        if width == 0 || height == 0 {
+            return Colour::new(1., 1., 1.);
+        }
+        // Pixel centers are at half coordinates
+        let x = u.rem_euclid(1.) * width as f64 - 0.5;
+        let y = (1. - v.rem_euclid(1.)) * height as f64 - 0.5;
+        let (x_weight, y_weight) = (x - x.floor(), y - y.floor());
+        let column = |offset: f64| (x.floor() + offset).rem_euclid(width as f64) as usize;
+        let row = |offset: f64| (y.floor() + offset).rem_euclid(height as f64) as usize;
+        let pixel = |column: usize, row: usize| image.get_pixels()[row * width + column];
+        let top =
+            pixel(column(0.), row(0.)) * (1. - x_weight) + pixel(column(1.), row(0.)) * x_weight;
+        let bottom =
+            pixel(column(0.), row(1.)) * (1. - x_weight) + pixel(column(1.), row(1.)) * x_weight;
+        top * (1. - y_weight) + bottom * y_weight
+    }
+}
+
+impl From<Colour> for Texture {
+    fn from(colour: Colour) -> Self {
+        Self::Solid(colour)
+    }
+}
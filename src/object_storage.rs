@@ -8,8 +8,10 @@ use crate::{
 pub trait ObjectStorage: IntoIterator<Item = Object> {
     fn add_object(&mut self, object: Object);
     fn clear(&mut self);
+    /// Objects in the storage, in the order they were added.
+    fn objects(&self) -> &[Object];
     fn find_intersection<'a>(
-        &self,
+        &'a self,
         ray: &'a Ray,
         ray_length_min_max: RangeInclusive<f64>,
     ) -> Option<HitRecord<'a>>;
@@ -24,8 +26,12 @@ impl ObjectStorage for Vec<Object> {
         self.clear();
     }
 
+    fn objects(&self) -> &[Object] {
+        self
+    }
+
     fn find_intersection<'a>(
-        &self,
+        &'a self,
         ray: &'a Ray,
         ray_length_min_max: RangeInclusive<f64>,
     ) -> Option<HitRecord<'a>> {
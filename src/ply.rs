@@ -0,0 +1,408 @@
+use crate::{
+    object::{MeshError, TriangleMesh},
+    vector::{Colour, Direction, Point},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn new(name: &str) -> Result<Self, MeshError> {
+        match name {
+            "char" | "int8" => Ok(Self::I8),
+            "uchar" | "uint8" => Ok(Self::U8),
+            "short" | "int16" => Ok(Self::I16),
+            "ushort" | "uint16" => Ok(Self::U16),
+            "int" | "int32" => Ok(Self::I32),
+            "uint" | "uint32" => Ok(Self::U32),
+            "float" | "float32" => Ok(Self::F32),
+            "double" | "float64" => Ok(Self::F64),
+            _ => Err(MeshError::Malformed("unknown property type")),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Largest value of integer types, which colours are divided by, 1 for floating point types.
+    fn colour_scale(self) -> f64 {
+        match self {
+            Self::U8 | Self::I8 => 255.,
+            Self::U16 | Self::I16 => 65535.,
+            Self::U32 | Self::I32 => f64::from(u32::MAX),
+            Self::F32 | Self::F64 => 1.,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        kind: Scalar,
+    },
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Self::Scalar { name, .. } | Self::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Decodes a PLY mesh, in ASCII or binary form, into a TriangleMesh.</br>
+/// Vertices can have normals (`nx`, `ny`, `nz`), surface coordinates (`u`, `v` or `s`, `t`), and colours
+/// (`red`, `green`, `blue`). Faces with more than three vertices are split into triangles around their first
+/// vertex. Other elements and properties are skipped.
+/// # Note
+/// Colours are stored in gamma space, so they are converted to linear colours.
+/// # Errors
+/// Fails if `bytes` is not a PLY mesh, or if a face uses a missing vertex.
+pub fn ply_to_mesh(bytes: &[u8]) -> Result<TriangleMesh, MeshError> {
+    let (encoding, elements, body) = read_header(bytes)?;
+    let mut reader = BodyReader {
+        bytes: body,
+        position: 0,
+        encoding,
+    };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colours = Vec::new();
+    let mut triangles = Vec::new();
+    for element in &elements {
+        let find = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|property| names.contains(&property.name()))
+        };
+        let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+        let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+        let uv = [
+            find(&["u", "s", "texture_u", "texture_s"]),
+            find(&["v", "t", "texture_v", "texture_t"]),
+        ];
+        let colour = [find(&["red"]), find(&["green"]), find(&["blue"])];
+        let indices = find(&["vertex_indices", "vertex_index"]);
+
+        for _ in 0..element.count {
+            let values = element
+                .properties
+                .iter()
+                .map(|property| reader.property(property))
+                .collect::<Result<Vec<_>, _>>()?;
+            let scalar = |index: Option<usize>| match index.map(|index| &values[index]) {
+                Some(Value::Scalar(value)) => Some(*value),
+                _ => None,
+            };
+            match element.name.as_str() {
+                "vertex" => {
+                    let [x, y, z] = position.map(scalar);
+                    positions.push(Point::new(
+                        x.ok_or(MeshError::Malformed("vertex without a position"))?,
+                        y.ok_or(MeshError::Malformed("vertex without a position"))?,
+                        z.ok_or(MeshError::Malformed("vertex without a position"))?,
+                    ));
+                    if let [Some(x), Some(y), Some(z)] = normal.map(scalar) {
+                        normals.push(Direction::new(x, y, z));
+                    }
+                    if let [Some(u), Some(v)] = uv.map(scalar) {
+                        uvs.push((u, v));
+                    }
+                    if let [Some(red), Some(green), Some(blue)] = colour.map(scalar) {
+                        let scale = match &element.properties[colour[0].unwrap_or_default()] {
+                            Property::Scalar { kind, .. } => kind.colour_scale(),
+                            Property::List { .. } => 1.,
+                        };
+                        colours.push(
+                            Colour::new(red / scale, green / scale, blue / scale).gamma_to_linear(),
+                        );
+                    }
+                }
+                "face" => {
+                    let Some(Value::List(face)) = indices.map(|index| &values[index]) else {
+                        return Err(MeshError::Malformed("face without vertex indices"));
+                    };
+                    if face.iter().any(|index| *index < 0.) {
+                        return Err(MeshError::Malformed("face uses a missing vertex"));
+                    }
+                    let face = face.iter().map(|index| *index as usize).collect::<Vec<_>>();
+                    for pair in face.windows(2).skip(1) {
+                        triangles.push([face[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut mesh = TriangleMesh::new(positions, triangles)
+        .ok_or(MeshError::Malformed("face uses a missing vertex"))?;
+    // Attributes only some of the vertices have are dropped
+    if !normals.is_empty() {
+        mesh = mesh.clone().with_normals(normals).unwrap_or(mesh);
+    }
+    if !uvs.is_empty() {
+        mesh = mesh.clone().with_uvs(uvs).unwrap_or(mesh);
+    }
+    if !colours.is_empty() {
+        mesh = mesh.clone().with_colours(colours).unwrap_or(mesh);
+    }
+    Ok(mesh)
+}
+
+/// Reads the header, returning the encoding of the body, the elements in it, and the body.
+fn read_header(bytes: &[u8]) -> Result<(Encoding, Vec<Element>, &[u8]), MeshError> {
+    const END_OF_HEADER: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END_OF_HEADER.len())
+        .position(|window| window == END_OF_HEADER)
+        .ok_or(MeshError::Malformed("missing end_header"))?;
+    let header = std::str::from_utf8(&bytes[..end])
+        .map_err(|_| MeshError::Malformed("header is not text"))?;
+    // The body starts after the line break that ends the header
+    let body_start = bytes[end..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .map_or(bytes.len(), |line_break| end + line_break + 1);
+
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(MeshError::Unsupported("not a PLY mesh"));
+    }
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            ["format", format, _version] => {
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::LittleEndian,
+                    "binary_big_endian" => Encoding::BigEndian,
+                    _ => return Err(MeshError::Unsupported("unknown PLY format")),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: (*name).to_owned(),
+                count: count
+                    .parse()
+                    .map_err(|_| MeshError::Malformed("element count is not a number"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .ok_or(MeshError::Malformed("property before any element"))?
+                .properties
+                .push(Property::List {
+                    name: (*name).to_owned(),
+                    count: Scalar::new(count)?,
+                    item: Scalar::new(item)?,
+                }),
+            ["property", kind, name] => elements
+                .last_mut()
+                .ok_or(MeshError::Malformed("property before any element"))?
+                .properties
+                .push(Property::Scalar {
+                    name: (*name).to_owned(),
+                    kind: Scalar::new(kind)?,
+                }),
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            _ => return Err(MeshError::Malformed("unknown header line")),
+        }
+    }
+    let encoding = encoding.ok_or(MeshError::Malformed("missing format"))?;
+    Ok((encoding, elements, &bytes[body_start..]))
+}
+
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+struct BodyReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    encoding: Encoding,
+}
+
+impl BodyReader<'_> {
+    fn property(&mut self, property: &Property) -> Result<Value, MeshError> {
+        match property {
+            Property::Scalar { kind, .. } => self.scalar(*kind).map(Value::Scalar),
+            Property::List { count, item, .. } => {
+                let count = self.scalar(*count)?;
+                if !(0. ..=f64::from(u32::MAX)).contains(&count) {
+                    return Err(MeshError::Malformed("list length out of range"));
+                }
+                (0..count as usize)
+                    .map(|_| self.scalar(*item))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List)
+            }
+        }
+    }
+
+    fn scalar(&mut self, kind: Scalar) -> Result<f64, MeshError> {
+        if self.encoding == Encoding::Ascii {
+            while self
+                .bytes
+                .get(self.position)
+                .is_some_and(u8::is_ascii_whitespace)
+            {
+                self.position += 1;
+            }
+            let start = self.position;
+            while self
+                .bytes
+                .get(self.position)
+                .is_some_and(|byte| !byte.is_ascii_whitespace())
+            {
+                self.position += 1;
+            }
+            return std::str::from_utf8(&self.bytes[start..self.position])
+                .ok()
+                .and_then(|token| token.parse().ok())
+                .ok_or(MeshError::Malformed("expected a number"));
+        }
+
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + kind.size())
+            .ok_or(MeshError::Malformed("unexpected end of mesh"))?;
+        self.position += kind.size();
+        let mut buffer = [0; 8];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        if self.encoding == Encoding::BigEndian {
+            buffer[..bytes.len()].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = buffer;
+        Ok(match kind {
+            Scalar::I8 => f64::from(b0 as i8),
+            Scalar::U8 => f64::from(b0),
+            Scalar::I16 => f64::from(i16::from_le_bytes([b0, b1])),
+            Scalar::U16 => f64::from(u16::from_le_bytes([b0, b1])),
+            Scalar::I32 => f64::from(i32::from_le_bytes([b0, b1, b2, b3])),
+            Scalar::U32 => f64::from(u32::from_le_bytes([b0, b1, b2, b3])),
+            Scalar::F32 => f64::from(f32::from_le_bytes([b0, b1, b2, b3])),
+            Scalar::F64 => f64::from_le_bytes(buffer),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ply_to_mesh_test() {
+        let ply = b"ply
+format ascii 1.0
+comment unit square with a red and a white corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 255 255 255
+1 1 0 255 255 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+        let mesh = ply_to_mesh(ply).expect("Valid PLY");
+        assert_eq!(mesh.positions().len(), 4);
+        assert_eq!(mesh.triangles(), &[[0, 1, 2], [0, 2, 3]]);
+        assert!(mesh.normals().is_none());
+        assert_eq!(
+            mesh.colours().map(|colours| colours[0]),
+            Some(Colour::new(1., 0., 0.))
+        );
+
+        // Same square in binary little endian, with the colours as `uchar`
+        let mut binary = b"ply
+format binary_little_endian 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+"
+        .to_vec();
+        for (position, colour) in [
+            ([0_f32, 0., 0.], [255_u8, 0, 0]),
+            ([1., 0., 0.], [255, 255, 255]),
+            ([1., 1., 0.], [255, 255, 255]),
+            ([0., 1., 0.], [255, 255, 255]),
+        ] {
+            binary.extend(position.iter().flat_map(|value| value.to_le_bytes()));
+            binary.extend_from_slice(&colour);
+        }
+        binary.push(4);
+        binary.extend(
+            [0_i32, 1, 2, 3]
+                .iter()
+                .flat_map(|index| index.to_le_bytes()),
+        );
+        let binary_mesh = ply_to_mesh(&binary).expect("Valid binary PLY");
+        assert_eq!(binary_mesh.positions(), mesh.positions());
+        assert_eq!(binary_mesh.triangles(), mesh.triangles());
+        assert_eq!(binary_mesh.colours(), mesh.colours());
+        assert!(matches!(
+            ply_to_mesh(&binary[..binary.len() - 1]),
+            Err(MeshError::Malformed(_))
+        ));
+
+        assert!(matches!(
+            ply_to_mesh(b"ply\nformat ascii 1.0\nelement face 1\nproperty list uchar int vertex_index\nend_header\n3 0 1 2\n"),
+            Err(MeshError::Malformed(_))
+        ));
+    }
+}
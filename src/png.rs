@@ -0,0 +1,498 @@
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::{vector::Colour, Image, ImageError};
+
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// Start and spacing of the columns and rows of each pass of Adam7 interlacing.
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+fn format_pixel(pixel: &Colour) -> [u8; 3] {
+    [
+        (pixel[0] * 256.).clamp(0., 255.).round() as u8,
+        (pixel[1] * 256.).clamp(0., 255.).round() as u8,
+        (pixel[2] * 256.).clamp(0., 255.).round() as u8,
+    ]
+}
+
+/// Encodes `image` as an 8-bit RGB PNG.
+#[must_use]
+pub fn image_to_png(image: &crate::Image) -> Box<[u8]> {
+    let (width, height) = image.get_dimensions();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    // Each row starts with its filter type, 0 is no filter
+    let scanlines = image
+        .get_pixels()
+        .chunks(width.max(1))
+        .flat_map(|row| std::iter::once(0).chain(row.iter().flat_map(format_pixel)))
+        .collect::<Vec<_>>();
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(
+        &mut png,
+        b"IDAT",
+        &miniz_oxide::deflate::compress_to_vec_zlib(&scanlines, 6),
+    );
+    write_chunk(&mut png, b"IEND", &[]);
+    png.into_boxed_slice()
+}
+
+/// Decodes a PNG image of any color type and bit depth, interlaced or not.
+/// # Note
+/// Images keep the values stored in the file, no gamma or color profile is applied. Alpha is ignored.</br>
+/// Decompression is left to `miniz_oxide`. The chunks, filters, and interlacing are decoded here because they map
+/// directly to Colours at any bit depth, which a general decoder would first expand into a byte buffer.
+/// # Errors
+/// Fails if `bytes` is not a PNG image, or if a chunk is damaged.
+pub fn png_to_image(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut chunks = bytes
+        .strip_prefix(&SIGNATURE)
+        .ok_or(ImageError::Unsupported("not a PNG image"))?;
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut compressed = Vec::new();
+    loop {
+        let (Chunk { kind, data }, rest) = read_chunk(chunks)?;
+        chunks = rest;
+        match &kind {
+            b"IHDR" => header = Some(PngHeader::read(data)?),
+            b"PLTE" => {
+                palette = data
+                    .chunks_exact(3)
+                    .map(|rgb| {
+                        let channel = |index: usize| f64::from(rgb[index]) / 255.;
+                        Colour::new(channel(0), channel(1), channel(2))
+                    })
+                    .collect();
+            }
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            // Chunks with a lowercase first letter can be skipped, but not the others
+            _ if kind[0].is_ascii_lowercase() => (),
+            _ => return Err(ImageError::Unsupported("unknown critical chunk")),
+        }
+    }
+    let header = header.ok_or(ImageError::Malformed("missing IHDR chunk"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(ImageError::Malformed("missing PLTE chunk"));
+    }
+
+    // Decompression stops as soon as the data is larger than the header allows, and the size in the header is
+    // only trusted once the data can fill it
+    let size = header
+        .data_size()
+        .ok_or(ImageError::Malformed("missing image data"))?;
+    let data = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, size).map_err(
+        |err| match err.status {
+            TINFLStatus::HasMoreOutput => {
+                ImageError::Malformed("image data is larger than its header")
+            }
+            _ => ImageError::Malformed("invalid zlib stream"),
+        },
+    )?;
+    if data.len() < size {
+        return Err(ImageError::Malformed("missing image data"));
+    }
+    let mut image = Image::new(header.width, header.height);
+    let mut data = data.as_slice();
+    for (column, row, column_step, row_step, width, height) in header.passes() {
+        let (pass, rest) = header.unfilter(data, width, height)?;
+        data = rest;
+        let row_size = header
+            .row_size(width)
+            .ok_or(ImageError::Malformed("image is too large"))?;
+        for (y, scanline) in pass.chunks_exact(row_size).enumerate() {
+            for x in 0..width {
+                let index = (row + y * row_step) * header.width + column + x * column_step;
+                image.get_pixels_mut()[index] = header.pixel(scanline, x, &palette)?;
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// Fields of the IHDR chunk.
+struct PngHeader {
+    width: usize,
+    height: usize,
+    bit_depth: usize,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl PngHeader {
+    fn read(data: &[u8]) -> Result<Self, ImageError> {
+        let [w0, w1, w2, w3, h0, h1, h2, h3, bit_depth, color_type, compression, filter, interlace] =
+            *data
+        else {
+            return Err(ImageError::Malformed("IHDR chunk has the wrong size"));
+        };
+        let valid_depths: &[u8] = match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(ImageError::Malformed("unknown color type")),
+        };
+        if !valid_depths.contains(&bit_depth) {
+            return Err(ImageError::Malformed(
+                "invalid bit depth for the color type",
+            ));
+        }
+        if compression != 0 || filter != 0 || interlace > 1 {
+            return Err(ImageError::Unsupported(
+                "unknown compression, filter, or interlace method",
+            ));
+        }
+        Ok(Self {
+            width: u32::from_be_bytes([w0, w1, w2, w3]) as usize,
+            height: u32::from_be_bytes([h0, h1, h2, h3]) as usize,
+            bit_depth: usize::from(bit_depth),
+            color_type,
+            interlaced: interlace == 1,
+        })
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Bytes in a scanline of `width` pixels, without the filter type, `None` if it overflows.
+    fn row_size(&self, width: usize) -> Option<usize> {
+        width
+            .checked_mul(self.channels() * self.bit_depth)
+            .map(|bits| bits.div_ceil(8))
+    }
+
+    /// Start and spacing of the columns and rows of each pass that has pixels, followed by its width and
+    /// height. Images that are not interlaced have a single pass.
+    fn passes(&self) -> impl Iterator<Item = (usize, usize, usize, usize, usize, usize)> + '_ {
+        let passes = if self.interlaced {
+            ADAM7_PASSES.as_slice()
+        } else {
+            &[(0, 0, 1, 1)]
+        };
+        passes
+            .iter()
+            .map(|&(column, row, column_step, row_step)| {
+                let width = self.width.saturating_sub(column).div_ceil(column_step);
+                let height = self.height.saturating_sub(row).div_ceil(row_step);
+                (column, row, column_step, row_step, width, height)
+            })
+            .filter(|pass| pass.4 != 0 && pass.5 != 0)
+    }
+
+    /// Bytes of filtered scanlines in all passes, `None` if it overflows.
+    fn data_size(&self) -> Option<usize> {
+        self.passes().try_fold(0_usize, |total, pass| {
+            self.row_size(pass.4)?
+                .checked_add(1)?
+                .checked_mul(pass.5)?
+                .checked_add(total)
+        })
+    }
+
+    /// Removes the filters of the scanlines of a `width` by `height` image from the start of `data`, returning
+    /// them and the rest of `data`.
+    fn unfilter<'a>(
+        &self,
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+    ) -> Result<(Vec<u8>, &'a [u8]), ImageError> {
+        let row_size = self
+            .row_size(width)
+            .ok_or(ImageError::Malformed("image is too large"))?;
+        // Filters work on whole pixels, or on bytes when pixels are smaller than a byte
+        let pixel_size = (self.channels() * self.bit_depth).div_ceil(8);
+        let filtered_size = (row_size + 1)
+            .checked_mul(height)
+            .filter(|size| *size <= data.len())
+            .ok_or(ImageError::Malformed("missing image data"))?;
+        let (filtered, rest) = data.split_at(filtered_size);
+
+        let mut rows = vec![0; row_size * height];
+        for (y, scanline) in filtered.chunks_exact(row_size + 1).enumerate() {
+            let (previous, current) = rows.split_at_mut(y * row_size);
+            let above = previous.get(previous.len().saturating_sub(row_size)..);
+            let above = above.filter(|_| y > 0);
+            let current = &mut current[..row_size];
+            for x in 0..row_size {
+                let a = if x >= pixel_size {
+                    current[x - pixel_size]
+                } else {
+                    0
+                };
+                let b = above.map_or(0, |above| above[x]);
+                let c = match above {
+                    Some(above) if x >= pixel_size => above[x - pixel_size],
+                    _ => 0,
+                };
+                let predictor = match scanline[0] {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+                    4 => paeth(a, b, c),
+                    _ => return Err(ImageError::Malformed("unknown filter type")),
+                };
+                current[x] = scanline[x + 1].wrapping_add(predictor);
+            }
+        }
+        Ok((rows, rest))
+    }
+
+    /// Colour of pixel `x` of an unfiltered scanline.
+    fn pixel(&self, scanline: &[u8], x: usize, palette: &[Colour]) -> Result<Colour, ImageError> {
+        let channels = self.channels();
+        let sample = |channel: usize| {
+            let bit = (x * channels + channel) * self.bit_depth;
+            match self.bit_depth {
+                16 => usize::from(u16::from_be_bytes([
+                    scanline[bit / 8],
+                    scanline[bit / 8 + 1],
+                ])),
+                8 => usize::from(scanline[bit / 8]),
+                // Samples smaller than a byte are packed from the highest bit
+                depth => {
+                    usize::from(scanline[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1)
+                }
+            }
+        };
+        let maximum = ((1 << self.bit_depth) - 1) as f64;
+        let value = |channel: usize| sample(channel) as f64 / maximum;
+        match self.color_type {
+            0 | 4 => Ok(Colour::new(value(0), value(0), value(0))),
+            2 | 6 => Ok(Colour::new(value(0), value(1), value(2))),
+            _ => palette
+                .get(sample(0))
+                .copied()
+                .ok_or(ImageError::Malformed("palette index out of range")),
+        }
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let estimate = i16::from(a) + i16::from(b) - i16::from(c);
+    let (distance_a, distance_b, distance_c) = (
+        (estimate - i16::from(a)).abs(),
+        (estimate - i16::from(b)).abs(),
+        (estimate - i16::from(c)).abs(),
+    );
+    if distance_a <= distance_b && distance_a <= distance_c {
+        a
+    } else if distance_b <= distance_c {
+        b
+    } else {
+        c
+    }
+}
+
+struct Chunk<'a> {
+    kind: [u8; 4],
+    data: &'a [u8],
+}
+
+/// Splits the first chunk from `bytes`, returning it and the remaining bytes.
+fn read_chunk(bytes: &[u8]) -> Result<(Chunk<'_>, &[u8]), ImageError> {
+    let end = bytes
+        .get(..4)
+        .and_then(|length| {
+            (u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize)
+                .checked_add(12)
+        })
+        .filter(|end| *end <= bytes.len())
+        .ok_or(ImageError::Malformed("truncated chunk"))?;
+    let crc = u32::from_be_bytes([
+        bytes[end - 4],
+        bytes[end - 3],
+        bytes[end - 2],
+        bytes[end - 1],
+    ]);
+    if crc32(&bytes[4..end - 4]) != crc {
+        return Err(ImageError::Malformed("chunk CRC mismatch"));
+    }
+    let chunk = Chunk {
+        kind: [bytes[4], bytes[5], bytes[6], bytes[7]],
+        data: &bytes[8..end - 4],
+    };
+    Ok((chunk, &bytes[end..]))
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let crc_start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[crc_start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PNG with the `header` fields and the `scanlines`, after the `extra` chunks.
+    fn png(header: &[u8; 13], extra: &[(&[u8; 4], &[u8])], scanlines: &[u8]) -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", header);
+        for (kind, data) in extra {
+            write_chunk(&mut png, kind, data);
+        }
+        write_chunk(
+            &mut png,
+            b"IDAT",
+            &miniz_oxide::deflate::compress_to_vec_zlib(scanlines, 6),
+        );
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn png_to_image_test() {
+        let byte = |value: u8| f64::from(value) / 255.;
+
+        let mut image = Image::new(3, 2);
+        for (index, pixel) in image.get_pixels_mut().iter_mut().enumerate() {
+            let value = 40 * index as u8;
+            *pixel = Colour::new(f64::from(value) / 256., 0., f64::from(255 - value) / 256.);
+        }
+        let decoded = png_to_image(&image_to_png(&image)).expect("Expect own PNG decoded");
+        assert_eq!(decoded.get_dimensions(), (3, 2));
+        for (index, pixel) in decoded.get_pixels().iter().enumerate() {
+            let value = 40 * index as u8;
+            assert_eq!(*pixel, Colour::new(byte(value), 0., byte(255 - value)));
+        }
+
+        // 2 by 2 RGB, rows filtered with Sub and Up, compressed with fixed Huffman codes
+        let fixed = [
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00,
+            0x00, 0xFD, 0xD4, 0x9A, 0x73, 0x00, 0x00, 0x00, 0x16, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x01, 0x63, 0xFC, 0xCF, 0xC0, 0xC0, 0xF8, 0x9F, 0x81, 0x89, 0x91, 0xE1, 0xFF, 0x7F,
+            0x86, 0xFF, 0x00, 0x1E, 0x1C, 0x05, 0x01, 0xEA, 0xC0, 0x0A, 0x1B, 0x00, 0x00, 0x00,
+            0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+        ];
+        let decoded = png_to_image(&fixed).expect("Expect fixed Huffman PNG decoded");
+        assert_eq!(
+            decoded.get_pixels(),
+            [
+                Colour::new(1., 0., 0.),
+                Colour::new(0., 1., 0.),
+                Colour::new(0., 0., 1.),
+                Colour::new(1., 1., 1.),
+            ]
+        );
+
+        // 3 by 3 gray with Adam7 interlacing, where pixel `i` has the value `10 * i`
+        let adam7 = [
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x03, 0x08, 0x00, 0x00, 0x00,
+            0x01, 0x04, 0x44, 0xDA, 0xF5, 0x00, 0x00, 0x00, 0x17, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0xDA, 0x63, 0x60, 0x60, 0x10, 0x61, 0xB0, 0x09, 0x60, 0xE0, 0x62, 0x70, 0x63, 0x90,
+            0xD3, 0x30, 0x02, 0x00, 0x08, 0xA7, 0x01, 0x69, 0x85, 0x60, 0xEE, 0x25, 0x00, 0x00,
+            0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+        ];
+        let decoded = png_to_image(&adam7).expect("Expect Adam7 PNG decoded");
+        for (index, pixel) in decoded.get_pixels().iter().enumerate() {
+            let value = byte(10 * index as u8);
+            assert_eq!(*pixel, Colour::new(value, value, value));
+        }
+
+        // 3 by 2 with 2 bit palette indices, 0, 1, 2 then 3, 3, 0
+        let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let indexed = png(
+            &[0, 0, 0, 3, 0, 0, 0, 2, 2, 3, 0, 0, 0],
+            &[(b"PLTE", &palette)],
+            &[0, 0b0001_1000, 0, 0b1111_0000],
+        );
+        let decoded = png_to_image(&indexed).expect("Expect palette PNG decoded");
+        let (red, white) = (Colour::new(1., 0., 0.), Colour::new(1., 1., 1.));
+        assert_eq!(
+            decoded.get_pixels(),
+            [
+                red,
+                Colour::new(0., 1., 0.),
+                Colour::new(0., 0., 1.),
+                white,
+                white,
+                red
+            ]
+        );
+
+        // 2 by 1 gray with alpha at 16 bits, filtered with Paeth, which is Sub on the first row
+        let deep = png(
+            &[0, 0, 0, 2, 0, 0, 0, 1, 16, 4, 0, 0, 0],
+            &[],
+            &[4, 0x80, 0x00, 0xFF, 0xFF, 0x7F, 0xFF, 0x01, 0x01],
+        );
+        let decoded = png_to_image(&deep).expect("Expect 16 bit PNG decoded");
+        let gray = f64::from(0x8000) / 65535.;
+        assert_eq!(
+            decoded.get_pixels(),
+            [Colour::new(gray, gray, gray), Colour::new(1., 1., 1.)]
+        );
+
+        assert!(matches!(
+            png_to_image(&fixed[..fixed.len() - 5]),
+            Err(ImageError::Malformed("truncated chunk"))
+        ));
+        let mut damaged = fixed;
+        damaged[45] ^= 0xFF;
+        assert!(matches!(
+            png_to_image(&damaged),
+            Err(ImageError::Malformed("chunk CRC mismatch"))
+        ));
+        // The header asks for 0xFFFFFFFF by 0xFFFFFFFF pixels, which is rejected before allocating
+        let oversized = png(
+            &[
+                0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 8, 2, 0, 0, 0,
+            ],
+            &[],
+            &[0; 4],
+        );
+        assert!(matches!(
+            png_to_image(&oversized),
+            Err(ImageError::Malformed("missing image data"))
+        ));
+        // A 1 by 1 image with a megabyte of data stops inflating once it passes the 2 bytes of the image
+        let bomb = png(&[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0], &[], &[0; 1 << 20]);
+        assert!(matches!(
+            png_to_image(&bomb),
+            Err(ImageError::Malformed(
+                "image data is larger than its header"
+            ))
+        ));
+    }
+}
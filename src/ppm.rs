@@ -1,4 +1,4 @@
-use crate::vector::Colour;
+use crate::{vector::Colour, Image, ImageError};
 
 fn format_pixel(pixel: &Colour) -> String {
     format!(
@@ -22,6 +22,108 @@ pub fn image_to_ppm(image: &crate::Image) -> Box<str> {
     format!("{header}\n{body}").into_boxed_str()
 }
 
+/// Decodes a PPM or PGM image, in either the plain (`P3`, `P2`) or the binary (`P6`, `P5`) form.</br>
+/// Each channel is divided by the maximum value of the image, gray images have the same value on every channel.
+/// # Errors
+/// Fails if `bytes` is not a PPM or PGM image.
+pub fn ppm_to_image(bytes: &[u8]) -> Result<Image, ImageError> {
+    let mut header = Header { bytes, position: 0 };
+    let (channels, plain) = match header.token()? {
+        b"P2" => (1, true),
+        b"P3" => (3, true),
+        b"P5" => (1, false),
+        b"P6" => (3, false),
+        _ => return Err(ImageError::Unsupported("not a PPM or PGM image")),
+    };
+    let width = header.number()?;
+    let height = header.number()?;
+    let maximum = header.number()?;
+    if !(1..=65535).contains(&maximum) {
+        return Err(ImageError::Malformed("maximum value out of range"));
+    }
+    let samples = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or(ImageError::Malformed("image too large"))?;
+
+    let values = if plain {
+        (0..samples)
+            .map(|_| header.number())
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        // A single whitespace separates the header from the samples
+        let data = bytes
+            .get(header.position + 1..)
+            .ok_or(ImageError::Malformed("missing samples"))?;
+        let sample_size = if maximum < 256 { 1 } else { 2 };
+        if data.len() < samples * sample_size {
+            return Err(ImageError::Malformed("missing samples"));
+        }
+        data.chunks_exact(sample_size)
+            .take(samples)
+            .map(|sample| {
+                sample
+                    .iter()
+                    .fold(0, |value, byte| value << 8 | usize::from(*byte))
+            })
+            .collect()
+    };
+
+    let mut image = Image::new(width, height);
+    for (pixel, sample) in image
+        .get_pixels_mut()
+        .iter_mut()
+        .zip(values.chunks_exact(channels))
+    {
+        let channel = |index: usize| sample[index % channels] as f64 / maximum as f64;
+        *pixel = Colour::new(channel(0), channel(1), channel(2));
+    }
+    Ok(image)
+}
+
+/// Whitespace separated tokens of the header of a PPM image, which can have comments.
+struct Header<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Header<'a> {
+    fn token(&mut self) -> Result<&'a [u8], ImageError> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(b'#') => {
+                    while self
+                        .bytes
+                        .get(self.position)
+                        .is_some_and(|byte| *byte != b'\n')
+                    {
+                        self.position += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(ImageError::Malformed("unexpected end of image")),
+            }
+        }
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#')
+        {
+            self.position += 1;
+        }
+        Ok(&self.bytes[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<usize, ImageError> {
+        std::str::from_utf8(self.token()?)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or(ImageError::Malformed("expected a number"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -39,4 +141,30 @@ mod tests {
         let ppm = image_to_ppm(&image);
         std::fs::write("gradient.ppm", ppm.as_bytes()).expect("Expect contents written to file");
     }
+
+    #[test]
+    fn ppm_to_image_test() {
+        let gray = ppm_to_image(b"P2\n# plain gray\n3 1\n4\n0 2\n4\n").expect("Expect PGM decoded");
+        assert_eq!(gray.get_dimensions(), (3, 1));
+        assert_eq!(
+            gray.get_pixels(),
+            [
+                Colour::new(0., 0., 0.),
+                Colour::new(0.5, 0.5, 0.5),
+                Colour::new(1., 1., 1.)
+            ]
+        );
+
+        let mut binary = b"P6 2 1 255\n".to_vec();
+        binary.extend_from_slice(&[255, 0, 51, 0, 255, 102]);
+        let colour = ppm_to_image(&binary).expect("Expect binary PPM decoded");
+        assert_eq!(
+            colour.get_pixels(),
+            [Colour::new(1., 0., 0.2), Colour::new(0., 1., 0.4)]
+        );
+        assert!(matches!(
+            ppm_to_image(&binary[..binary.len() - 1]),
+            Err(ImageError::Malformed("missing samples"))
+        ));
+    }
 }
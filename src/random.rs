@@ -0,0 +1,36 @@
+use std::cell::RefCell;
+
+use rand::{
+    distributions::{Distribution, Standard},
+    rngs::SmallRng,
+    Rng, SeedableRng,
+};
+
+thread_local! {
+    static GENERATOR: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Creates a random value from the generator of the current thread.
+/// Floating point values are between [0.0..1.0).
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    GENERATOR.with(|generator| generator.borrow_mut().gen())
+}
+
+/// Restarts the generator of the current thread, so the values that follow are always the same for a `seed`.
+pub fn seed(seed: u64) {
+    GENERATOR.with(|generator| *generator.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Combines `values` into a single seed, so nearby values give unrelated seeds.
+pub fn mix_seed(values: &[u64]) -> u64 {
+    values.iter().fold(0x9E37_79B9_7F4A_7C15, |seed, value| {
+        // SplitMix64 finalizer
+        let mut z = (seed ^ value).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    })
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::{
+    object::{MeshError, TriangleMesh},
+    vector::Point,
+};
+
+/// Size of the header of a binary STL mesh, before the number of triangles.
+const BINARY_HEADER_SIZE: usize = 80;
+/// Size of each triangle of a binary STL mesh, a normal, three corners, and an attribute.
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+/// Decodes an STL mesh, in ASCII or binary form, into a TriangleMesh.</br>
+/// Corners at the same position are merged into one vertex.
+/// # Note
+/// The normals stored in the file are ignored, the triangles face the side where their corners go
+/// counter-clockwise, which is what the format requires.
+/// # Errors
+/// Fails if `bytes` is not an STL mesh.
+pub fn stl_to_mesh(bytes: &[u8]) -> Result<TriangleMesh, MeshError> {
+    // Binary meshes can also start with `solid`, so the size is checked first
+    let binary_count = bytes
+        .get(BINARY_HEADER_SIZE..BINARY_HEADER_SIZE + 4)
+        .map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize);
+    let corners = match binary_count {
+        Some(count) if bytes.len() == BINARY_HEADER_SIZE + 4 + count * BINARY_TRIANGLE_SIZE => {
+            binary_corners(&bytes[BINARY_HEADER_SIZE + 4..])
+        }
+        _ if bytes.trim_ascii_start().starts_with(b"solid") => ascii_corners(bytes)?,
+        _ => return Err(MeshError::Unsupported("not an STL mesh")),
+    };
+    if corners.len() % 3 != 0 {
+        return Err(MeshError::Malformed("facet without three vertices"));
+    }
+
+    let mut positions = Vec::new();
+    let mut indices = HashMap::new();
+    let corners = corners
+        .into_iter()
+        .map(|corner| {
+            *indices.entry(corner.map(f64::to_bits)).or_insert_with(|| {
+                positions.push(Point::new(corner[0], corner[1], corner[2]));
+                positions.len() - 1
+            })
+        })
+        .collect::<Vec<_>>();
+    let triangles = corners
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+    TriangleMesh::new(positions, triangles)
+        .ok_or(MeshError::Malformed("facet uses a missing vertex"))
+}
+
+fn binary_corners(triangles: &[u8]) -> Vec<[f64; 3]> {
+    triangles
+        .chunks_exact(BINARY_TRIANGLE_SIZE)
+        .flat_map(|triangle| {
+            // The normal comes before the corners
+            (1..4).map(move |corner| {
+                std::array::from_fn(|axis| {
+                    let start = (corner * 3 + axis) * 4;
+                    let value = &triangle[start..start + 4];
+                    f64::from(f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+                })
+            })
+        })
+        .collect()
+}
+
+fn ascii_corners(bytes: &[u8]) -> Result<Vec<[f64; 3]>, MeshError> {
+    let text = std::str::from_utf8(bytes).map_err(|_| MeshError::Malformed("mesh is not text"))?;
+    let mut words = text.split_whitespace();
+    let mut corners = Vec::new();
+    while let Some(word) = words.next() {
+        if word == "vertex" {
+            let mut coordinate = || {
+                words
+                    .next()
+                    .and_then(|word| word.parse().ok())
+                    .ok_or(MeshError::Malformed("vertex without three coordinates"))
+            };
+            corners.push([coordinate()?, coordinate()?, coordinate()?]);
+        }
+    }
+    Ok(corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stl_to_mesh_test() {
+        // Unit square split in two facets that share an edge
+        let facets = [
+            [[0_f32, 0., 0.], [1., 0., 0.], [1., 1., 0.]],
+            [[0., 0., 0.], [1., 1., 0.], [0., 1., 0.]],
+        ];
+        let square = |mesh: &TriangleMesh| {
+            // The corners of the shared edge are merged
+            assert_eq!(mesh.positions().len(), 4);
+            assert_eq!(mesh.triangles(), &[[0, 1, 2], [0, 2, 3]]);
+            assert_eq!(mesh.positions()[3], Point::new(0., 1., 0.));
+        };
+
+        let mut ascii = String::from("solid square\n");
+        for facet in facets {
+            ascii.push_str("facet normal 0 0 1\nouter loop\n");
+            for [x, y, z] in facet {
+                ascii.push_str(&format!("vertex {x} {y} {z}\n"));
+            }
+            ascii.push_str("endloop\nendfacet\n");
+        }
+        ascii.push_str("endsolid square\n");
+        square(&stl_to_mesh(ascii.as_bytes()).expect("Valid ASCII STL"));
+
+        // Binary meshes are told apart by their size, even when the header starts with `solid`
+        for header in [&b"binary square"[..], b"solid square"] {
+            let mut binary = header.to_vec();
+            binary.resize(BINARY_HEADER_SIZE, 0);
+            binary.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+            for facet in facets {
+                let normal = [0_f32, 0., 1.];
+                binary.extend(
+                    std::iter::once(normal)
+                        .chain(facet)
+                        .flatten()
+                        .flat_map(f32::to_le_bytes),
+                );
+                binary.extend_from_slice(&[0, 0]);
+            }
+            square(&stl_to_mesh(&binary).expect("Valid binary STL"));
+        }
+
+        assert!(matches!(
+            stl_to_mesh(
+                b"solid broken\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\n"
+            ),
+            Err(MeshError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{
+    description::{Describe, DescriptionError, Tokens},
+    ray::Ray,
+    vector::{Direction, Point},
+};
+
+/// Rows of a 3x4 affine matrix, the last column is the translation.
+type Matrix = [[f64; 4]; 3];
+
+const IDENTITY: Matrix = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Affine transformation from the local space of an Object to the world, stored with its inverse.
+pub struct Transform {
+    matrix: Matrix,
+    inverse: Matrix,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    #[must_use]
+    pub fn identity() -> Self {
+        Self {
+            matrix: IDENTITY,
+            inverse: IDENTITY,
+        }
+    }
+
+    /// Creates a Transform that moves points by `offset`.
+    #[must_use]
+    pub fn translation(offset: Direction) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            matrix[axis][3] = offset[axis];
+            inverse[axis][3] = -offset[axis];
+        }
+        Self { matrix, inverse }
+    }
+
+    /// Creates a Transform that scales each axis by the components of `factors`.</br>
+    /// # Note
+    /// Factors are clamped away from zero so the Transform can always be inverted.
+    #[must_use]
+    pub fn scale(factors: Direction) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            let factor = if factors[axis].abs() < f64::EPSILON {
+                f64::EPSILON.copysign(factors[axis])
+            } else {
+                factors[axis]
+            };
+            matrix[axis][axis] = factor;
+            inverse[axis][axis] = 1. / factor;
+        }
+        Self { matrix, inverse }
+    }
+
+    /// Creates a Transform that rotates by `angle` degrees counter-clockwise around `axis`, through the origin.
+    #[must_use]
+    pub fn rotation(axis: Direction, angle: f64) -> Self {
+        if axis.is_zero() {
+            return Self::identity();
+        }
+        let axis = axis.unit_vector();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (x, y, z) = (axis[0], axis[1], axis[2]);
+        let rotation = [
+            [
+                cos + x * x * (1. - cos),
+                x * y * (1. - cos) - z * sin,
+                x * z * (1. - cos) + y * sin,
+            ],
+            [
+                y * x * (1. - cos) + z * sin,
+                cos + y * y * (1. - cos),
+                y * z * (1. - cos) - x * sin,
+            ],
+            [
+                z * x * (1. - cos) - y * sin,
+                z * y * (1. - cos) + x * sin,
+                cos + z * z * (1. - cos),
+            ],
+        ];
+
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for row in 0..3 {
+            for column in 0..3 {
+                matrix[row][column] = rotation[row][column];
+                // The inverse of a rotation is its transpose
+                inverse[column][row] = rotation[row][column];
+            }
+        }
+        Self { matrix, inverse }
+    }
+
+    /// Creates a Transform that rotates by the components of `angles`, in degrees, around the X, Y, then Z axes.
+    #[must_use]
+    pub fn euler_rotation(angles: Direction) -> Self {
+        Self::rotation(Direction::new(1., 0., 0.), angles[0])
+            .then(&Self::rotation(Direction::new(0., 1., 0.), angles[1]))
+            .then(&Self::rotation(Direction::new(0., 0., 1.), angles[2]))
+    }
+
+    /// Creates a Transform that scales, then rotates with [`Transform::euler_rotation`], then translates.
+    #[must_use]
+    pub fn from_translation_rotation_scale(
+        translation: Direction,
+        rotation: Direction,
+        scale: Direction,
+    ) -> Self {
+        Self::scale(scale)
+            .then(&Self::euler_rotation(rotation))
+            .then(&Self::translation(translation))
+    }
+
+    /// Creates a Transform that applies this Transform, and then `next`.
+    #[must_use]
+    pub fn then(&self, next: &Self) -> Self {
+        Self {
+            matrix: multiply(&next.matrix, &self.matrix),
+            inverse: multiply(&self.inverse, &next.inverse),
+        }
+    }
+
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn apply_point(&self, point: Point) -> Point {
+        let [x, y, z] = apply(&self.matrix, [point[0], point[1], point[2]], 1.);
+        Point::new(x, y, z)
+    }
+
+    pub fn apply_direction(&self, direction: Direction) -> Direction {
+        let [x, y, z] = apply(&self.matrix, [direction[0], direction[1], direction[2]], 0.);
+        Direction::new(x, y, z)
+    }
+
+    /// Transforms a surface normal, which uses the transpose of the inverse to stay perpendicular to the surface.
+    /// The result is not normalized.
+    pub fn apply_normal(&self, normal: Direction) -> Direction {
+        let component = |axis: usize| {
+            (0..3)
+                .map(|row| self.inverse[row][axis] * normal[row])
+                .sum::<f64>()
+        };
+        Direction::new(component(0), component(1), component(2))
+    }
+
+    /// Transforms `ray` without normalizing its direction, so distances along the Ray are kept.
+    pub fn apply_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.apply_point(*ray.origin()),
+            self.apply_direction(*ray.direction()),
+        )
+    }
+
+    /// Rows of the matrix of the Transform, the last column is the translation.
+    pub fn get_matrix(&self) -> [[f64; 4]; 3] {
+        self.matrix
+    }
+
+    /// Creates a Transform from the rows of an affine matrix.</br>
+    /// Returns `None` if the matrix can not be inverted.
+    #[must_use]
+    pub fn from_matrix(matrix: [[f64; 4]; 3]) -> Option<Self> {
+        let m = |row: usize, column: usize| matrix[row][column];
+        let cofactor = |row: usize, column: usize| {
+            let (r0, r1) = ((row + 1) % 3, (row + 2) % 3);
+            let (c0, c1) = ((column + 1) % 3, (column + 2) % 3);
+            m(r0, c0) * m(r1, c1) - m(r0, c1) * m(r1, c0)
+        };
+        let determinant = (0..3)
+            .map(|column| m(0, column) * cofactor(0, column))
+            .sum::<f64>();
+        if !determinant.is_finite() || determinant.abs() < 1e-300 {
+            return None;
+        }
+
+        let mut inverse = IDENTITY;
+        for (row, inverse_row) in inverse.iter_mut().enumerate() {
+            for (column, value) in inverse_row.iter_mut().take(3).enumerate() {
+                *value = cofactor(column, row) / determinant;
+            }
+            inverse_row[3] = -(0..3)
+                .map(|column| inverse_row[column] * matrix[column][3])
+                .sum::<f64>();
+        }
+        Some(Self { matrix, inverse })
+    }
+}
+
+impl Describe for Transform {
+    fn describe(&self, description: &mut Vec<String>) {
+        for value in self.matrix.iter().flatten() {
+            value.describe(description);
+        }
+    }
+
+    fn read(tokens: &mut Tokens) -> Result<Self, DescriptionError> {
+        let mut matrix = IDENTITY;
+        for value in matrix.iter_mut().flatten() {
+            *value = tokens.value()?;
+        }
+        Self::from_matrix(matrix).ok_or_else(|| tokens.error("transform can not be inverted"))
+    }
+}
+
+fn multiply(lhs: &Matrix, rhs: &Matrix) -> Matrix {
+    let mut result = [[0.; 4]; 3];
+    for row in 0..3 {
+        for column in 0..4 {
+            result[row][column] = (0..3).map(|k| lhs[row][k] * rhs[k][column]).sum::<f64>()
+                + if column == 3 { lhs[row][3] } else { 0. };
+        }
+    }
+    result
+}
+
+fn apply(matrix: &Matrix, vector: [f64; 3], w: f64) -> [f64; 3] {
+    let component = |row: usize| {
+        matrix[row][0] * vector[0]
+            + matrix[row][1] * vector[1]
+            + matrix[row][2] * vector[2]
+            + matrix[row][3] * w
+    };
+    [component(0), component(1), component(2)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_inverse_test() {
+        let transform = Transform::from_translation_rotation_scale(
+            Direction::new(1., -2., 3.),
+            Direction::new(30., 45., -60.),
+            Direction::new(2., 0.5, 3.),
+        );
+        let point = Point::new(0.3, -1.2, 4.5);
+        let back = transform
+            .inverse()
+            .apply_point(transform.apply_point(point));
+        for axis in 0..3 {
+            assert!((back[axis] - point[axis]).abs() < 1e-12);
+        }
+
+        let from_matrix =
+            Transform::from_matrix(transform.get_matrix()).expect("Expect invertible matrix");
+        for row in 0..3 {
+            for column in 0..4 {
+                assert!(
+                    (from_matrix.inverse[row][column] - transform.inverse[row][column]).abs()
+                        < 1e-12
+                );
+            }
+        }
+
+        // A quarter turn around Y takes X to -Z
+        let turned = Transform::rotation(Direction::new(0., 1., 0.), 90.)
+            .apply_direction(Direction::new(1., 0., 0.));
+        assert!((turned[2] + 1.).abs() < 1e-12 && turned[0].abs() < 1e-12);
+    }
+}
@@ -223,9 +223,9 @@ impl Direction {
     #[must_use]
     pub fn new_random() -> Self {
         Self {
-            x: rand::random::<f64>() * 2. - 1.,
-            y: rand::random::<f64>() * 2. - 1.,
-            z: rand::random::<f64>() * 2. - 1.,
+            x: crate::random::random::<f64>() * 2. - 1.,
+            y: crate::random::random::<f64>() * 2. - 1.,
+            z: crate::random::random::<f64>() * 2. - 1.,
         }
     }
 
@@ -234,9 +234,9 @@ impl Direction {
     pub fn new_random_in_unit_sphere() -> Self {
         loop {
             let rand_vec = Self {
-                x: rand::random::<f64>() * 2. - 1.,
-                y: rand::random::<f64>() * 2. - 1.,
-                z: rand::random::<f64>() * 2. - 1.,
+                x: crate::random::random::<f64>() * 2. - 1.,
+                y: crate::random::random::<f64>() * 2. - 1.,
+                z: crate::random::random::<f64>() * 2. - 1.,
             };
             if rand_vec.length_squared() < 1. {
                 break rand_vec;
@@ -310,9 +310,9 @@ impl Colour {
     #[must_use]
     pub fn new_random() -> Self {
         Self {
-            x: rand::random::<f64>(),
-            y: rand::random::<f64>(),
-            z: rand::random::<f64>(),
+            x: crate::random::random::<f64>(),
+            y: crate::random::random::<f64>(),
+            z: crate::random::random::<f64>(),
         }
     }
 
@@ -323,4 +323,13 @@ impl Colour {
             z: self.z.sqrt(),
         }
     }
+
+    /// Inverse of [`Colour::linear_to_gamma`], for colours read from files.
+    pub fn gamma_to_linear(self) -> Self {
+        Self {
+            x: self.x.powi(2),
+            y: self.y.powi(2),
+            z: self.z.powi(2),
+        }
+    }
 }